@@ -0,0 +1,117 @@
+//! Benchmarks for every stage of the parser, run separately on generated corpora.
+//!
+//! Run with `cargo bench`. To compare against a previous run, save a baseline first with
+//! `cargo bench -- --save-baseline <name>` and then compare with
+//! `cargo bench -- --baseline <name>`.
+
+use std::hint::black_box;
+
+use chumsky::Parser as _;
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+use rust_norg::{metadata::parse_metadata, stage_1, stage_2, stage_3, stage_4};
+
+/// A journal with many headings, paragraphs and todo lists, similar to a multi-year daily log.
+fn journal(days: usize) -> String {
+    (0..days)
+        .map(|day| {
+            format!(
+                "* Day {day}\n  Woke up early and went for a walk, then worked on the parser for a\n  while. Wrote some notes about {{* Day {}}} and read a bit.\n** Tasks\n   - ( ) write the benchmarks\n   - (x) review pull requests\n   - (# A|< Feb {}) plan the week\n** Notes\n   #comment\n   Nothing much to add today.\n   ---\n  ===\n",
+                day.saturating_sub(1),
+                day % 28 + 1
+            )
+        })
+        .collect()
+}
+
+/// A list which is nested as deeply as the syntax allows, over and over again.
+fn nested_lists(repeats: usize) -> String {
+    (0..repeats)
+        .flat_map(|_| (1..=8).chain((1..8).rev()))
+        .map(|level| format!("{} item at level {level}\n", "-".repeat(level)))
+        .collect()
+}
+
+/// Paragraphs consisting almost entirely of inline markup, links and anchors.
+fn inline_markup(paragraphs: usize) -> String {
+    (0..paragraphs)
+        .map(|i| {
+            format!(
+                "This *is* /very/ _heavily_ -marked- up */text/* with `inline verbatim`,\n{{https://example.com/{i}}}[links], [anchors]{{* Heading {i}}} and <inline targets>.\n\n"
+            )
+        })
+        .collect()
+}
+
+/// Large verbatim blocks, e.g. code which is being tangled.
+fn verbatim(blocks: usize) -> String {
+    (0..blocks)
+        .map(|i| {
+            let body: String = (0..50)
+                .map(|line| format!("    local value_{line} = compute({i}, {line}) -- comment\n"))
+                .collect();
+            format!("@code lua\n{body}@end\n\n")
+        })
+        .collect()
+}
+
+/// A large `@document.meta` style metadata block.
+fn metadata(keys: usize) -> String {
+    (0..keys)
+        .map(|i| format!("key_{i}: value number {i}\narr_{i}: [\n  one\n  two\n  {i}\n]\n"))
+        .collect()
+}
+
+fn bench_stages(c: &mut Criterion) {
+    let corpora = [
+        ("journal", journal(200)),
+        ("nested_lists", nested_lists(100)),
+        ("inline_markup", inline_markup(200)),
+        ("verbatim", verbatim(50)),
+    ];
+
+    for (name, input) in corpora {
+        let tokens = stage_1().parse(input.as_str()).unwrap();
+        let blocks = stage_2().parse(tokens.clone()).unwrap();
+        let flat = stage_3().parse(blocks.clone()).unwrap();
+
+        let mut group = c.benchmark_group(name);
+        group.throughput(Throughput::Bytes(input.len() as u64));
+
+        group.bench_function("stage_1", |b| {
+            b.iter(|| stage_1().parse(black_box(input.as_str())).unwrap())
+        });
+        group.bench_function("stage_2", |b| {
+            b.iter_batched(
+                || tokens.clone(),
+                |tokens| stage_2().parse(tokens).unwrap(),
+                BatchSize::SmallInput,
+            )
+        });
+        group.bench_function("stage_3", |b| {
+            b.iter_batched(
+                || blocks.clone(),
+                |blocks| stage_3().parse(blocks).unwrap(),
+                BatchSize::SmallInput,
+            )
+        });
+        group.bench_function("stage_4", |b| {
+            b.iter_batched(|| flat.clone(), stage_4, BatchSize::SmallInput)
+        });
+
+        group.finish();
+    }
+}
+
+fn bench_metadata(c: &mut Criterion) {
+    let input = metadata(500);
+
+    let mut group = c.benchmark_group("metadata");
+    group.throughput(Throughput::Bytes(input.len() as u64));
+    group.bench_function("parse_metadata", |b| {
+        b.iter(|| parse_metadata(black_box(&input)).unwrap())
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_stages, bench_metadata);
+criterion_main!(benches);
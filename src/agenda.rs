@@ -0,0 +1,616 @@
+//! Collects todo items from parsed documents and arranges them into agenda views.
+//!
+//! Every heading and nestable detached modifier (list item or quote) with a todo extension is a
+//! [`Task`]. Dates are taken from the due date (`<`), start date (`>`) and timestamp (`@`)
+//! extensions and understood in the common forms `2025-01-31`, `31 Jan 2025`, `Jan 31 2025`,
+//! `Fri, 31st January 2025` and `Jan 31`. Times and timezones are ignored, and dates without a
+//! year are taken to be in the year of the agenda's reference date.
+//!
+//! Recurring items (`(+ ...)`) recur weekly when given a weekday (`+ Friday`), monthly when given
+//! a day of the month (`+ 15th`) and yearly when given a day and a month (`+ 15th May`).
+
+use std::fmt::{self, Display};
+
+use serde::{Serialize, Serializer};
+
+use crate::{
+    plain_text::{inline_to_plain_text, PlainTextOptions},
+    query::{children, todo_status_name},
+    DetachedModifierExtension, NorgAST, ParagraphSegment, TodoStatus,
+};
+
+const MONTHS: [&str; 12] = [
+    "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+];
+const WEEKDAYS: [&str; 7] = ["mon", "tue", "wed", "thu", "fri", "sat", "sun"];
+
+/// A calendar date in the proleptic Gregorian calendar.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Date {
+    pub year: i32,
+    pub month: u8,
+    pub day: u8,
+}
+
+fn is_leap_year(year: i32) -> bool {
+    (year % 4 == 0 && year % 100 != 0) || year % 400 == 0
+}
+
+fn days_in_month(year: i32, month: u8) -> u8 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+impl Date {
+    /// Creates a date, returning `None` if it does not exist.
+    pub fn new(year: i32, month: u8, day: u8) -> Option<Self> {
+        ((1..=12).contains(&month) && (1..=days_in_month(year, month)).contains(&day))
+            .then_some(Self { year, month, day })
+    }
+
+    /// The number of days since 1970-01-01.
+    fn to_days(self) -> i64 {
+        let year = i64::from(self.year) - i64::from(self.month <= 2);
+        let era = year.div_euclid(400);
+        let year_of_era = year.rem_euclid(400);
+        let month = i64::from(self.month);
+        let day_of_year =
+            (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + i64::from(self.day) - 1;
+        let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+        era * 146097 + day_of_era - 719468
+    }
+
+    fn from_days(days: i64) -> Self {
+        let days = days + 719468;
+        let era = days.div_euclid(146097);
+        let day_of_era = days.rem_euclid(146097);
+        let year_of_era =
+            (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let month = (5 * day_of_year + 2) / 153;
+        let day = day_of_year - (153 * month + 2) / 5 + 1;
+        let month = if month < 10 { month + 3 } else { month - 9 };
+
+        Self {
+            year: (year_of_era + era * 400 + i64::from(month <= 2)) as i32,
+            month: month as u8,
+            day: day as u8,
+        }
+    }
+
+    /// Returns the date `days` days after this one (or before, if negative).
+    pub fn add_days(self, days: i64) -> Self {
+        Self::from_days(self.to_days() + days)
+    }
+
+    /// The day of the week, starting with `0` for Monday.
+    pub fn weekday(self) -> u8 {
+        (self.to_days() + 3).rem_euclid(7) as u8
+    }
+
+    /// Parses a Norg timestamp, see the [module documentation](self) for the supported forms.
+    /// Dates without a year are placed in the year of `reference`.
+    pub fn parse(input: &str, reference: Date) -> Option<Self> {
+        let components = DateComponents::parse(input);
+        Self::new(
+            components.year.unwrap_or(reference.year),
+            components.month?,
+            components.day?,
+        )
+    }
+}
+
+impl Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+impl Serialize for Date {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+/// The parts of a date that were present in a timestamp.
+#[derive(Debug, Default)]
+struct DateComponents {
+    year: Option<i32>,
+    month: Option<u8>,
+    day: Option<u8>,
+    weekday: Option<u8>,
+}
+
+impl DateComponents {
+    fn parse(input: &str) -> Self {
+        let mut components = Self::default();
+
+        for word in input
+            .split(|c: char| c.is_whitespace() || c == ',')
+            .filter(|word| !word.is_empty())
+        {
+            let word = word.to_lowercase();
+            let mut iso = word.splitn(3, '-');
+
+            if let (Some(year), Some(month), Some(day)) = (iso.next(), iso.next(), iso.next()) {
+                // `2025-01-31`, possibly followed by a time.
+                let day = day.split('t').next().unwrap_or_default();
+                components.year = year.parse().ok();
+                components.month = month.parse().ok();
+                components.day = day.parse().ok();
+            } else if word.chars().all(|c| c.is_ascii_alphabetic()) && word.len() >= 3 {
+                if let Some(month) = MONTHS.iter().position(|month| word.starts_with(month)) {
+                    components.month = Some(month as u8 + 1);
+                } else if let Some(weekday) = WEEKDAYS.iter().position(|day| word.starts_with(day))
+                {
+                    components.weekday = Some(weekday as u8);
+                }
+            } else if word.starts_with(|c: char| c.is_ascii_digit()) {
+                let digits = word.trim_end_matches(|c: char| c.is_ascii_alphabetic());
+                if word.contains(':') {
+                    // A time of day.
+                } else if digits.len() == 4 {
+                    components.year = digits.parse().ok();
+                } else if digits.len() <= 2 {
+                    components.day = digits.parse().ok();
+                }
+            }
+        }
+
+        components
+    }
+}
+
+/// How often a recurring task recurs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub enum Recurrence {
+    /// On the given day of the week, starting with `0` for Monday.
+    Weekly(u8),
+    /// On the given day of the month. Months that are too short are skipped.
+    Monthly(u8),
+    /// Every year on the given month and day.
+    Yearly { month: u8, day: u8 },
+    /// Just once, for a recurring item with a full date.
+    Once(Date),
+}
+
+impl Recurrence {
+    /// Parses the timestamp of a recurring todo item, e.g. `Friday`, `15th` or `15th May`.
+    pub fn parse(input: &str) -> Option<Self> {
+        let components = DateComponents::parse(input);
+        match components {
+            DateComponents {
+                year: Some(year),
+                month: Some(month),
+                day: Some(day),
+                ..
+            } => Date::new(year, month, day).map(Self::Once),
+            DateComponents {
+                month: Some(month),
+                day: Some(day),
+                ..
+            } => Date::new(2000, month, day).map(|_| Self::Yearly { month, day }),
+            DateComponents {
+                month: None,
+                day: Some(day @ 1..=31),
+                ..
+            } => Some(Self::Monthly(day)),
+            DateComponents {
+                weekday: Some(weekday),
+                ..
+            } => Some(Self::Weekly(weekday)),
+            _ => None,
+        }
+    }
+
+    fn occurs_on(&self, date: Date) -> bool {
+        match *self {
+            Self::Weekly(weekday) => date.weekday() == weekday,
+            Self::Monthly(day) => date.day == day,
+            Self::Yearly { month, day } => date.month == month && date.day == day,
+            Self::Once(once) => date == once,
+        }
+    }
+}
+
+/// A todo item.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Task {
+    /// The name of the file the task was found in.
+    pub file: String,
+    /// Titles of the headings the task is nested in, outermost first.
+    pub heading_path: Vec<String>,
+    /// Indices leading from the root of the document to the task, the same as in
+    /// [`crate::query::QueryMatch::path`]. Pass it to [`crate::edit::set_todo_status_by_path`]
+    /// to change the status of the task in the source.
+    pub path: Vec<usize>,
+    /// The title of the heading or the text of the list item, as plain text.
+    pub title: String,
+    pub status: TodoStatus,
+    pub priority: Option<String>,
+    pub due: Option<String>,
+    pub start: Option<String>,
+    pub timestamp: Option<String>,
+}
+
+impl Task {
+    /// Returns `true` unless the task is done or canceled.
+    pub fn is_open(&self) -> bool {
+        !matches!(self.status, TodoStatus::Done | TodoStatus::Canceled)
+    }
+
+    /// The due date of the task, see [`Date::parse`].
+    pub fn due_date(&self, reference: Date) -> Option<Date> {
+        Date::parse(self.due.as_deref()?, reference)
+    }
+
+    /// The start date of the task, see [`Date::parse`].
+    pub fn start_date(&self, reference: Date) -> Option<Date> {
+        Date::parse(self.start.as_deref()?, reference)
+    }
+
+    /// How the task recurs, if it is a recurring task with a timestamp.
+    pub fn recurrence(&self) -> Option<Recurrence> {
+        match &self.status {
+            TodoStatus::Recurring(Some(timestamp)) => Recurrence::parse(timestamp),
+            _ => None,
+        }
+    }
+}
+
+fn task(
+    file: &str,
+    heading_path: &[String],
+    path: &[usize],
+    title: &[ParagraphSegment],
+    extensions: &[DetachedModifierExtension],
+) -> Option<Task> {
+    let status = extensions.iter().find_map(|extension| match extension {
+        DetachedModifierExtension::Todo(status) => Some(status.clone()),
+        _ => None,
+    })?;
+    let find = |f: fn(&DetachedModifierExtension) -> Option<&String>| {
+        extensions
+            .iter()
+            .find_map(f)
+            .map(|value| value.trim().to_string())
+    };
+
+    Some(Task {
+        file: file.to_string(),
+        heading_path: heading_path.to_vec(),
+        path: path.to_vec(),
+        title: inline_to_plain_text(title, &PlainTextOptions::default())
+            .trim()
+            .to_string(),
+        status,
+        priority: find(|extension| match extension {
+            DetachedModifierExtension::Priority(priority) => Some(priority),
+            _ => None,
+        }),
+        due: find(|extension| match extension {
+            DetachedModifierExtension::DueDate(due) => Some(due),
+            _ => None,
+        }),
+        start: find(|extension| match extension {
+            DetachedModifierExtension::StartDate(start) => Some(start),
+            _ => None,
+        }),
+        timestamp: find(|extension| match extension {
+            DetachedModifierExtension::Timestamp(timestamp) => Some(timestamp),
+            _ => None,
+        }),
+    })
+}
+
+fn collect(
+    file: &str,
+    nodes: &[NorgAST],
+    path: &mut Vec<usize>,
+    heading_path: &mut Vec<String>,
+    tasks: &mut Vec<Task>,
+) {
+    for (i, node) in nodes.iter().enumerate() {
+        path.push(i);
+
+        match node {
+            NorgAST::Heading {
+                title, extensions, ..
+            } => tasks.extend(task(file, heading_path, path, title, extensions)),
+            NorgAST::NestableDetachedModifier {
+                text, extensions, ..
+            } => {
+                if let NorgAST::Paragraph(text) = text.as_ref() {
+                    tasks.extend(task(file, heading_path, path, text, extensions));
+                }
+            }
+            _ => {}
+        }
+
+        if let NorgAST::Heading { title, .. } = node {
+            heading_path.push(
+                inline_to_plain_text(title, &PlainTextOptions::default())
+                    .trim()
+                    .to_string(),
+            );
+            collect(file, children(node), path, heading_path, tasks);
+            heading_path.pop();
+        } else {
+            collect(file, children(node), path, heading_path, tasks);
+        }
+
+        path.pop();
+    }
+}
+
+/// Collects all tasks in a document, in document order.
+pub fn tasks(file: &str, ast: &[NorgAST]) -> Vec<Task> {
+    let mut tasks = vec![];
+    collect(file, ast, &mut vec![], &mut vec![], &mut tasks);
+    tasks
+}
+
+/// A single occurrence of a recurring task.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Occurrence<'a> {
+    pub date: Date,
+    pub task: &'a Task,
+}
+
+/// Tasks from any number of documents, viewed from a given day.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Agenda {
+    pub today: Date,
+    pub tasks: Vec<Task>,
+}
+
+impl Agenda {
+    pub fn new(today: Date) -> Self {
+        Self {
+            today,
+            tasks: vec![],
+        }
+    }
+
+    /// Adds all tasks in a document to the agenda.
+    pub fn add_document(&mut self, file: &str, ast: &[NorgAST]) {
+        self.tasks.extend(tasks(file, ast));
+    }
+
+    /// Open tasks with a due date in the given range, sorted by due date.
+    fn due_between(&self, from: Option<Date>, to: Date) -> Vec<&Task> {
+        let mut due: Vec<_> = self
+            .tasks
+            .iter()
+            .filter(|task| task.is_open())
+            .filter_map(|task| Some((task.due_date(self.today)?, task)))
+            .filter(|(date, _)| from.is_none_or(|from| *date >= from) && *date <= to)
+            .collect();
+        due.sort_by_key(|(date, _)| *date);
+        due.into_iter().map(|(_, task)| task).collect()
+    }
+
+    /// Open tasks that were due before today, oldest first.
+    pub fn overdue(&self) -> Vec<&Task> {
+        self.due_between(None, self.today.add_days(-1))
+    }
+
+    /// Open tasks that are due today.
+    pub fn due_today(&self) -> Vec<&Task> {
+        self.due_between(Some(self.today), self.today)
+    }
+
+    /// Open tasks that are due between today and the end of the week (Sunday), soonest first.
+    pub fn due_this_week(&self) -> Vec<&Task> {
+        let sunday = self.today.add_days(6 - i64::from(self.today.weekday()));
+        self.due_between(Some(self.today), sunday)
+    }
+
+    /// Open tasks grouped by priority. Priorities are sorted alphabetically, tasks without a
+    /// priority come last.
+    pub fn by_priority(&self) -> Vec<(Option<&str>, Vec<&Task>)> {
+        let mut groups: Vec<(Option<&str>, Vec<&Task>)> = vec![];
+        for task in self.tasks.iter().filter(|task| task.is_open()) {
+            let priority = task.priority.as_deref();
+            match groups.iter_mut().find(|(group, _)| *group == priority) {
+                Some((_, tasks)) => tasks.push(task),
+                None => groups.push((priority, vec![task])),
+            }
+        }
+
+        groups.sort_by(|(a, _), (b, _)| match (a, b) {
+            (Some(a), Some(b)) => a.cmp(b),
+            (a, b) => b.is_some().cmp(&a.is_some()),
+        });
+        groups
+    }
+
+    /// All tasks grouped by status, named as in the `:todo(...)` selector of
+    /// [`crate::query`]. Groups are in the order the statuses appear in the Norg specification.
+    pub fn by_status(&self) -> Vec<(&'static str, Vec<&Task>)> {
+        [
+            TodoStatus::Undone,
+            TodoStatus::Done,
+            TodoStatus::NeedsClarification,
+            TodoStatus::Paused,
+            TodoStatus::Urgent,
+            TodoStatus::Recurring(None),
+            TodoStatus::Pending,
+            TodoStatus::Canceled,
+        ]
+        .iter()
+        .map(|status| todo_status_name(status))
+        .map(|name| {
+            let tasks: Vec<_> = self
+                .tasks
+                .iter()
+                .filter(|task| todo_status_name(&task.status) == name)
+                .collect();
+            (name, tasks)
+        })
+        .filter(|(_, tasks)| !tasks.is_empty())
+        .collect()
+    }
+
+    /// Expands recurring tasks into their occurrences between `from` and `to` (inclusive),
+    /// sorted by date. Tasks with a start date don't occur before it.
+    pub fn recurring(&self, from: Date, to: Date) -> Vec<Occurrence<'_>> {
+        let recurring: Vec<_> = self
+            .tasks
+            .iter()
+            .filter_map(|task| Some((task.recurrence()?, task.start_date(self.today), task)))
+            .collect();
+
+        let mut occurrences = vec![];
+        let mut date = from;
+        while date <= to {
+            occurrences.extend(
+                recurring
+                    .iter()
+                    .filter(|(recurrence, start, _)| {
+                        recurrence.occurs_on(date) && start.is_none_or(|start| start <= date)
+                    })
+                    .map(|(_, _, task)| Occurrence { date, task }),
+            );
+            date = date.add_days(1);
+        }
+        occurrences
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        agenda::{Agenda, Date, Recurrence},
+        parse_tree,
+    };
+
+    const INPUT: &str = "* Work
+  - (!|< 2025-01-10) file taxes
+  - ( |# B|< 15 Jan 2025) review the parser
+  - (x|< Jan 14) already done
+  - ( |# A|< Sunday, 19th January 2025 12:00) plan the sprint
+  - (-|< 2025-02-01) long running
+** (?|# A) Unclear heading task
+* Home
+  - (+ Friday) take out the trash
+  - (+ 31st|> 2025-02-01) pay rent
+  - (+ 15th Jan) birthday
+  - (_) cancelled
+";
+
+    fn date(year: i32, month: u8, day: u8) -> Date {
+        Date::new(year, month, day).unwrap()
+    }
+
+    fn agenda() -> Agenda {
+        let mut agenda = Agenda::new(date(2025, 1, 15));
+        agenda.add_document("todo.norg", &parse_tree(INPUT).unwrap());
+        agenda
+    }
+
+    fn titles<'a>(tasks: impl IntoIterator<Item = &'a crate::agenda::Task>) -> Vec<&'a str> {
+        tasks.into_iter().map(|task| task.title.as_str()).collect()
+    }
+
+    #[test]
+    fn dates() {
+        let today = date(2025, 1, 15);
+
+        assert_eq!(Date::parse("2025-01-31", today), Some(date(2025, 1, 31)));
+        assert_eq!(Date::parse("31 Jan 2025", today), Some(date(2025, 1, 31)));
+        assert_eq!(Date::parse("Feb 1", today), Some(date(2025, 2, 1)));
+        assert_eq!(
+            Date::parse("Sat, 29 Oct 1949 12:32.10 [GMT]", today),
+            Some(date(1949, 10, 29))
+        );
+        assert_eq!(Date::parse("30 Feb 2025", today), None);
+        assert_eq!(Date::parse("tomorrow", today), None);
+
+        assert_eq!(today.weekday(), 2);
+        assert_eq!(date(1970, 1, 1).add_days(-1), date(1969, 12, 31));
+        assert_eq!(date(2024, 2, 28).add_days(1), date(2024, 2, 29));
+        assert_eq!(today.add_days(365), date(2026, 1, 15));
+
+        assert_eq!(Recurrence::parse("Friday"), Some(Recurrence::Weekly(4)));
+        assert_eq!(Recurrence::parse("5th"), Some(Recurrence::Monthly(5)));
+        assert_eq!(
+            Recurrence::parse("4th May"),
+            Some(Recurrence::Yearly { month: 5, day: 4 })
+        );
+    }
+
+    #[test]
+    fn views() {
+        let agenda = agenda();
+
+        assert_eq!(agenda.tasks.len(), 10);
+        assert_eq!(agenda.tasks[5].heading_path, ["Work"]);
+        assert_eq!(agenda.tasks[5].path, [0, 5]);
+        assert_eq!(titles(agenda.overdue()), ["file taxes"]);
+        assert_eq!(titles(agenda.due_today()), ["review the parser"]);
+        assert_eq!(
+            titles(agenda.due_this_week()),
+            ["review the parser", "plan the sprint"]
+        );
+
+        let by_priority: Vec<_> = agenda
+            .by_priority()
+            .into_iter()
+            .map(|(priority, tasks)| (priority, tasks.len()))
+            .collect();
+        assert_eq!(by_priority, [(Some("A"), 2), (Some("B"), 1), (None, 5)]);
+
+        let by_status: Vec<_> = agenda
+            .by_status()
+            .into_iter()
+            .map(|(status, tasks)| (status, titles(tasks)))
+            .collect();
+        assert_eq!(
+            by_status,
+            [
+                ("undone", vec!["review the parser", "plan the sprint"]),
+                ("done", vec!["already done"]),
+                ("needs-clarification", vec!["Unclear heading task"]),
+                ("urgent", vec!["file taxes"]),
+                (
+                    "recurring",
+                    vec!["take out the trash", "pay rent", "birthday"]
+                ),
+                ("pending", vec!["long running"]),
+                ("canceled", vec!["cancelled"]),
+            ]
+        );
+    }
+
+    #[test]
+    fn recurring() {
+        let agenda = agenda();
+        let occurrences: Vec<_> = agenda
+            .recurring(date(2025, 1, 14), date(2025, 3, 31))
+            .into_iter()
+            .map(|occurrence| (occurrence.date.to_string(), occurrence.task.title.as_str()))
+            .filter(|(_, title)| *title != "take out the trash")
+            .collect();
+
+        assert_eq!(
+            occurrences,
+            [
+                ("2025-01-15".to_string(), "birthday"),
+                ("2025-03-31".to_string(), "pay rent"),
+            ]
+        );
+        assert_eq!(
+            agenda
+                .recurring(date(2025, 1, 14), date(2025, 1, 24))
+                .iter()
+                .map(|occurrence| occurrence.date.to_string())
+                .collect::<Vec<_>>(),
+            ["2025-01-15", "2025-01-17", "2025-01-24"]
+        );
+    }
+}
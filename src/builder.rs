@@ -0,0 +1,505 @@
+//! A builder for creating documents in code, e.g. to generate templates.
+//!
+//! ```
+//! use rust_norg::{builder::Doc, TodoStatus};
+//!
+//! let doc = Doc::new()
+//!     .heading(1, "Weekly review")
+//!     .paragraph("What happened *this* week?")
+//!     .list(|l| l.item("Inbox zero").todo(TodoStatus::Done).item("Plan next week"));
+//!
+//! assert_eq!(
+//!     doc.to_string(),
+//!     "* Weekly review\n  What happened *this* week?\n\n  - (x) Inbox zero\n  - Plan next week\n"
+//! );
+//! ```
+//!
+//! Text is read as Norg inline markup, so `*this*` above is bold. A special character at the
+//! start of a text is escaped, so that e.g. a paragraph can't turn into a list item when the
+//! document is printed.
+//!
+//! Printing a document and parsing it again gives back the AST the builder built, as long as
+//! titles, list items and the text of definitions, footnotes and table cells aren't empty and
+//! verbatim content doesn't contain an `@end` line. Neither can be written in Norg.
+
+use std::fmt;
+
+use chumsky::Parser as _;
+use itertools::Itertools;
+use textwrap::dedent;
+
+use crate::{
+    format::{print, FormatOptions},
+    metadata::NorgMeta,
+    stage_1::{stage_1, NorgToken},
+    stage_2::{stringify_tokens, tokens_to_paragraph_segment},
+    stage_3::parse_paragraph,
+    stage_4, CarryoverTag, DelimitingModifier, DetachedModifierExtension, NestableDetachedModifier,
+    NorgAST, NorgASTFlat, ParagraphSegment, ParagraphSegmentToken, RangeableDetachedModifier,
+    TodoStatus,
+};
+
+/// Characters which open and close attached modifiers, e.g. `*bold*`.
+const MODIFIERS: &str = "*/_-!^,%$&";
+/// Characters which start links, anchors, inline link targets or inline verbatim, or escape
+/// the character after them.
+const ALWAYS_ESCAPED: &str = "\\`{}[]<>";
+
+fn is_boundary(c: Option<&char>) -> bool {
+    c.is_none_or(|c| c.is_whitespace() || c.is_ascii_punctuation())
+}
+
+/// Escapes literal text, so that it isn't read as markup when passed to the builder.
+///
+/// A modifier character is only escaped if it could open an attached modifier that is closed
+/// later in the text, so that ordinary punctuation like `well-known` or `a, b` stays readable.
+pub fn escape(text: &str) -> String {
+    let chars: Vec<_> = text.chars().collect();
+    let opens = |i: usize| {
+        (i == 0 || is_boundary(chars.get(i - 1)))
+            && chars.get(i + 1).is_some_and(|c| !c.is_whitespace())
+    };
+    let closes = |i: usize| i > 0 && !chars[i - 1].is_whitespace() && is_boundary(chars.get(i + 1));
+
+    let mut out = String::with_capacity(text.len());
+    for (i, &c) in chars.iter().enumerate() {
+        let escaped = ALWAYS_ESCAPED.contains(c)
+            // The parser reads `@end` as the end of a tag, even inside a paragraph.
+            || (c == '@' && chars[i + 1..].starts_with(&['e', 'n', 'd']))
+            || (MODIFIERS.contains(c)
+                && opens(i)
+                && (i + 1..chars.len()).any(|j| chars[j] == c && closes(j)));
+        if escaped {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// Lexes text with the regular lexer, on a single line.
+fn lex(text: &str) -> Vec<NorgToken> {
+    stage_1()
+        .parse(text)
+        .unwrap_or_else(|_| text.chars().map(NorgToken::Regular).collect())
+}
+
+/// Parses inline markup into paragraph segments.
+fn inline(text: &str) -> Vec<ParagraphSegment> {
+    let tokens = lex(text.trim())
+        .into_iter()
+        .flat_map(|token| match token {
+            NorgToken::SingleNewline | NorgToken::Newlines(_) => vec![NorgToken::Whitespace(1)],
+            NorgToken::End(c) => std::iter::once(NorgToken::Special(c))
+                .chain("end".chars().map(NorgToken::Regular))
+                .collect(),
+            NorgToken::Eof => vec![],
+            token => vec![token],
+        })
+        .dedup_by(|a, b| matches!((a, b), (NorgToken::Whitespace(_), NorgToken::Whitespace(_))))
+        .collect();
+    let mut tokens = tokens_to_paragraph_segment(tokens);
+
+    // A backslash at the end would escape the line break after it.
+    if let Some(token) = tokens.last_mut() {
+        if *token == ParagraphSegmentToken::Special('\\') {
+            *token = ParagraphSegmentToken::Escape('\\');
+        }
+    }
+
+    let segments = paragraph(tokens.clone());
+    match (segments.first(), tokens.first()) {
+        (
+            Some(ParagraphSegment::Token(ParagraphSegmentToken::Special(_))),
+            Some(&ParagraphSegmentToken::Special(c)),
+        ) => {
+            tokens[0] = ParagraphSegmentToken::Escape(c);
+            paragraph(tokens)
+        }
+        _ => segments,
+    }
+}
+
+fn paragraph(tokens: Vec<ParagraphSegmentToken>) -> Vec<ParagraphSegment> {
+    parse_paragraph(tokens.clone()).unwrap_or_else(|_| {
+        tokens
+            .into_iter()
+            .map(|token| match token {
+                ParagraphSegmentToken::Special(c) => ParagraphSegmentToken::Escape(c),
+                token => token,
+            })
+            .map(ParagraphSegment::Token)
+            .collect()
+    })
+}
+
+fn tag_name(name: &str) -> Vec<String> {
+    name.split('.').map(String::from).collect()
+}
+
+fn tag_parameters(parameters: &[&str]) -> Vec<String> {
+    parameters
+        .iter()
+        .map(|parameter| parameter.to_string())
+        .collect()
+}
+
+/// Adds an extension to a heading or detached modifier, looking through carryover tags.
+fn add_extension(object: &mut NorgASTFlat, extension: DetachedModifierExtension) {
+    match object {
+        NorgASTFlat::Heading {
+            title, extensions, ..
+        } => {
+            // The parser keeps the space between the extension section and the title.
+            if extensions.is_empty() {
+                title.insert(
+                    0,
+                    ParagraphSegment::Token(ParagraphSegmentToken::Whitespace),
+                );
+            }
+            extensions.push(extension);
+        }
+        NorgASTFlat::NestableDetachedModifier { extensions, .. }
+        | NorgASTFlat::RangeableDetachedModifier { extensions, .. } => extensions.push(extension),
+        NorgASTFlat::CarryoverTag { next_object, .. } => add_extension(next_object, extension),
+        _ => (),
+    }
+}
+
+/// Something in an imported document that couldn't be converted exactly.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Loss {
+    /// The zero-based index of the line it is on.
+    pub line: usize,
+    pub message: String,
+}
+
+/// The result of converting a document from another format.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Import {
+    pub document: Doc,
+    /// The document's metadata, which is also in a `@document.meta` tag at the start of it.
+    pub meta: Option<NorgMeta>,
+    pub losses: Vec<Loss>,
+}
+
+/// A document under construction.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Doc {
+    objects: Vec<NorgASTFlat>,
+    /// Carryover tags waiting for the object they apply to.
+    tags: Vec<(CarryoverTag, Vec<String>, Vec<String>)>,
+}
+
+impl Doc {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn push(mut self, object: NorgASTFlat) -> Self {
+        let object =
+            self.tags
+                .drain(..)
+                .rev()
+                .fold(object, |object, (tag_type, name, parameters)| {
+                    NorgASTFlat::CarryoverTag {
+                        tag_type,
+                        name,
+                        parameters,
+                        next_object: Box::new(object),
+                    }
+                });
+        self.objects.push(object);
+        self
+    }
+
+    /// Adds a heading. Everything after it belongs to the heading, up to the next heading of
+    /// the same or a higher level or a delimiting modifier.
+    pub fn heading(self, level: u16, title: &str) -> Self {
+        self.push(NorgASTFlat::Heading {
+            level: level.max(1),
+            title: inline(title),
+            extensions: Vec::new(),
+        })
+    }
+
+    /// Adds a detached modifier extension to the last object, if it's a heading, a definition
+    /// or a footnote.
+    pub fn extension(mut self, extension: DetachedModifierExtension) -> Self {
+        if let Some(object) = self.objects.last_mut() {
+            add_extension(object, extension);
+        }
+        self
+    }
+
+    /// Marks the last heading as a task, like `* (x) Heading`.
+    pub fn todo(self, status: TodoStatus) -> Self {
+        self.extension(DetachedModifierExtension::Todo(status))
+    }
+
+    /// Adds a paragraph. Empty paragraphs are skipped, as they don't exist in a document.
+    pub fn paragraph(self, text: &str) -> Self {
+        let segments = inline(text);
+        if segments.is_empty() {
+            return self;
+        }
+        self.push(NorgASTFlat::Paragraph(segments))
+    }
+
+    fn nestable(
+        mut self,
+        modifier_type: NestableDetachedModifier,
+        build: impl FnOnce(List) -> List,
+    ) -> Self {
+        let list = build(List {
+            modifier_type,
+            level: 1,
+            items: Vec::new(),
+        });
+        for item in list.items {
+            self = self.push(item);
+        }
+        self
+    }
+
+    /// Adds an unordered list (`-`).
+    pub fn list(self, build: impl FnOnce(List) -> List) -> Self {
+        self.nestable(NestableDetachedModifier::UnorderedList, build)
+    }
+
+    /// Adds an ordered list (`~`).
+    pub fn ordered_list(self, build: impl FnOnce(List) -> List) -> Self {
+        self.nestable(NestableDetachedModifier::OrderedList, build)
+    }
+
+    /// Adds a quote (`>`), with a [`List`] item per paragraph.
+    pub fn quote(self, build: impl FnOnce(List) -> List) -> Self {
+        self.nestable(NestableDetachedModifier::Quote, build)
+    }
+
+    fn rangeable(self, modifier_type: RangeableDetachedModifier, title: &str, text: &str) -> Self {
+        self.push(NorgASTFlat::RangeableDetachedModifier {
+            modifier_type,
+            title: inline(title),
+            extensions: Vec::new(),
+            content: vec![NorgASTFlat::Paragraph(inline(text))],
+        })
+    }
+
+    /// Adds a definition (`$ title`) with a single paragraph.
+    pub fn definition(self, title: &str, text: &str) -> Self {
+        self.rangeable(RangeableDetachedModifier::Definition, title, text)
+    }
+
+    /// Adds a footnote (`^ title`) with a single paragraph.
+    pub fn footnote(self, title: &str, text: &str) -> Self {
+        self.rangeable(RangeableDetachedModifier::Footnote, title, text)
+    }
+
+    /// Adds a table cell (`: A1`) with a single paragraph.
+    pub fn table_cell(self, cell: &str, text: &str) -> Self {
+        self.rangeable(RangeableDetachedModifier::Table, cell, text)
+    }
+
+    /// Adds a verbatim ranged tag, e.g. `@code lua`. The content is dedented.
+    pub fn verbatim(self, name: &str, parameters: &[&str], content: &str) -> Self {
+        let mut content = content.to_string();
+        if !content.is_empty() && !content.ends_with('\n') {
+            content.push('\n');
+        }
+        let tokens = lex(&content)
+            .into_iter()
+            .filter(|token| *token != NorgToken::Eof);
+
+        self.push(NorgASTFlat::VerbatimRangedTag {
+            name: tag_name(name),
+            parameters: tag_parameters(parameters),
+            // Like the parser, drop empty lines at the start.
+            content: dedent(&stringify_tokens(tokens))
+                .trim_start_matches('\n')
+                .to_string(),
+        })
+    }
+
+    /// Adds a code block, e.g. `@code rust`. `language` may be empty.
+    pub fn code(self, language: &str, content: &str) -> Self {
+        let parameters: Vec<_> = Some(language)
+            .filter(|l| !l.is_empty())
+            .into_iter()
+            .collect();
+        self.verbatim("code", &parameters, content)
+    }
+
+    /// Adds a ranged tag, e.g. `|example`, with the objects built by `build` inside of it.
+    pub fn ranged_tag(
+        self,
+        name: &str,
+        parameters: &[&str],
+        build: impl FnOnce(Doc) -> Doc,
+    ) -> Self {
+        self.push(NorgASTFlat::RangedTag {
+            name: tag_name(name),
+            parameters: tag_parameters(parameters),
+            content: build(Doc::new()).build(),
+        })
+    }
+
+    /// Adds an infirm tag, e.g. `.toc`.
+    pub fn infirm_tag(self, name: &str, parameters: &[&str]) -> Self {
+        self.push(NorgASTFlat::InfirmTag {
+            name: tag_name(name),
+            parameters: tag_parameters(parameters),
+        })
+    }
+
+    /// Applies a carryover tag (`+attribute` or `#macro`) to the next object. Tags that are
+    /// never followed by an object are dropped.
+    pub fn carryover_tag(
+        mut self,
+        tag_type: CarryoverTag,
+        name: &str,
+        parameters: &[&str],
+    ) -> Self {
+        self.tags
+            .push((tag_type, tag_name(name), tag_parameters(parameters)));
+        self
+    }
+
+    /// Adds a weak delimiting modifier (`---`), which closes the last heading.
+    pub fn weak_delimiter(self) -> Self {
+        self.push(NorgASTFlat::DelimitingModifier(DelimitingModifier::Weak))
+    }
+
+    /// Adds a strong delimiting modifier (`===`), which closes all headings.
+    pub fn strong_delimiter(self) -> Self {
+        self.push(NorgASTFlat::DelimitingModifier(DelimitingModifier::Strong))
+    }
+
+    /// Adds a horizontal rule (`___`).
+    pub fn horizontal_rule(self) -> Self {
+        self.push(NorgASTFlat::DelimitingModifier(
+            DelimitingModifier::HorizontalRule,
+        ))
+    }
+
+    /// Returns the document as a flat AST, as returned by [`crate::parse`].
+    pub fn build(self) -> Vec<NorgASTFlat> {
+        self.objects
+    }
+
+    /// Returns the document as a tree, as returned by [`crate::parse_tree`].
+    pub fn build_tree(self) -> Vec<NorgAST> {
+        stage_4(self.objects)
+    }
+
+    /// Prints the document in canonical style, see [`crate::format::print`].
+    pub fn print(&self, options: &FormatOptions) -> String {
+        print(&self.objects, options)
+    }
+}
+
+impl fmt::Display for Doc {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.print(&FormatOptions::default()))
+    }
+}
+
+/// The items of a list or quote under construction, see [`Doc::list`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct List {
+    modifier_type: NestableDetachedModifier,
+    level: u16,
+    /// The items in document order, including nested ones.
+    items: Vec<NorgASTFlat>,
+}
+
+impl List {
+    /// Adds an item.
+    pub fn item(mut self, text: &str) -> Self {
+        self.items.push(NorgASTFlat::NestableDetachedModifier {
+            modifier_type: self.modifier_type.clone(),
+            level: self.level,
+            extensions: Vec::new(),
+            content: Box::new(NorgASTFlat::Paragraph(inline(text))),
+        });
+        self
+    }
+
+    /// Adds items nested below the last item.
+    pub fn nested(mut self, build: impl FnOnce(List) -> List) -> Self {
+        let nested = build(List {
+            modifier_type: self.modifier_type.clone(),
+            level: self.level + 1,
+            items: Vec::new(),
+        });
+        self.items.extend(nested.items);
+        self
+    }
+
+    /// Adds a detached modifier extension to the last item.
+    pub fn extension(mut self, extension: DetachedModifierExtension) -> Self {
+        let level = self.level;
+        let last = self.items.iter_mut().rev().find(|item| {
+            matches!(item, NorgASTFlat::NestableDetachedModifier { level: item_level, .. } if *item_level == level)
+        });
+        if let Some(item) = last {
+            add_extension(item, extension);
+        }
+        self
+    }
+
+    /// Marks the last item as a task, like `- (x) item`.
+    pub fn todo(self, status: TodoStatus) -> Self {
+        self.extension(DetachedModifierExtension::Todo(status))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use insta::assert_snapshot;
+
+    use crate::{
+        builder::{escape, Doc},
+        parse, CarryoverTag, DetachedModifierExtension, TodoStatus,
+    };
+
+    #[test]
+    fn meeting_notes() {
+        let doc = Doc::new()
+            .carryover_tag(CarryoverTag::Attribute, "date", &["2025-01-06"])
+            .heading(1, "Meeting notes")
+            .todo(TodoStatus::Pending)
+            .paragraph("Attendees: {:people:* Alice}[Alice] and /Bob/")
+            .heading(2, "Action items")
+            .list(|l| {
+                l.item("Ship the release")
+                    .todo(TodoStatus::Done)
+                    .extension(DetachedModifierExtension::DueDate("Fri".into()))
+                    .nested(|l| l.item("Write the changelog").todo(TodoStatus::Undone))
+                    .item("- not a nested item")
+            })
+            .ordered_list(|l| l.item("first").item("second"))
+            .strong_delimiter()
+            .definition("Norg", "A markup format")
+            .code("rust", "fn main() {\n    println!(\"hi\");\n}")
+            .ranged_tag("comment", &[], |d| d.paragraph("internal"))
+            .infirm_tag("toc", &[])
+            .horizontal_rule();
+
+        let text = doc.to_string();
+        assert_eq!(parse(&text).unwrap(), doc.build());
+        assert_snapshot!(text);
+    }
+
+    #[test]
+    fn escaping() {
+        assert_eq!(
+            escape("well-known, a/b and 5 - 3"),
+            "well-known, a/b and 5 - 3"
+        );
+        assert_eq!(escape("*not bold*"), "\\*not bold*");
+        assert_eq!(escape("[x] {y}"), "\\[x\\] \\{y\\}");
+        assert_eq!(escape("a$b$c costs US$5-$10"), "a$b$c costs US$5-$10");
+        assert_eq!(escape("$x$ and $5"), "\\$x$ and $5");
+    }
+}
@@ -0,0 +1,874 @@
+//! A lossless concrete syntax tree.
+//!
+//! The AST drops everything the parser considers insignificant: extra whitespace, the kind of
+//! line endings, indentation of verbatim content. The CST keeps every byte of the source, so
+//! `parse_cst(source)?.text() == source` always holds. That makes it the right tree for
+//! refactoring tools, which have to change one part of a document and leave the rest alone.
+//!
+//! The tree is split in two layers, like in `rowan`:
+//!
+//! * [`GreenNode`]s and [`GreenToken`]s are immutable, know their kind and length but not their
+//!   position, and can be shared between trees.
+//! * [`SyntaxNode`]s and [`SyntaxToken`]s are cheap handles on top, which know their parent and
+//!   their offset in the document.
+
+use std::{fmt, ops::Range, rc::Rc, sync::Arc};
+
+use chumsky::Parser as _;
+
+use crate::{
+    error::NorgParseError,
+    stage_1::{stage_1_spanned, NorgToken},
+    stage_2::{stage_2_spanned, NorgBlock},
+};
+
+/// The kind of a node or token in the CST.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum SyntaxKind {
+    // Tokens
+    /// Spaces, tabs and other inline whitespace.
+    Whitespace,
+    /// A single line break, e.g. `\n` or `\r\n`.
+    Newline,
+    /// A run of regular characters.
+    Text,
+    /// A single special character, e.g. `*` or `{`.
+    Special,
+    /// An escaped character including its backslash, e.g. `\*`.
+    Escape,
+    /// The end of a ranged tag, e.g. `@end`.
+    TagEnd,
+
+    // Nodes
+    Document,
+    Paragraph,
+    Heading,
+    /// The title of a heading.
+    Title,
+    /// A detached modifier extension section including its parentheses, e.g. `(x|# A)`.
+    ExtensionSection,
+    NestableDetachedModifier,
+    RangeableDetachedModifier,
+    CarryoverTag,
+    RangedTag,
+    VerbatimRangedTag,
+    /// The lines between a verbatim ranged tag and its `@end`.
+    VerbatimContent,
+    InfirmTag,
+    DelimitingModifier,
+    /// A closer without an opener.
+    Error,
+}
+
+impl SyntaxKind {
+    /// Returns `true` if this is the kind of a token rather than a node.
+    pub fn is_token(self) -> bool {
+        matches!(
+            self,
+            Self::Whitespace
+                | Self::Newline
+                | Self::Text
+                | Self::Special
+                | Self::Escape
+                | Self::TagEnd
+        )
+    }
+}
+
+/// An immutable token with its exact source text.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct GreenToken {
+    kind: SyntaxKind,
+    text: String,
+}
+
+impl GreenToken {
+    pub fn new(kind: SyntaxKind, text: impl Into<String>) -> Self {
+        Self {
+            kind,
+            text: text.into(),
+        }
+    }
+
+    pub fn kind(&self) -> SyntaxKind {
+        self.kind
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+}
+
+/// A child of a [`GreenNode`].
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum GreenElement {
+    Node(Arc<GreenNode>),
+    Token(Arc<GreenToken>),
+}
+
+impl GreenElement {
+    pub fn kind(&self) -> SyntaxKind {
+        match self {
+            Self::Node(node) => node.kind,
+            Self::Token(token) => token.kind,
+        }
+    }
+
+    /// The length of the element in bytes.
+    pub fn text_len(&self) -> usize {
+        match self {
+            Self::Node(node) => node.len,
+            Self::Token(token) => token.text.len(),
+        }
+    }
+}
+
+impl From<GreenNode> for GreenElement {
+    fn from(node: GreenNode) -> Self {
+        Self::Node(Arc::new(node))
+    }
+}
+
+impl From<GreenToken> for GreenElement {
+    fn from(token: GreenToken) -> Self {
+        Self::Token(Arc::new(token))
+    }
+}
+
+/// An immutable node, which only knows its kind, its children and its length.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct GreenNode {
+    kind: SyntaxKind,
+    len: usize,
+    children: Vec<GreenElement>,
+}
+
+impl GreenNode {
+    pub fn new(kind: SyntaxKind, children: Vec<GreenElement>) -> Self {
+        Self {
+            kind,
+            len: children.iter().map(GreenElement::text_len).sum(),
+            children,
+        }
+    }
+
+    pub fn kind(&self) -> SyntaxKind {
+        self.kind
+    }
+
+    /// The length of the node's text in bytes.
+    pub fn text_len(&self) -> usize {
+        self.len
+    }
+
+    pub fn children(&self) -> &[GreenElement] {
+        &self.children
+    }
+
+    /// Returns a copy of this node with the child at `index` replaced.
+    fn with_child(&self, index: usize, child: GreenElement) -> Self {
+        let mut children = self.children.clone();
+        children[index] = child;
+        Self::new(self.kind, children)
+    }
+}
+
+impl fmt::Display for GreenNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.children.iter().try_for_each(|child| match child {
+            GreenElement::Node(node) => node.fmt(f),
+            GreenElement::Token(token) => f.write_str(&token.text),
+        })
+    }
+}
+
+struct NodeData {
+    green: Arc<GreenNode>,
+    parent: Option<SyntaxNode>,
+    /// The index of this node in its parent's children.
+    index: usize,
+    /// The offset of this node in the document, in bytes.
+    offset: usize,
+}
+
+/// A node in the CST, which knows its position and parent.
+#[derive(Clone)]
+pub struct SyntaxNode(Rc<NodeData>);
+
+impl SyntaxNode {
+    /// Creates the root of a tree.
+    pub fn new_root(green: GreenNode) -> Self {
+        Self(Rc::new(NodeData {
+            green: Arc::new(green),
+            parent: None,
+            index: 0,
+            offset: 0,
+        }))
+    }
+
+    pub fn kind(&self) -> SyntaxKind {
+        self.0.green.kind
+    }
+
+    pub fn green(&self) -> &GreenNode {
+        &self.0.green
+    }
+
+    /// The range of bytes in the document this node was created from.
+    pub fn text_range(&self) -> Range<usize> {
+        self.0.offset..self.0.offset + self.0.green.len
+    }
+
+    /// The exact source text of the node.
+    pub fn text(&self) -> String {
+        self.0.green.to_string()
+    }
+
+    pub fn parent(&self) -> Option<SyntaxNode> {
+        self.0.parent.clone()
+    }
+
+    /// The node and all of its ancestors, from the inside out.
+    pub fn ancestors(&self) -> impl Iterator<Item = SyntaxNode> {
+        std::iter::successors(Some(self.clone()), SyntaxNode::parent)
+    }
+
+    pub fn children_with_tokens(&self) -> impl Iterator<Item = SyntaxElement> {
+        let mut offset = self.0.offset;
+        let children: Vec<_> = self
+            .0
+            .green
+            .children
+            .iter()
+            .enumerate()
+            .map(|(index, child)| {
+                let element = match child {
+                    GreenElement::Node(green) => {
+                        SyntaxElement::Node(SyntaxNode(Rc::new(NodeData {
+                            green: green.clone(),
+                            parent: Some(self.clone()),
+                            index,
+                            offset,
+                        })))
+                    }
+                    GreenElement::Token(green) => SyntaxElement::Token(SyntaxToken {
+                        green: green.clone(),
+                        parent: self.clone(),
+                        index,
+                        offset,
+                    }),
+                };
+                offset += child.text_len();
+                element
+            })
+            .collect();
+
+        children.into_iter()
+    }
+
+    pub fn children(&self) -> impl Iterator<Item = SyntaxNode> {
+        self.children_with_tokens()
+            .filter_map(SyntaxElement::into_node)
+    }
+
+    /// The node and all nodes below it, in document order.
+    pub fn descendants(&self) -> impl Iterator<Item = SyntaxNode> {
+        let mut nodes = vec![self.clone()];
+        let mut i = 0;
+        while i < nodes.len() {
+            let children: Vec<_> = nodes[i].children().collect();
+            nodes.splice(i + 1..i + 1, children);
+            i += 1;
+        }
+
+        nodes.into_iter()
+    }
+
+    /// All tokens below this node, in document order.
+    pub fn tokens(&self) -> impl Iterator<Item = SyntaxToken> {
+        let tokens: Vec<_> = self
+            .children_with_tokens()
+            .flat_map(|child| match child {
+                SyntaxElement::Node(node) => node.tokens().collect(),
+                SyntaxElement::Token(token) => vec![token],
+            })
+            .collect();
+
+        tokens.into_iter()
+    }
+
+    /// Returns the green tree of the whole document with this node replaced.
+    ///
+    /// Nothing outside of this node changes, so turning the result back into text only changes
+    /// the bytes this node covered.
+    pub fn replace_with(&self, replacement: GreenNode) -> GreenNode {
+        match &self.0.parent {
+            Some(parent) => {
+                parent.replace_with(parent.green().with_child(self.0.index, replacement.into()))
+            }
+            None => replacement,
+        }
+    }
+}
+
+impl PartialEq for SyntaxNode {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0.green, &other.0.green) && self.0.offset == other.0.offset
+    }
+}
+
+impl Eq for SyntaxNode {}
+
+impl fmt::Display for SyntaxNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.green.fmt(f)
+    }
+}
+
+impl fmt::Debug for SyntaxNode {
+    /// Prints the whole tree with one node or token per line, e.g. `Heading@0..10`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fn write_node(node: &SyntaxNode, depth: usize, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            writeln!(
+                f,
+                "{}{:?}@{:?}",
+                "  ".repeat(depth),
+                node.kind(),
+                node.text_range()
+            )?;
+            node.children_with_tokens()
+                .try_for_each(|child| match child {
+                    SyntaxElement::Node(child) => write_node(&child, depth + 1, f),
+                    SyntaxElement::Token(token) => {
+                        writeln!(f, "{}{:?}", "  ".repeat(depth + 1), token)
+                    }
+                })
+        }
+
+        write_node(self, 0, f)
+    }
+}
+
+/// A token in the CST, which knows its position and parent.
+#[derive(Clone)]
+pub struct SyntaxToken {
+    green: Arc<GreenToken>,
+    parent: SyntaxNode,
+    index: usize,
+    offset: usize,
+}
+
+impl SyntaxToken {
+    pub fn kind(&self) -> SyntaxKind {
+        self.green.kind
+    }
+
+    pub fn text(&self) -> &str {
+        &self.green.text
+    }
+
+    /// The range of bytes in the document this token was created from.
+    pub fn text_range(&self) -> Range<usize> {
+        self.offset..self.offset + self.green.text.len()
+    }
+
+    pub fn parent(&self) -> SyntaxNode {
+        self.parent.clone()
+    }
+
+    /// Returns the green tree of the whole document with this token replaced.
+    pub fn replace_with(&self, replacement: GreenToken) -> GreenNode {
+        self.parent.replace_with(
+            self.parent
+                .green()
+                .with_child(self.index, replacement.into()),
+        )
+    }
+}
+
+impl PartialEq for SyntaxToken {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.green, &other.green) && self.offset == other.offset
+    }
+}
+
+impl Eq for SyntaxToken {}
+
+impl fmt::Debug for SyntaxToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:?}@{:?} {:?}",
+            self.kind(),
+            self.text_range(),
+            self.text()
+        )
+    }
+}
+
+/// Either a [`SyntaxNode`] or a [`SyntaxToken`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SyntaxElement {
+    Node(SyntaxNode),
+    Token(SyntaxToken),
+}
+
+impl SyntaxElement {
+    pub fn kind(&self) -> SyntaxKind {
+        match self {
+            Self::Node(node) => node.kind(),
+            Self::Token(token) => token.kind(),
+        }
+    }
+
+    pub fn text_range(&self) -> Range<usize> {
+        match self {
+            Self::Node(node) => node.text_range(),
+            Self::Token(token) => token.text_range(),
+        }
+    }
+
+    pub fn into_node(self) -> Option<SyntaxNode> {
+        match self {
+            Self::Node(node) => Some(node),
+            Self::Token(_) => None,
+        }
+    }
+
+    pub fn into_token(self) -> Option<SyntaxToken> {
+        match self {
+            Self::Node(_) => None,
+            Self::Token(token) => Some(token),
+        }
+    }
+}
+
+/// Splits a run of line breaks into one piece per line break, keeping `\r\n` together.
+fn split_newlines(text: &str, start: usize, pieces: &mut Vec<(SyntaxKind, Range<usize>)>) {
+    let mut chars = text.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        let mut end = i + c.len_utf8();
+        if c == '\r' {
+            if let Some((_, '\n')) = chars.peek() {
+                chars.next();
+                end += 1;
+            }
+        }
+        pieces.push((SyntaxKind::Newline, start + i..start + end));
+    }
+}
+
+/// Builds the green tree from the tokens and blocks of the parser, grouping blocks like
+/// `stage_3` does.
+struct Builder<'a> {
+    source: &'a str,
+    /// The pieces of source each token is made of, with byte ranges.
+    pieces: Vec<(SyntaxKind, Range<usize>)>,
+    /// The index of the first piece of each token.
+    token_pieces: Vec<usize>,
+    /// The blocks with the range of pieces they were parsed from.
+    blocks: Vec<(NorgBlock, Range<usize>)>,
+    /// The next block to build a node for.
+    block: usize,
+    /// The next piece to add to the tree.
+    piece: usize,
+}
+
+impl Builder<'_> {
+    fn peek(&self) -> Option<&NorgBlock> {
+        self.blocks.get(self.block).map(|(block, _)| block)
+    }
+
+    fn peek_start(&self) -> usize {
+        self.blocks
+            .get(self.block)
+            .map_or(self.pieces.len(), |(_, span)| span.start)
+    }
+
+    /// Adds the pieces up to `end` as tokens, merging runs of regular characters.
+    fn tokens(&mut self, end: usize, children: &mut Vec<GreenElement>) {
+        while self.piece < end {
+            let (kind, range) = self.pieces[self.piece].clone();
+            let mut text_end = range.end;
+            self.piece += 1;
+
+            if kind == SyntaxKind::Text {
+                while self.piece < end && self.pieces[self.piece].0 == SyntaxKind::Text {
+                    text_end = self.pieces[self.piece].1.end;
+                    self.piece += 1;
+                }
+            }
+
+            if range.start < text_end {
+                children.push(GreenToken::new(kind, &self.source[range.start..text_end]).into());
+            }
+        }
+    }
+
+    /// Creates a node from the pieces up to `end`.
+    fn node(&mut self, kind: SyntaxKind, end: usize) -> GreenElement {
+        let mut children = Vec::new();
+        self.tokens(end, &mut children);
+        GreenNode::new(kind, children).into()
+    }
+
+    /// Adds the whitespace before the next block and a node for it.
+    fn object(&mut self, children: &mut Vec<GreenElement>) {
+        self.tokens(self.peek_start(), children);
+
+        let (block, span) = self.blocks[self.block].clone();
+        self.block += 1;
+
+        let mut own = Vec::new();
+        let kind = match block {
+            NorgBlock::Heading { extension_span, .. } => {
+                self.extension_section(extension_span, &mut own);
+                let title_start = (self.piece..span.end)
+                    .find(|&i| self.pieces[i].0 != SyntaxKind::Whitespace)
+                    .unwrap_or(span.end);
+                let title_end = (title_start..span.end)
+                    .rev()
+                    .find(|&i| self.pieces[i].0 != SyntaxKind::Newline && !self.is_empty(i))
+                    .map_or(title_start, |i| i + 1);
+                self.tokens(title_start, &mut own);
+                if title_start < title_end {
+                    own.push(self.node(SyntaxKind::Title, title_end));
+                }
+                SyntaxKind::Heading
+            }
+            NorgBlock::NestableDetachedModifier { extension_span, .. } => {
+                self.extension_section(extension_span, &mut own);
+                self.tokens(span.end, &mut own);
+                self.paragraph(&mut own);
+                SyntaxKind::NestableDetachedModifier
+            }
+            NorgBlock::RangeableDetachedModifier { ranged: false, .. } => {
+                self.tokens(span.end, &mut own);
+                self.paragraph(&mut own);
+                SyntaxKind::RangeableDetachedModifier
+            }
+            NorgBlock::RangeableDetachedModifier { ranged: true, .. } => {
+                self.tokens(span.end, &mut own);
+                self.ranged_content(&mut own, |block| {
+                    matches!(block, NorgBlock::RangeableDetachedModifierClose(_))
+                });
+                SyntaxKind::RangeableDetachedModifier
+            }
+            NorgBlock::RangedTag { .. } => {
+                self.tokens(span.end, &mut own);
+                self.ranged_content(&mut own, |block| {
+                    matches!(block, NorgBlock::RangedTagEnd(_))
+                });
+                SyntaxKind::RangedTag
+            }
+            NorgBlock::CarryoverTag { .. } => {
+                self.tokens(span.end, &mut own);
+                if self.peek().is_some() {
+                    self.object(&mut own);
+                }
+                SyntaxKind::CarryoverTag
+            }
+            NorgBlock::VerbatimRangedTag { .. } => {
+                self.verbatim_ranged_tag(span, &mut own);
+                SyntaxKind::VerbatimRangedTag
+            }
+            NorgBlock::ParagraphSegment(_) | NorgBlock::ParagraphSegmentEnd(_) => {
+                self.block -= 1;
+                return self.paragraph(children);
+            }
+            NorgBlock::InfirmTag { .. } => {
+                self.tokens(span.end, &mut own);
+                SyntaxKind::InfirmTag
+            }
+            NorgBlock::DelimitingModifier(_) => {
+                self.tokens(span.end, &mut own);
+                SyntaxKind::DelimitingModifier
+            }
+            NorgBlock::RangeableDetachedModifierClose(_) | NorgBlock::RangedTagEnd(_) => {
+                self.tokens(span.end, &mut own);
+                SyntaxKind::Error
+            }
+        };
+
+        children.push(GreenNode::new(kind, own).into());
+    }
+
+    fn is_empty(&self, piece: usize) -> bool {
+        self.pieces[piece].1.is_empty()
+    }
+
+    fn extension_section(&mut self, span: Range<usize>, children: &mut Vec<GreenElement>) {
+        let span = self.token_pieces[span.start]..self.token_pieces[span.end];
+        self.tokens(span.start, children);
+        if !span.is_empty() {
+            children.push(self.node(SyntaxKind::ExtensionSection, span.end));
+        }
+    }
+
+    /// Adds the paragraph that starts at the next block, if there is one. Like in `stage_3`, a
+    /// paragraph is made of any number of segments, followed by the one that ends it.
+    fn paragraph(&mut self, children: &mut Vec<GreenElement>) {
+        let first = self.block;
+        while let Some(NorgBlock::ParagraphSegment(_)) = self.peek() {
+            self.block += 1;
+        }
+        if let Some(NorgBlock::ParagraphSegmentEnd(_)) = self.peek() {
+            self.block += 1;
+        }
+
+        if self.block > first {
+            self.tokens(self.blocks[first].1.start, children);
+            let end = self.blocks[self.block - 1].1.end;
+            children.push(self.node(SyntaxKind::Paragraph, end));
+        }
+    }
+
+    /// Adds objects until the block that closes them, which is added as well.
+    fn ranged_content(
+        &mut self,
+        children: &mut Vec<GreenElement>,
+        is_closer: impl Fn(&NorgBlock) -> bool,
+    ) {
+        while let Some(block) = self.peek() {
+            if is_closer(block) {
+                let end = self.blocks[self.block].1.end;
+                self.block += 1;
+                self.tokens(end, children);
+                return;
+            }
+            self.object(children);
+        }
+    }
+
+    /// Splits a verbatim ranged tag into the line of the tag, its content and the `@end`,
+    /// including the indentation before it.
+    fn verbatim_ranged_tag(&mut self, span: Range<usize>, children: &mut Vec<GreenElement>) {
+        let content_start = (self.piece..span.end)
+            .find(|&i| self.pieces[i].0 == SyntaxKind::Newline)
+            .map_or(span.end, |i| i + 1);
+        let mut content_end = (content_start..span.end)
+            .rev()
+            .find(|&i| self.pieces[i].0 == SyntaxKind::TagEnd)
+            .unwrap_or(span.end);
+        if content_end > content_start
+            && self.pieces[content_end - 1].0 == SyntaxKind::Whitespace
+            && (content_end - 1 == content_start
+                || self.pieces[content_end - 2].0 == SyntaxKind::Newline)
+        {
+            content_end -= 1;
+        }
+
+        self.tokens(content_start, children);
+        if content_start <= content_end {
+            children.push(self.node(SyntaxKind::VerbatimContent, content_end));
+        }
+        self.tokens(span.end, children);
+    }
+}
+
+/// Parses a document into a lossless concrete syntax tree.
+///
+/// # Arguments
+///
+/// * `source` - The source of the document.
+///
+/// # Returns
+///
+/// * `Ok(SyntaxNode)` with the [`SyntaxKind::Document`] node, whose text is exactly `source`.
+/// * `Err(NorgParseError)` if the document can't be split into blocks.
+pub fn parse_cst(source: &str) -> Result<SyntaxNode, NorgParseError> {
+    let (tokens, spans): (Vec<_>, Vec<_>) = stage_1_spanned().parse(source)?.into_iter().unzip();
+    // Stage 2 expects at least one block, but a document of nothing but whitespace is just
+    // trivia.
+    let blank = tokens.iter().all(|token| {
+        matches!(
+            token,
+            NorgToken::Whitespace(_)
+                | NorgToken::SingleNewline
+                | NorgToken::Newlines(_)
+                | NorgToken::Eof
+        )
+    });
+    let blocks = if blank {
+        Vec::new()
+    } else {
+        stage_2_spanned().parse(tokens.clone())?
+    };
+
+    // Token spans are counted in characters, the tree in bytes.
+    let bytes: Vec<_> = source
+        .char_indices()
+        .map(|(i, _)| i)
+        .chain([source.len()])
+        .collect();
+
+    let mut pieces = Vec::new();
+    let mut token_pieces = Vec::new();
+    for (token, span) in tokens.iter().zip(&spans) {
+        token_pieces.push(pieces.len());
+        let range = bytes[span.start]..bytes[span.end];
+        let kind = match token {
+            NorgToken::SingleNewline | NorgToken::Newlines(_) => {
+                split_newlines(&source[range.clone()], range.start, &mut pieces);
+                continue;
+            }
+            NorgToken::Whitespace(_) => SyntaxKind::Whitespace,
+            NorgToken::Regular(_) | NorgToken::Eof => SyntaxKind::Text,
+            NorgToken::Special(_) => SyntaxKind::Special,
+            NorgToken::Escape(_) => SyntaxKind::Escape,
+            NorgToken::End(_) => SyntaxKind::TagEnd,
+        };
+        pieces.push((kind, range));
+    }
+    token_pieces.push(pieces.len());
+
+    let mut builder = Builder {
+        source,
+        blocks: blocks
+            .into_iter()
+            .map(|(block, span)| (block, token_pieces[span.start]..token_pieces[span.end]))
+            .collect(),
+        pieces,
+        token_pieces,
+        block: 0,
+        piece: 0,
+    };
+
+    let mut children = Vec::new();
+    while builder.peek().is_some() {
+        builder.object(&mut children);
+    }
+    builder.tokens(builder.pieces.len(), &mut children);
+
+    Ok(SyntaxNode::new_root(GreenNode::new(
+        SyntaxKind::Document,
+        children,
+    )))
+}
+
+/// Hands out the ranges of the block nodes of the CST in document order, to find out which part
+/// of the source a node of the AST was parsed from.
+///
+/// The AST has the same objects in the same order, apart from the delimiting modifiers that end
+/// a heading, which are skipped over.
+pub(crate) struct BlockSpans<'a> {
+    pub(crate) source: &'a str,
+    nodes: Vec<(SyntaxKind, Range<usize>)>,
+    next: usize,
+}
+
+impl<'a> BlockSpans<'a> {
+    pub(crate) fn new(source: &'a str) -> Result<Self, NorgParseError> {
+        Ok(Self {
+            source,
+            nodes: parse_cst(source)?
+                .descendants()
+                .map(|node| (node.kind(), node.text_range()))
+                .collect(),
+            next: 0,
+        })
+    }
+
+    /// Returns the range of the next node of the given kind, without trailing whitespace.
+    pub(crate) fn take(&mut self, kind: SyntaxKind) -> Range<usize> {
+        while let Some((node_kind, range)) = self.nodes.get(self.next) {
+            self.next += 1;
+            if *node_kind == kind {
+                let text = self.source[range.clone()].trim_end();
+                return range.start..range.start + text.len();
+            }
+        }
+
+        let end = self.nodes.last().map_or(0, |(_, range)| range.end);
+        end..end
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use insta::assert_snapshot;
+
+    use crate::cst::{parse_cst, GreenNode, GreenToken, SyntaxKind};
+
+    #[test]
+    fn lossless() {
+        let examples = [
+            "* Heading\n  content\n",
+            "* (x|#  A)   Spaced   out  \r\n\r\n  - item\r\n  continued\r\n",
+            "\t@code python\n\t  if x:\n\t\tprint(x)\n\t@end",
+            "no\u{a0}break\u{2028}line separator\u{2029}paragraph separator\r",
+            "|example\n  $$ Ranged\n  text\n  $$\n|end\n+attr\n#macro\n.infirm\n___\n",
+            "stray closer\n\n$$\n\n|end\n",
+            "\\*escaped\\* ünïcödé 📝 `verbatim` {https://neorg.org}[link]\n\n\n",
+            "",
+            "\n",
+            "\r\n\r\n",
+            " \t\n  ",
+        ];
+
+        for example in examples {
+            let cst = parse_cst(example).unwrap();
+            assert_eq!(cst.text(), example);
+
+            let mut offset = 0;
+            for token in cst.tokens() {
+                assert_eq!(token.text_range().start, offset);
+                assert_eq!(&example[token.text_range()], token.text());
+                offset = token.text_range().end;
+            }
+            assert_eq!(offset, example.len());
+        }
+    }
+
+    #[test]
+    fn blank() {
+        let cst = parse_cst(" \r\n\n").unwrap();
+        assert_eq!(cst.children().count(), 0);
+        assert_eq!(
+            cst.tokens().map(|token| token.kind()).collect::<Vec<_>>(),
+            [
+                SyntaxKind::Whitespace,
+                SyntaxKind::Newline,
+                SyntaxKind::Newline
+            ]
+        );
+    }
+
+    #[test]
+    fn structure() {
+        let cst = parse_cst(
+            "* (x) Heading\r\n  - item\r\n  @code\r\n    raw\r\n  @end\r\n|example\r\ntext\r\n|end\r\n",
+        )
+        .unwrap();
+
+        assert_snapshot!(format!("{:?}", cst));
+    }
+
+    #[test]
+    fn replace() {
+        let source = "* Heading  \n  text\n** (x) Second\n";
+        let cst = parse_cst(source).unwrap();
+
+        let title = cst
+            .descendants()
+            .filter(|node| node.kind() == SyntaxKind::Title)
+            .nth(1)
+            .unwrap();
+        assert_eq!(title.text(), "Second");
+        assert_eq!(title.parent().unwrap().kind(), SyntaxKind::Heading);
+
+        let renamed = title.replace_with(GreenNode::new(
+            SyntaxKind::Title,
+            vec![GreenToken::new(SyntaxKind::Text, "Renamed").into()],
+        ));
+        assert_eq!(renamed.to_string(), "* Heading  \n  text\n** (x) Renamed\n");
+
+        let status = cst.tokens().find(|token| token.text() == "x").unwrap();
+        assert_eq!(
+            status
+                .replace_with(GreenToken::new(SyntaxKind::Whitespace, " "))
+                .to_string(),
+            "* Heading  \n  text\n** ( ) Second\n"
+        );
+    }
+}
@@ -0,0 +1,303 @@
+//! Programmatic edits to Norg source.
+//!
+//! Instead of printing a modified tree, which would lose formatting, edits are returned as a
+//! [`TextEdit`] that only touches the bytes that have to change.
+
+use std::ops::Range;
+
+use chumsky::Parser as _;
+
+use crate::{
+    error::NorgParseError, parse_tree, query::children, stage_1::stage_1_spanned, stage_2,
+    stage_2::NorgBlock, NorgAST, TodoStatus,
+};
+
+/// A replacement of a range of bytes in the source.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct TextEdit {
+    /// The byte range to replace, empty for insertions.
+    pub range: Range<usize>,
+    pub replacement: String,
+}
+
+impl TextEdit {
+    /// Applies the edit to the source it was created for.
+    pub fn apply(&self, source: &str) -> String {
+        let mut result = source.to_string();
+        result.replace_range(self.range.clone(), &self.replacement);
+        result
+    }
+}
+
+/// Returns `true` if the section of an extension (the text between two `|`) is a todo status.
+pub(crate) fn is_todo_section(section: &str) -> bool {
+    match section.chars().next() {
+        Some(' ' | '=' | '!' | '+' | '-' | '_') => true,
+        Some('x' | '?') => section.trim_end().len() == 1,
+        _ => false,
+    }
+}
+
+/// Creates the edit that sets the todo status inside of the given extension section, which is
+/// a byte range including the parentheses, or empty if there is no extension section.
+fn todo_edit(source: &str, range: Range<usize>, status: &TodoStatus) -> TextEdit {
+    if range.is_empty() {
+        return TextEdit {
+            range,
+            replacement: format!("({}) ", status),
+        };
+    }
+
+    let inner = range.start + 1..range.end - 1;
+    let mut start = inner.start;
+    for section in source[inner.clone()].split('|') {
+        let end = start + section.len();
+        if is_todo_section(section) {
+            return TextEdit {
+                range: start..end,
+                replacement: status.to_string(),
+            };
+        }
+        start = end + 1;
+    }
+
+    // There are other extensions, but no todo status yet.
+    TextEdit {
+        range: inner.start..inner.start,
+        replacement: format!("{}|", status),
+    }
+}
+
+/// Returns the byte ranges of the extension sections of all headings and nestable detached
+/// modifiers, in document order. See [`todo_edit`] for what a range covers.
+fn extension_ranges(source: &str) -> Result<Vec<Range<usize>>, NorgParseError> {
+    let (tokens, spans): (Vec<_>, Vec<_>) = stage_1_spanned().parse(source)?.into_iter().unzip();
+    let blocks = stage_2().parse(tokens)?;
+
+    // Token spans are counted in characters, edits in bytes.
+    let bytes: Vec<_> = source
+        .char_indices()
+        .map(|(i, _)| i)
+        .chain([source.len()])
+        .collect();
+    let to_bytes = |range: &Range<usize>| {
+        if range.is_empty() {
+            let start = bytes[spans[range.start].start];
+            start..start
+        } else {
+            bytes[spans[range.start].start]..bytes[spans[range.end - 1].end]
+        }
+    };
+
+    Ok(blocks
+        .iter()
+        .filter_map(|block| match block {
+            NorgBlock::Heading { extension_span, .. }
+            | NorgBlock::NestableDetachedModifier { extension_span, .. } => {
+                Some(to_bytes(extension_span))
+            }
+            _ => None,
+        })
+        .collect())
+}
+
+fn is_item(node: &NorgAST) -> bool {
+    matches!(
+        node,
+        NorgAST::Heading { .. } | NorgAST::NestableDetachedModifier { .. }
+    )
+}
+
+/// Counts the headings and nestable detached modifiers in the given nodes and all their
+/// descendants.
+fn count_items(nodes: &[NorgAST]) -> usize {
+    nodes
+        .iter()
+        .map(|node| usize::from(is_item(node)) + count_items(children(node)))
+        .sum()
+}
+
+/// Returns how many headings and nestable detached modifiers come before the one at the given
+/// path, in document order.
+fn item_index(nodes: &[NorgAST], path: &[usize]) -> Option<usize> {
+    let (&i, rest) = path.split_first()?;
+    let node = nodes.get(i)?;
+    let before = count_items(&nodes[..i]);
+
+    if rest.is_empty() {
+        is_item(node).then_some(before)
+    } else {
+        Some(before + usize::from(is_item(node)) + item_index(children(node), rest)?)
+    }
+}
+
+/// Creates an edit that sets the todo status of the heading or list item (or quote) on the given
+/// line.
+///
+/// Only the status inside of the extension section, e.g. the `x` in `- (x|# A) task`, is
+/// replaced. Items without a status get one, and items without an extension section get a
+/// new `( )` section. Everything else stays byte-for-byte unchanged.
+///
+/// # Arguments
+///
+/// * `source` - The source of the document.
+/// * `line` - The zero-based index of the line the heading or list item starts on.
+/// * `status` - The new status.
+///
+/// # Returns
+///
+/// * `Ok(Some(TextEdit))` with the edit to apply to `source`.
+/// * `Ok(None)` if there is no heading or nestable detached modifier on that line.
+/// * `Err(NorgParseError)` if the document can't be parsed.
+pub fn set_todo_status(
+    source: &str,
+    line: usize,
+    status: &TodoStatus,
+) -> Result<Option<TextEdit>, NorgParseError> {
+    Ok(extension_ranges(source)?
+        .into_iter()
+        .find(|range| source[..range.start].matches('\n').count() == line)
+        .map(|range| todo_edit(source, range, status)))
+}
+
+/// Creates an edit that sets the todo status of the heading or list item (or quote) at the given
+/// path in the tree, like [`set_todo_status`].
+///
+/// # Arguments
+///
+/// * `source` - The source of the document.
+/// * `path` - Indices leading from the root of the tree built by [`crate::parse_tree`] to the
+///   heading or list item, e.g. [`crate::agenda::Task::path`].
+/// * `status` - The new status.
+///
+/// # Returns
+///
+/// * `Ok(Some(TextEdit))` with the edit to apply to `source`.
+/// * `Ok(None)` if there is no heading or nestable detached modifier at that path.
+/// * `Err(NorgParseError)` if the document can't be parsed.
+pub fn set_todo_status_by_path(
+    source: &str,
+    path: &[usize],
+    status: &TodoStatus,
+) -> Result<Option<TextEdit>, NorgParseError> {
+    let Some(index) = item_index(&parse_tree(source)?, path) else {
+        return Ok(None);
+    };
+
+    Ok(extension_ranges(source)?
+        .into_iter()
+        .nth(index)
+        .map(|range| todo_edit(source, range, status)))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        agenda::tasks,
+        edit::{set_todo_status, set_todo_status_by_path},
+        parse_tree, TodoStatus,
+    };
+
+    fn set(source: &str, line: usize, status: TodoStatus) -> String {
+        set_todo_status(source, line, &status)
+            .unwrap()
+            .unwrap()
+            .apply(source)
+    }
+
+    #[test]
+    fn replaces_status() {
+        let source = "* Tasks\n  - ( ) write tests\n  - (!|# A|< Feb 1) ship it\n";
+
+        assert_eq!(
+            set(source, 1, TodoStatus::Done),
+            "* Tasks\n  - (x) write tests\n  - (!|# A|< Feb 1) ship it\n"
+        );
+        assert_eq!(
+            set(source, 2, TodoStatus::Recurring(Some("Friday".to_string()))),
+            "* Tasks\n  - ( ) write tests\n  - (+ Friday|# A|< Feb 1) ship it\n"
+        );
+        assert_eq!(
+            set_todo_status(source, 1, &TodoStatus::Done)
+                .unwrap()
+                .unwrap()
+                .range,
+            13..14
+        );
+    }
+
+    #[test]
+    fn inserts_status() {
+        assert_eq!(
+            set("* Heading\n- plain item\n", 0, TodoStatus::Pending),
+            "* (-) Heading\n- plain item\n"
+        );
+        assert_eq!(
+            set("* Heading\n- plain item\n", 1, TodoStatus::Undone),
+            "* Heading\n- ( ) plain item\n"
+        );
+        assert_eq!(
+            set("** (# B|< 1 Jan) prioritised\n", 0, TodoStatus::Urgent),
+            "** (!|# B|< 1 Jan) prioritised\n"
+        );
+        assert_eq!(
+            set_todo_status("* Heading\n  just text\n", 1, &TodoStatus::Done).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn keeps_everything_else() {
+        let source = "* Ünïcode 📝\r\n  - (x)   spaced   out  \r\n  -- (_) nested\r\n";
+
+        assert_eq!(
+            set(source, 1, TodoStatus::Undone),
+            "* Ünïcode 📝\r\n  - ( )   spaced   out  \r\n  -- (_) nested\r\n"
+        );
+        assert_eq!(
+            set(source, 2, TodoStatus::Paused),
+            "* Ünïcode 📝\r\n  - (x)   spaced   out  \r\n  -- (=) nested\r\n"
+        );
+    }
+
+    #[test]
+    fn agenda_tasks() {
+        let source = "* ( ) Heading\n  @code norg\n  - ( ) not a task\n  @end\n  #id 1\n  +attr\n  - ( ) tagged\n  |example\n  ** (!) in a tag\n  |end\n  - plain\n  -- (?) nested\n";
+        let found = tasks("file.norg", &parse_tree(source).unwrap());
+        assert_eq!(found.len(), 4);
+
+        for task in found {
+            let edited = set_todo_status_by_path(source, &task.path, &TodoStatus::Done)
+                .unwrap()
+                .unwrap()
+                .apply(source);
+            let statuses: Vec<_> = tasks("file.norg", &parse_tree(&edited).unwrap())
+                .into_iter()
+                .map(|other| (other.title, other.status))
+                .collect();
+
+            assert!(statuses.contains(&(task.title.clone(), TodoStatus::Done)));
+            assert_eq!(
+                statuses
+                    .iter()
+                    .filter(|(_, status)| *status == TodoStatus::Done)
+                    .count(),
+                1,
+                "task: {}",
+                task.title
+            );
+        }
+
+        assert_eq!(
+            set_todo_status_by_path(source, &[0, 0], &TodoStatus::Done).unwrap(),
+            None
+        );
+        assert_eq!(
+            set_todo_status_by_path(source, &[0, 3], &TodoStatus::Done)
+                .unwrap()
+                .unwrap()
+                .apply(source),
+            source.replace("- plain", "- (x) plain")
+        );
+    }
+}
@@ -10,6 +10,9 @@ pub enum NorgParseError {
     Stage3(Vec<Simple<NorgBlock>>),
     Stage4(Vec<Simple<NorgASTFlat>>),
     Meta(Simple<char>),
+    Query(Vec<Simple<char>>),
+    Config(String),
+    Io(std::io::Error),
 }
 
 impl From<Vec<Simple<char>>> for NorgParseError {
@@ -41,3 +44,9 @@ impl From<Simple<char>> for NorgParseError {
         NorgParseError::Meta(error)
     }
 }
+
+impl From<std::io::Error> for NorgParseError {
+    fn from(error: std::io::Error) -> Self {
+        NorgParseError::Io(error)
+    }
+}
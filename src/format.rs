@@ -0,0 +1,798 @@
+//! A formatter that rewrites Norg documents in a canonical style.
+//!
+//! The document is printed back from its flat AST, so everything the parser treats as
+//! insignificant (indentation, extra spaces, line breaks inside of paragraphs, blank lines) is
+//! normalised. Verbatim ranged tags are the exception: their content is copied from the source,
+//! and only moved by as much as the `@tag` and `@end` lines are re-indented. That way the content
+//! relative to the `@` stays the same.
+//!
+//! Tables are not aligned. A Norg table cell is a `: A1` modifier of its own, which names its
+//! position explicitly, so there are no columns in the source to line up. Table cells are
+//! formatted like the other rangeable detached modifiers.
+
+use std::borrow::Cow;
+
+use textwrap::{core::Word, wrap_algorithms::wrap_first_fit};
+
+use crate::{
+    error::NorgParseError, parse, CarryoverTag, DelimitingModifier, DetachedModifierExtension,
+    LinkTarget, NorgASTFlat, ParagraphSegment, ParagraphSegmentToken,
+};
+
+/// Options for the formatter.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FormatOptions {
+    /// The width to wrap paragraphs at, including indentation. Paragraphs are joined into a
+    /// single line if this is `None`.
+    pub width: Option<usize>,
+    /// The number of spaces to indent content with per heading level.
+    pub indent: usize,
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        Self {
+            width: Some(80),
+            indent: 2,
+        }
+    }
+}
+
+/// Parts of the source that aren't preserved in the AST, in document order.
+#[derive(Default)]
+struct SourceTags<'a> {
+    /// The character (`|` or `=`) that opened each ranged tag.
+    ranged: Vec<char>,
+    /// The indentation (in characters) and raw lines of each verbatim ranged tag.
+    verbatim: Vec<(usize, Vec<&'a str>)>,
+}
+
+impl<'a> SourceTags<'a> {
+    fn scan(source: &'a str) -> Self {
+        let mut tags = Self::default();
+        let mut verbatim: Option<(usize, Vec<&str>)> = None;
+
+        for line in source.lines() {
+            let text = line.trim_start();
+
+            if let Some((indentation, lines)) = &mut verbatim {
+                if text.trim_end() == "@end" {
+                    tags.verbatim.push((*indentation, std::mem::take(lines)));
+                    verbatim = None;
+                } else {
+                    lines.push(line);
+                }
+                continue;
+            }
+
+            let mut chars = text.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c @ ('@' | '|' | '=')), Some(next))
+                    if !next.is_whitespace()
+                        && next != c
+                        && text[1..].split_whitespace().next() != Some("end") =>
+                {
+                    if c == '@' {
+                        let indentation = line[..line.len() - text.len()].chars().count();
+                        verbatim = Some((indentation, Vec::new()));
+                    } else {
+                        tags.ranged.push(c);
+                    }
+                }
+                _ => (),
+            }
+        }
+
+        tags
+    }
+}
+
+/// Moves a line of verbatim content from a tag indented by `from` characters to one indented by
+/// `to`. Lines with less indentation than a tag is moved to the left lose what they have.
+fn reindent(line: &str, from: usize, to: usize) -> Cow<'_, str> {
+    if line.trim().is_empty() || from == to {
+        Cow::Borrowed(line)
+    } else if from < to {
+        Cow::Owned(" ".repeat(to - from) + line)
+    } else {
+        let indentation: usize = line
+            .chars()
+            .take(from - to)
+            .take_while(|c| c.is_whitespace())
+            .map(char::len_utf8)
+            .sum();
+        Cow::Borrowed(&line[indentation..])
+    }
+}
+
+/// Counts the ranged tags and verbatim ranged tags in the AST.
+fn count_tags(ast: &[NorgASTFlat]) -> (usize, usize) {
+    ast.iter().fold((0, 0), |(ranged, verbatim), node| {
+        let (inner_ranged, inner_verbatim) = match node {
+            NorgASTFlat::RangedTag { content, .. }
+            | NorgASTFlat::RangeableDetachedModifier { content, .. } => count_tags(content),
+            NorgASTFlat::CarryoverTag { next_object, .. } => {
+                count_tags(std::slice::from_ref(next_object))
+            }
+            _ => (0, 0),
+        };
+        let (own_ranged, own_verbatim) = match node {
+            NorgASTFlat::RangedTag { .. } => (1, 0),
+            NorgASTFlat::VerbatimRangedTag { .. } => (0, 1),
+            _ => (0, 0),
+        };
+
+        (
+            ranged + inner_ranged + own_ranged,
+            verbatim + inner_verbatim + own_verbatim,
+        )
+    })
+}
+
+fn push_token(out: &mut String, token: &ParagraphSegmentToken) {
+    match token {
+        ParagraphSegmentToken::Text(text) => out.push_str(text),
+        ParagraphSegmentToken::Whitespace => out.push(' '),
+        ParagraphSegmentToken::Special(c) => out.push(*c),
+        ParagraphSegmentToken::Escape(c) => {
+            out.push('\\');
+            out.push(*c);
+        }
+    }
+}
+
+fn push_link_target(out: &mut String, target: &LinkTarget) {
+    let (modifier, content) = match target {
+        LinkTarget::Heading { level, title } => ("*".repeat(*level as usize), title),
+        LinkTarget::Footnote(content) => ("^".to_string(), content),
+        LinkTarget::Definition(content) => ("$".to_string(), content),
+        LinkTarget::Generic(content) => ("#".to_string(), content),
+        LinkTarget::Wiki(content) => ("?".to_string(), content),
+        LinkTarget::Extendable(content) => ("=".to_string(), content),
+        LinkTarget::Path(text) => return push_str_all(out, &["/ ", text]),
+        LinkTarget::Timestamp(text) => return push_str_all(out, &["@ ", text]),
+        LinkTarget::Url(text) => return out.push_str(text),
+    };
+
+    out.push_str(&modifier);
+    out.push(' ');
+    push_segments(out, content);
+}
+
+fn push_str_all(out: &mut String, parts: &[&str]) {
+    parts.iter().for_each(|part| out.push_str(part));
+}
+
+fn push_bracketed(out: &mut String, open: char, content: &[ParagraphSegment], close: char) {
+    out.push(open);
+    push_segments(out, content);
+    out.push(close);
+}
+
+fn push_segment(out: &mut String, segment: &ParagraphSegment) {
+    match segment {
+        ParagraphSegment::Token(token) => push_token(out, token),
+        ParagraphSegment::AttachedModifier {
+            modifier_type,
+            content,
+        } => push_bracketed(out, *modifier_type, content, *modifier_type),
+        ParagraphSegment::Link {
+            filepath,
+            targets,
+            description,
+        } => {
+            out.push('{');
+            if let Some(filepath) = filepath {
+                push_str_all(out, &[":", filepath, ":"]);
+            }
+            targets
+                .iter()
+                .for_each(|target| push_link_target(out, target));
+            out.push('}');
+            if let Some(description) = description {
+                push_bracketed(out, '[', description, ']');
+            }
+        }
+        ParagraphSegment::AnchorDefinition { content, target } => {
+            push_bracketed(out, '[', content, ']');
+            push_segment(out, target);
+        }
+        ParagraphSegment::Anchor {
+            content,
+            description,
+        } => {
+            push_bracketed(out, '[', content, ']');
+            if let Some(description) = description {
+                push_bracketed(out, '[', description, ']');
+            }
+        }
+        ParagraphSegment::InlineLinkTarget(content) => push_bracketed(out, '<', content, '>'),
+        ParagraphSegment::InlineVerbatim(tokens) => {
+            out.push('`');
+            tokens.iter().for_each(|token| push_token(out, token));
+            out.push('`');
+        }
+        ParagraphSegment::InlineMath(tokens) => {
+            out.push('$');
+            tokens.iter().for_each(|token| push_token(out, token));
+            out.push('$');
+        }
+        // Intermediate results of the paragraph parser, which don't make it into the AST.
+        ParagraphSegment::AttachedModifierOpener((left, modifiers, right))
+        | ParagraphSegment::AttachedModifierOpenerFail((left, modifiers, right)) => {
+            if let Some(left) = left {
+                push_token(out, left);
+            }
+            out.extend(modifiers);
+            push_token(out, right);
+        }
+        ParagraphSegment::AttachedModifierCloserCandidate((left, modifiers, right)) => {
+            push_segment(out, left);
+            out.extend(modifiers);
+            if let Some(right) = right {
+                push_segment(out, right);
+            }
+        }
+        ParagraphSegment::AttachedModifierCloser(c) => out.push(*c),
+        ParagraphSegment::AttachedModifierCandidate {
+            modifier_type,
+            content,
+            closer,
+        } => {
+            out.push(*modifier_type);
+            push_segments(out, content);
+            if let Some(closer) = closer {
+                push_segment(out, closer);
+            }
+        }
+    }
+}
+
+fn push_segments(out: &mut String, segments: &[ParagraphSegment]) {
+    segments
+        .iter()
+        .for_each(|segment| push_segment(out, segment));
+}
+
+/// Prints inline content on a single line.
+pub(crate) fn inline(segments: &[ParagraphSegment]) -> String {
+    let mut out = String::new();
+    push_segments(&mut out, segments);
+    out
+}
+
+/// Splits a paragraph into the words it may be wrapped between.
+///
+/// A line break is only allowed before text starting with a letter or digit. Anything else at
+/// the start of a line could be read as the start of a new block, e.g. a `-` or a `*`.
+/// Paragraphs that start with a special character aren't split at all, as ending their first
+/// line early could turn it into a tag, e.g. `.tag parameter`.
+fn words(segments: &[ParagraphSegment]) -> Vec<String> {
+    if let Some(ParagraphSegment::Token(ParagraphSegmentToken::Special(_))) = segments.first() {
+        return vec![inline(segments)];
+    }
+
+    let mut words = vec![String::new()];
+
+    for (i, segment) in segments.iter().enumerate() {
+        let breakable = matches!(
+            segments.get(i + 1),
+            Some(ParagraphSegment::Token(ParagraphSegmentToken::Text(text)))
+                if text.starts_with(char::is_alphanumeric)
+        );
+
+        match segment {
+            ParagraphSegment::Token(ParagraphSegmentToken::Whitespace) if breakable => {
+                words.push(String::new())
+            }
+            _ => push_segment(words.last_mut().unwrap(), segment),
+        }
+    }
+
+    words
+}
+
+/// Prints a document from its flat AST.
+struct Printer<'a> {
+    options: &'a FormatOptions,
+    out: String,
+    ranged: std::vec::IntoIter<char>,
+    verbatim: std::vec::IntoIter<(usize, Vec<&'a str>)>,
+}
+
+impl Printer<'_> {
+    fn line(&mut self, indentation: usize, text: &str) {
+        if !text.is_empty() {
+            self.out.push_str(&" ".repeat(indentation));
+            self.out.push_str(text);
+        }
+        self.out.push('\n');
+    }
+
+    /// Prints a paragraph, starting with `prefix` and continuing with a hanging indent below it.
+    fn paragraph(&mut self, indentation: usize, prefix: &str, segments: &[ParagraphSegment]) {
+        let words = words(segments);
+        let hanging = indentation + prefix.chars().count();
+
+        let Some(width) = self.options.width else {
+            return self.line(indentation, &format!("{}{}", prefix, words.join(" ")));
+        };
+
+        let spaced: Vec<_> = words.iter().map(|word| format!("{} ", word)).collect();
+        let fragments: Vec<_> = spaced.iter().map(|word| Word::from(word)).collect();
+        // The prefix is as wide as the hanging indent, so all lines have the same width.
+        let widths = [width.saturating_sub(hanging).max(1) as f64];
+
+        // The lines are joined from the original words, as `Word` drops trailing whitespace,
+        // even when it's escaped.
+        let mut start = 0;
+        for (i, line) in wrap_first_fit(&fragments, &widths).into_iter().enumerate() {
+            let text = words[start..start + line.len()].join(" ");
+            start += line.len();
+            if i == 0 {
+                self.line(indentation, &format!("{}{}", prefix, text));
+            } else {
+                self.line(hanging, &text);
+            }
+        }
+    }
+
+    fn tag(&mut self, indentation: usize, prefix: char, name: &[String], parameters: &[String]) {
+        let mut text = format!("{}{}", prefix, name.join("."));
+        for parameter in parameters {
+            text.push(' ');
+            text.push_str(parameter);
+        }
+        self.line(indentation, &text);
+    }
+
+    /// Prints a list of objects, where `base` is the indentation of headings.
+    fn objects(&mut self, objects: &[NorgASTFlat], base: usize) {
+        let mut level = 0;
+
+        for (i, object) in objects.iter().enumerate() {
+            if i > 0 && needs_blank_line(&objects[i - 1], object) {
+                self.out.push('\n');
+            }
+            self.object(object, base, &mut level);
+        }
+    }
+
+    fn content_indentation(&self, base: usize, level: u16) -> usize {
+        if level == 0 {
+            base
+        } else {
+            base + self.options.indent * level as usize
+        }
+    }
+
+    fn object(&mut self, object: &NorgASTFlat, base: usize, level: &mut u16) {
+        let indentation = self.content_indentation(base, *level);
+
+        match object {
+            NorgASTFlat::Heading {
+                level: heading_level,
+                title,
+                extensions,
+            } => {
+                let indentation = self.content_indentation(base, heading_level - 1);
+                let text = format!(
+                    "{} {}",
+                    "*".repeat(*heading_level as usize),
+                    with_extensions(extensions, inline(title))
+                );
+                self.line(indentation, &text);
+                *level = *heading_level;
+            }
+            NorgASTFlat::Paragraph(segments) => self.paragraph(indentation, "", segments),
+            NorgASTFlat::NestableDetachedModifier {
+                modifier_type,
+                level: modifier_level,
+                extensions,
+                content,
+            } => {
+                let prefix = format!(
+                    "{} {}",
+                    modifier_type.to_string().repeat(*modifier_level as usize),
+                    with_extensions(extensions, String::new())
+                );
+                let indentation =
+                    indentation + self.options.indent * (*modifier_level as usize - 1);
+
+                match content.as_ref() {
+                    NorgASTFlat::Paragraph(segments) => {
+                        self.paragraph(indentation, &prefix, segments)
+                    }
+                    other => {
+                        self.line(indentation, prefix.trim_end());
+                        self.object(other, base, &mut { *level });
+                    }
+                }
+            }
+            NorgASTFlat::RangeableDetachedModifier {
+                modifier_type,
+                title,
+                extensions,
+                content,
+            } => {
+                let modifier = modifier_type.to_string();
+                let title = with_extensions(extensions, inline(title));
+
+                // A single paragraph doesn't need the ranged form.
+                if let [NorgASTFlat::Paragraph(segments)] = content.as_slice() {
+                    self.line(indentation, &format!("{} {}", modifier, title));
+                    self.paragraph(indentation, "", segments);
+                } else {
+                    self.line(indentation, &format!("{}{} {}", modifier, modifier, title));
+                    self.objects(content, indentation);
+                    self.line(indentation, &format!("{}{}", modifier, modifier));
+                }
+            }
+            NorgASTFlat::CarryoverTag {
+                tag_type,
+                name,
+                parameters,
+                next_object,
+            } => {
+                let prefix = match tag_type {
+                    CarryoverTag::Attribute => '+',
+                    CarryoverTag::Macro => '#',
+                };
+                let tag_indentation = match next_object.as_ref() {
+                    NorgASTFlat::Heading {
+                        level: heading_level,
+                        ..
+                    } => self.content_indentation(base, heading_level - 1),
+                    _ => indentation,
+                };
+                self.tag(tag_indentation, prefix, name, parameters);
+                self.object(next_object, base, level);
+            }
+            NorgASTFlat::VerbatimRangedTag {
+                name,
+                parameters,
+                content,
+            } => {
+                self.tag(indentation, '@', name, parameters);
+                match self.verbatim.next() {
+                    // Content is relative to the `@`, so it moves along with the tag.
+                    Some((original, lines)) => lines
+                        .iter()
+                        .for_each(|line| self.line(0, &reindent(line, original, indentation))),
+                    None => content
+                        .lines()
+                        .for_each(|line| self.line(indentation, line)),
+                }
+                self.line(indentation, "@end");
+            }
+            NorgASTFlat::RangedTag {
+                name,
+                parameters,
+                content,
+            } => {
+                let prefix = self.ranged.next().unwrap_or('|');
+                self.tag(indentation, prefix, name, parameters);
+                self.objects(content, indentation);
+                self.line(indentation, &format!("{}end", prefix));
+            }
+            NorgASTFlat::InfirmTag { name, parameters } => {
+                self.tag(indentation, '.', name, parameters)
+            }
+            NorgASTFlat::DelimitingModifier(DelimitingModifier::Strong) => {
+                *level = 0;
+                self.line(base, "===");
+            }
+            NorgASTFlat::DelimitingModifier(DelimitingModifier::Weak) => {
+                *level = level.saturating_sub(1);
+                let indentation = self.content_indentation(base, *level);
+                self.line(indentation, "---");
+            }
+            NorgASTFlat::DelimitingModifier(DelimitingModifier::HorizontalRule) => {
+                self.line(indentation, "___")
+            }
+        }
+    }
+}
+
+/// Returns `true` if a blank line goes between the two objects. Headings stick to their content
+/// and the items of a list stick together.
+fn needs_blank_line(previous: &NorgASTFlat, next: &NorgASTFlat) -> bool {
+    if let NorgASTFlat::CarryoverTag { next_object, .. } = previous {
+        return needs_blank_line(next_object, next);
+    }
+
+    !matches!(
+        (previous, next),
+        (NorgASTFlat::Heading { .. }, _)
+            | (
+                NorgASTFlat::NestableDetachedModifier { .. },
+                NorgASTFlat::NestableDetachedModifier { .. }
+            )
+    )
+}
+
+/// Prefixes a title with its detached modifier extension section, e.g. `(x|# A|< Feb 1) title`.
+///
+/// The title of a heading keeps the whitespace after the extension section, in which case no
+/// extra space is needed.
+fn with_extensions(extensions: &[DetachedModifierExtension], title: String) -> String {
+    if extensions.is_empty() {
+        return title;
+    }
+
+    let sections: Vec<_> = extensions
+        .iter()
+        .map(|extension| match extension {
+            DetachedModifierExtension::Todo(status) => status.to_string(),
+            DetachedModifierExtension::Priority(priority) => format!("# {}", priority),
+            DetachedModifierExtension::Timestamp(timestamp) => format!("@ {}", timestamp),
+            DetachedModifierExtension::DueDate(date) => format!("< {}", date),
+            DetachedModifierExtension::StartDate(date) => format!("> {}", date),
+        })
+        .collect();
+
+    let separator = if title.starts_with(' ') { "" } else { " " };
+    format!("({}){}{}", sections.join("|"), separator, title)
+}
+
+/// Rewrites a document in canonical style.
+///
+/// Heading content is indented by its level, list markers and extension sections are
+/// normalised, paragraphs are wrapped and blank lines are inserted between objects. The result
+/// parses to the same AST as the source, and formatting it again doesn't change it. The content
+/// of verbatim ranged tags is left alone.
+///
+/// # Arguments
+///
+/// * `source` - The source of the document.
+/// * `options` - The width to wrap at and the indentation to use.
+///
+/// # Returns
+///
+/// * `Ok(String)` with the formatted document.
+/// * `Err(NorgParseError)` if the document can't be parsed.
+pub fn format(source: &str, options: &FormatOptions) -> Result<String, NorgParseError> {
+    if source.trim().is_empty() {
+        return Ok(String::new());
+    }
+
+    let ast = parse(source)?;
+    let tags = SourceTags::scan(source);
+    let (ranged, verbatim) = count_tags(&ast);
+
+    // If the scan doesn't line up with the AST, fall back to what the AST knows.
+    let mut printer = Printer {
+        options,
+        out: String::new(),
+        ranged: if tags.ranged.len() == ranged {
+            tags.ranged
+        } else {
+            Vec::new()
+        }
+        .into_iter(),
+        verbatim: if tags.verbatim.len() == verbatim {
+            tags.verbatim
+        } else {
+            Vec::new()
+        }
+        .into_iter(),
+    };
+    printer.objects(&ast, 0);
+
+    // A paragraph at the end of the document can mean something else when followed by a
+    // newline, e.g. `.tag` is only an infirm tag if it ends its line.
+    if !source.ends_with('\n') && matches!(ast.last(), Some(NorgASTFlat::Paragraph(_))) {
+        printer.out.pop();
+    }
+
+    Ok(printer.out)
+}
+
+/// Prints a document from its flat AST in canonical style, e.g. one built with
+/// [`crate::builder::Doc`].
+///
+/// Unlike [`format()`], there is no source to take the opening character of ranged tags from, so
+/// they are all printed as standard ranged tags (`|tag`). Verbatim content is indented like the
+/// tag it belongs to.
+///
+/// # Arguments
+///
+/// * `ast` - The objects of the document.
+/// * `options` - The width to wrap at and the indentation to use.
+///
+/// # Returns
+///
+/// * `String` with the printed document.
+pub fn print(ast: &[NorgASTFlat], options: &FormatOptions) -> String {
+    let mut printer = Printer {
+        options,
+        out: String::new(),
+        ranged: Vec::new().into_iter(),
+        verbatim: Vec::new().into_iter(),
+    };
+    printer.objects(ast, 0);
+    printer.out
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        format::{format, FormatOptions},
+        parse,
+        verbatim::{verbatim_blocks, VerbatimBlock},
+    };
+
+    /// Formats the source and checks that the result is stable and means the same thing.
+    fn check(source: &str, options: &FormatOptions) -> String {
+        let formatted = format(source, options).unwrap();
+
+        assert_eq!(parse(&formatted).unwrap(), parse(source).unwrap());
+        assert_eq!(format(&formatted, options).unwrap(), formatted);
+        formatted
+    }
+
+    #[test]
+    fn canonical_style() {
+        let source = "
+* Heading
+content   with  *bold*
+text.
+   ** (x |#  Low|<   Feb 1)   Sub heading
+ - ( |#  A) item
+ --   nested {* Heading}[link]
+ ~~ ordered
+$ Term
+definition
+---
+back out
+===
+after
+";
+
+        assert_eq!(
+            check(source, &FormatOptions::default()),
+            "* Heading
+  content with *bold* text.
+
+  ** (x|# Low|< Feb 1) Sub heading
+    - ( |# A) item
+      -- nested {* Heading}[link]
+      ~~ ordered
+
+    $ Term
+    definition
+
+  ---
+
+  back out
+
+===
+
+after
+"
+        );
+    }
+
+    #[test]
+    fn wraps_paragraphs() {
+        let source = "* Heading\nthe quick brown fox jumps over -the- lazy dog\n- a list item that is long enough to wrap\n";
+        let options = FormatOptions {
+            width: Some(20),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            check(source, &options),
+            "* Heading
+  the quick brown
+  fox jumps
+  over -the- lazy
+  dog
+
+  - a list item that
+    is long enough
+    to wrap
+"
+        );
+        assert_eq!(
+            check(
+                source,
+                &FormatOptions {
+                    width: None,
+                    ..Default::default()
+                }
+            ),
+            "* Heading
+  the quick brown fox jumps over -the- lazy dog
+
+  - a list item that is long enough to wrap
+"
+        );
+    }
+
+    #[test]
+    fn keeps_verbatim_and_tags() {
+        let source = "
+* Code
+@code rust
+  fn main() {
+  \tprintln!(\"hi\");
+   }
+@end
+=example
+  #comment
+  - item
+=end
+|details
+text
+|end
+";
+
+        assert_eq!(
+            check(source, &FormatOptions::default()),
+            "* Code
+  @code rust
+    fn main() {
+    \tprintln!(\"hi\");
+     }
+  @end
+
+  =example
+  #comment
+  - item
+  =end
+
+  |details
+  text
+  |end
+"
+        );
+
+        let contents = |source: &str| {
+            verbatim_blocks(source)
+                .unwrap()
+                .iter()
+                .map(VerbatimBlock::content)
+                .collect::<Vec<_>>()
+        };
+        for source in [
+            source,
+            "- item
+      @code
+        indented
+       less
+
+      @end
+",
+        ] {
+            let formatted = format(source, &FormatOptions::default()).unwrap();
+            assert_eq!(contents(&formatted), contents(source), "{formatted}");
+        }
+    }
+
+    #[test]
+    fn round_trips() {
+        let examples = [
+            "Just a paragraph with `verbatim` and {https://neorg.org}[a link] and [anchor][text].",
+            "> quote\n>> nested quote\n\n- (+ Friday|@ 5pm) recurring\n",
+            ": A1\ncell\n\n^^ Footnote\nfirst\n\nsecond\n^^\n",
+            "+attribute value\n* Heading\n.toc\n___\n{:file:}[in a file] <target> \\*escaped\\*",
+            "* One\n** Two\n*** Three\ncontent\n---\nless\n---\neven less\n",
+            "  @document.meta\n  title: test\n  @end\n",
+            "escaped\\  space\n",
+        ];
+
+        for example in examples {
+            check(example, &FormatOptions::default());
+            check(
+                example,
+                &FormatOptions {
+                    width: Some(10),
+                    indent: 4,
+                },
+            );
+        }
+
+        assert_eq!(format(" \n\n", &FormatOptions::default()).unwrap(), "");
+    }
+}
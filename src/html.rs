@@ -0,0 +1,760 @@
+//! Renders documents as HTML.
+//!
+//! Headings become `<h1>` to `<h6>` with the same identifiers as in
+//! [`crate::outline::outline`], lists and quotes become `<ul>`, `<ol>` and `<blockquote>`,
+//! definitions become a `<dl>` and table cells a `<table>`. Footnotes are collected in a list at
+//! the end of the document, in the order they are referenced. `@code` blocks are rendered as
+//! `<pre><code class="language-...">`, which is what syntax highlighters expect, and `@math`
+//! blocks are wrapped in `\[` and `\]` for MathJax or KaTeX.
+//!
+//! Tags without an equivalent in HTML are dropped, apart from ranged and carryover tags whose
+//! content is kept. `#comment` tags and null modifiers (`%comment%`) are dropped with their
+//! content.
+
+use std::collections::VecDeque;
+
+use crate::{
+    metadata::{parse_metadata, NorgMeta},
+    outline::{outline, slugify, OutlineEntry, Slugs},
+    pandoc::{cell_position, plain, same_group, References},
+    visit::Visitor,
+    DelimitingModifier, DetachedModifierExtension, LinkTarget, NestableDetachedModifier, NorgAST,
+    ParagraphSegment, ParagraphSegmentToken, RangeableDetachedModifier, TodoStatus,
+};
+
+/// Options for the HTML renderer.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HtmlOptions {
+    /// Extra markup for the end of `<head>`, e.g. a `<style>` or `<link rel="stylesheet">`.
+    pub head: String,
+    /// Render a complete page. If `false`, only the content of `<body>` is rendered.
+    pub standalone: bool,
+}
+
+impl Default for HtmlOptions {
+    fn default() -> Self {
+        Self {
+            head: String::new(),
+            standalone: true,
+        }
+    }
+}
+
+/// Escapes the characters with a special meaning in HTML, both in text and in attributes.
+pub(crate) fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+fn element(name: &str, content: &str) -> String {
+    format!("<{name}>\n{content}\n</{name}>")
+}
+
+/// Renders an outline as nested lists of links.
+fn toc(entries: &[OutlineEntry]) -> String {
+    let items: Vec<_> = entries
+        .iter()
+        .map(|entry| {
+            let link = format!(
+                "<a href=\"#{}\">{}</a>",
+                escape(&entry.slug),
+                escape(entry.title.trim())
+            );
+            if entry.children.is_empty() {
+                format!("<li>{link}</li>")
+            } else {
+                format!("<li>{link}\n{}\n</li>", toc(&entry.children))
+            }
+        })
+        .collect();
+    element("ul", &items.join("\n"))
+}
+
+pub(crate) struct Renderer {
+    references: References,
+    pub(crate) slugs: Slugs,
+    outline: Vec<OutlineEntry>,
+    /// The titles and slugs of the outline's headings which haven't been rendered yet, in
+    /// document order. Their slugs are reserved up front, so inline link targets can't take them.
+    headings: VecDeque<(String, String)>,
+    /// The footnotes currently being rendered, so a footnote can't contain itself.
+    notes: Vec<String>,
+    /// The footnotes referenced so far, by title, with their rendered content.
+    footnotes: Vec<(String, String)>,
+    /// Where the content of `|notes` tags goes. If `None`, it is rendered like any other tag.
+    pub(crate) speaker_notes: Option<Vec<String>>,
+}
+
+impl Renderer {
+    pub(crate) fn new(ast: &[NorgAST]) -> Self {
+        let mut references = References::default();
+        ast.iter().for_each(|node| references.visit_node(node));
+
+        fn flatten(entries: &[OutlineEntry], headings: &mut VecDeque<(String, String)>) {
+            for entry in entries {
+                headings.push_back((entry.title.clone(), entry.slug.clone()));
+                flatten(&entry.children, headings);
+            }
+        }
+
+        let outline = outline(ast);
+        let mut headings = VecDeque::new();
+        flatten(&outline, &mut headings);
+        let mut slugs = Slugs::default();
+        headings.iter().for_each(|(_, slug)| slugs.reserve(slug));
+
+        Self {
+            references,
+            slugs,
+            outline,
+            headings,
+            notes: Vec::new(),
+            footnotes: Vec::new(),
+            speaker_notes: None,
+        }
+    }
+
+    pub(crate) fn inlines(&mut self, segments: &[ParagraphSegment]) -> String {
+        let mut out = String::new();
+        for segment in segments {
+            self.push_inline(&mut out, segment);
+        }
+        out
+    }
+
+    fn push_inline(&mut self, out: &mut String, segment: &ParagraphSegment) {
+        match segment {
+            ParagraphSegment::Token(token) => match token {
+                ParagraphSegmentToken::Text(text) => out.push_str(&escape(text)),
+                ParagraphSegmentToken::Whitespace => out.push(' '),
+                ParagraphSegmentToken::Special(c) | ParagraphSegmentToken::Escape(c) => {
+                    out.push_str(&escape(c.encode_utf8(&mut [0; 4])))
+                }
+            },
+            ParagraphSegment::AttachedModifier {
+                modifier_type,
+                content,
+            } => {
+                let name = match modifier_type {
+                    '*' => "strong",
+                    '/' => "em",
+                    '_' => "u",
+                    '-' => "s",
+                    // Null modifiers are comments.
+                    '%' => return,
+                    _ => return out.push_str(&self.inlines(content)),
+                };
+                let content = self.inlines(content);
+                out.push_str(&format!("<{name}>{content}</{name}>"));
+            }
+            ParagraphSegment::InlineVerbatim(tokens) => {
+                let code: String = tokens.iter().map(ToString::to_string).collect();
+                out.push_str(&format!("<code>{}</code>", escape(&code)));
+            }
+            ParagraphSegment::InlineMath(tokens) => {
+                let math: String = tokens.iter().map(ToString::to_string).collect();
+                out.push_str(&format!(
+                    "<span class=\"math inline\">\\({}\\)</span>",
+                    escape(&math)
+                ));
+            }
+            ParagraphSegment::Link {
+                filepath,
+                targets,
+                description,
+            } => {
+                let link = self.link(filepath, targets, description.as_deref());
+                out.push_str(&link);
+            }
+            ParagraphSegment::AnchorDefinition { content, target } => match target.as_ref() {
+                ParagraphSegment::Link {
+                    filepath, targets, ..
+                } => {
+                    let link = self.link(filepath, targets, Some(content));
+                    out.push_str(&link);
+                }
+                _ => out.push_str(&self.inlines(content)),
+            },
+            ParagraphSegment::Anchor {
+                content,
+                description,
+            } => {
+                let text = description.as_deref().unwrap_or(content);
+                match self.references.anchors.get(&plain(content)).cloned() {
+                    Some(ParagraphSegment::Link {
+                        filepath, targets, ..
+                    }) => {
+                        let link = self.link(&filepath, &targets, Some(text));
+                        out.push_str(&link);
+                    }
+                    _ => out.push_str(&self.inlines(text)),
+                }
+            }
+            ParagraphSegment::InlineLinkTarget(content) => {
+                let id = self.slugs.unique(&plain(content));
+                let content = self.inlines(content);
+                out.push_str(&format!("<span id=\"{}\">{content}</span>", escape(&id)));
+            }
+            // Intermediate results of the paragraph parser, which don't make it into the AST.
+            _ => (),
+        }
+    }
+
+    fn link(
+        &mut self,
+        filepath: &Option<String>,
+        targets: &[LinkTarget],
+        description: Option<&[ParagraphSegment]>,
+    ) -> String {
+        let description = description.map(|description| self.inlines(description));
+        let anchor = |href: &str, text: &str| format!("<a href=\"{}\">{text}</a>", escape(href));
+
+        match targets.first() {
+            Some(LinkTarget::Footnote(title)) if filepath.is_none() => {
+                if let Some(note) = self.note(&plain(title)) {
+                    return note;
+                }
+                let text = description.unwrap_or_else(|| self.inlines(title));
+                anchor(&format!("#{}", slugify(&plain(title))), &text)
+            }
+            Some(LinkTarget::Timestamp(timestamp)) => {
+                let text = description.unwrap_or_else(|| escape(timestamp));
+                format!("<time>{text}</time>")
+            }
+            Some(LinkTarget::Url(url)) => {
+                let text = description.unwrap_or_else(|| escape(url));
+                anchor(url, &text)
+            }
+            Some(LinkTarget::Path(path)) => {
+                let text = description.unwrap_or_else(|| escape(path));
+                anchor(path, &text)
+            }
+            Some(
+                LinkTarget::Heading { title, .. }
+                | LinkTarget::Footnote(title)
+                | LinkTarget::Definition(title)
+                | LinkTarget::Generic(title)
+                | LinkTarget::Wiki(title)
+                | LinkTarget::Extendable(title),
+            ) => {
+                let text = description.unwrap_or_else(|| self.inlines(title));
+                let slug = slugify(&plain(title));
+                let file = filepath.as_deref().unwrap_or_default();
+                anchor(&format!("{file}#{slug}"), &text)
+            }
+            None => {
+                let file = filepath.clone().unwrap_or_default();
+                let text = description.unwrap_or_else(|| escape(&file));
+                anchor(&file, &text)
+            }
+        }
+    }
+
+    /// A reference to a footnote, which is rendered the first time it is referenced.
+    fn note(&mut self, title: &str) -> Option<String> {
+        if self.notes.iter().any(|note| note == title) {
+            return None;
+        }
+
+        let number = match self.footnotes.iter().position(|(note, _)| note == title) {
+            Some(i) => i + 1,
+            None => {
+                let content = self.references.footnotes.get(title)?.clone();
+
+                self.notes.push(title.to_string());
+                let blocks = self.blocks(&content);
+                self.notes.pop();
+
+                self.footnotes.push((title.to_string(), blocks.join("\n")));
+                self.footnotes.len()
+            }
+        };
+
+        let id = escape(&slugify(title));
+        Some(format!(
+            "<sup class=\"footnote-ref\"><a href=\"#fn-{id}\">{number}</a></sup>"
+        ))
+    }
+
+    /// Renders the footnotes referenced since the last call, if there are any.
+    pub(crate) fn footnotes(&mut self) -> Option<String> {
+        if self.footnotes.is_empty() {
+            return None;
+        }
+
+        let items: Vec<_> = self
+            .footnotes
+            .drain(..)
+            .map(|(title, content)| {
+                format!(
+                    "<li id=\"fn-{}\">\n{content}\n</li>",
+                    escape(&slugify(&title))
+                )
+            })
+            .collect();
+        Some(format!(
+            "<div class=\"footnotes\">\n<hr>\n{}\n</div>",
+            element("ol", &items.join("\n"))
+        ))
+    }
+
+    /// Renders nodes into blocks, which are separated by line breaks.
+    pub(crate) fn blocks<'a>(
+        &mut self,
+        nodes: impl IntoIterator<Item = &'a NorgAST>,
+    ) -> Vec<String> {
+        let mut out = Vec::new();
+        let mut nodes = nodes.into_iter().peekable();
+
+        while let Some(first) = nodes.next() {
+            let mut group = vec![first];
+            while let Some(node) = nodes.next_if(|node| same_group(first, node)) {
+                group.push(node);
+            }
+            self.push_group(&mut out, &group);
+        }
+
+        out
+    }
+
+    /// Renders the text of a list item, quote or cell without a `<p>` if it is a single
+    /// paragraph.
+    fn compact(&mut self, nodes: &[NorgAST]) -> String {
+        match nodes {
+            [NorgAST::Paragraph(segments)] => self.inlines(segments).trim().to_string(),
+            nodes => self.blocks(nodes).join("\n"),
+        }
+    }
+
+    /// Renders a list item as an `<li>`, with a checkbox for a task.
+    fn item(&mut self, node: &NorgAST) -> String {
+        let NorgAST::NestableDetachedModifier {
+            extensions,
+            text,
+            content,
+            ..
+        } = node
+        else {
+            return String::new();
+        };
+
+        let checkbox = match extensions.iter().find_map(|extension| match extension {
+            DetachedModifierExtension::Todo(status) => Some(status),
+            _ => None,
+        }) {
+            Some(TodoStatus::Done) => "<input type=\"checkbox\" disabled checked> ",
+            Some(_) => "<input type=\"checkbox\" disabled> ",
+            None => "",
+        };
+
+        let text = self.compact(std::slice::from_ref(text.as_ref()));
+        let content = self.blocks(content);
+        if content.is_empty() {
+            format!("<li>{checkbox}{text}</li>")
+        } else {
+            format!("<li>{checkbox}{text}\n{}\n</li>", content.join("\n"))
+        }
+    }
+
+    fn push_group(&mut self, out: &mut Vec<String>, group: &[&NorgAST]) {
+        match group[0] {
+            NorgAST::NestableDetachedModifier { modifier_type, .. } => {
+                let name = match modifier_type {
+                    NestableDetachedModifier::UnorderedList => "ul",
+                    NestableDetachedModifier::OrderedList => "ol",
+                    NestableDetachedModifier::Quote => {
+                        let paragraphs: Vec<_> = group
+                            .iter()
+                            .flat_map(|node| match node {
+                                NorgAST::NestableDetachedModifier { text, content, .. } => {
+                                    let mut blocks =
+                                        self.blocks(std::slice::from_ref(text.as_ref()));
+                                    blocks.extend(self.blocks(content));
+                                    blocks
+                                }
+                                _ => Vec::new(),
+                            })
+                            .collect();
+                        return out.push(element("blockquote", &paragraphs.join("\n")));
+                    }
+                };
+                let items: Vec<_> = group.iter().map(|node| self.item(node)).collect();
+                out.push(element(name, &items.join("\n")));
+            }
+            NorgAST::RangeableDetachedModifier {
+                modifier_type: RangeableDetachedModifier::Definition,
+                ..
+            } => {
+                let definitions: Vec<_> = group
+                    .iter()
+                    .filter_map(|node| match node {
+                        NorgAST::RangeableDetachedModifier { title, content, .. } => {
+                            Some(self.description_item(title, content))
+                        }
+                        _ => None,
+                    })
+                    .collect();
+                out.push(element("dl", &definitions.join("\n")));
+            }
+            NorgAST::RangeableDetachedModifier {
+                modifier_type: RangeableDetachedModifier::Table,
+                ..
+            } => self.push_table(out, group),
+            // Footnotes are collected where they are referenced.
+            NorgAST::RangeableDetachedModifier {
+                modifier_type: RangeableDetachedModifier::Footnote,
+                ..
+            } => (),
+            node => self.push_block(out, node),
+        }
+    }
+
+    fn description_item(&mut self, title: &[ParagraphSegment], content: &[NorgAST]) -> String {
+        let title = self.inlines(title);
+        let content = self.compact(content);
+        format!("<dt>{}</dt>\n<dd>{content}</dd>", title.trim())
+    }
+
+    /// Turns table cells like `: A1` into a `<table>`. If a cell doesn't name a position, the
+    /// cells are rendered as a `<dl>` instead.
+    fn push_table(&mut self, out: &mut Vec<String>, group: &[&NorgAST]) {
+        let cells: Vec<_> = group
+            .iter()
+            .filter_map(|node| match node {
+                NorgAST::RangeableDetachedModifier { title, content, .. } => Some((title, content)),
+                _ => None,
+            })
+            .collect();
+
+        let positions: Option<Vec<_>> = cells
+            .iter()
+            .map(|(title, _)| cell_position(&plain(title)))
+            .collect();
+        let Some(positions) = positions else {
+            let items: Vec<_> = cells
+                .into_iter()
+                .map(|(title, content)| self.description_item(title, content))
+                .collect();
+            return out.push(element("dl", &items.join("\n")));
+        };
+
+        let rows = positions.iter().map(|(row, _)| row + 1).max().unwrap_or(0);
+        let columns = positions
+            .iter()
+            .map(|(_, column)| column + 1)
+            .max()
+            .unwrap_or(0);
+        let mut grid = vec![vec![String::new(); columns]; rows];
+        for ((_, content), (row, column)) in cells.into_iter().zip(positions) {
+            grid[row][column] = self.compact(content);
+        }
+
+        let rows: Vec<_> = grid
+            .into_iter()
+            .map(|row| {
+                let cells: String = row.iter().map(|cell| format!("<td>{cell}</td>")).collect();
+                format!("<tr>{cells}</tr>")
+            })
+            .collect();
+        out.push(element("table", &rows.join("\n")));
+    }
+
+    /// Renders the title of a heading, and hands out its identifier.
+    pub(crate) fn heading(&mut self, level: u16, title: &[ParagraphSegment]) -> String {
+        let text = plain(title);
+        // Headings which aren't rendered, e.g. commented out ones, are skipped.
+        let id = match self.headings.iter().position(|(next, _)| *next == text) {
+            Some(i) => self.headings.drain(..=i).next_back().unwrap().1,
+            // Headings outside of the outline, e.g. in an `|example` tag.
+            None => self.slugs.unique(&text),
+        };
+        let level = level.clamp(1, 6);
+        let title = self.inlines(title);
+        format!(
+            "<h{level} id=\"{}\">{}</h{level}>",
+            escape(&id),
+            title.trim()
+        )
+    }
+
+    fn push_block(&mut self, out: &mut Vec<String>, node: &NorgAST) {
+        match node {
+            NorgAST::Paragraph(segments) => {
+                out.push(format!("<p>{}</p>", self.inlines(segments).trim()))
+            }
+            NorgAST::Heading {
+                level,
+                title,
+                content,
+                ..
+            } => {
+                let heading = self.heading(*level, title);
+                out.push(heading);
+                out.extend(self.blocks(content));
+            }
+            NorgAST::CarryoverTag {
+                name, next_object, ..
+            } => {
+                if name != &["comment"] {
+                    out.extend(self.blocks([next_object.as_ref()]));
+                }
+            }
+            NorgAST::VerbatimRangedTag {
+                name,
+                parameters,
+                content,
+            } => {
+                let content = escape(content.trim_end_matches('\n'));
+                match name
+                    .iter()
+                    .map(String::as_str)
+                    .collect::<Vec<_>>()
+                    .as_slice()
+                {
+                    ["code"] => match parameters.first() {
+                        Some(language) => out.push(format!(
+                            "<pre><code class=\"language-{}\">{content}</code></pre>",
+                            escape(language)
+                        )),
+                        None => out.push(format!("<pre><code>{content}</code></pre>")),
+                    },
+                    ["math"] => out.push(format!(
+                        "<div class=\"math\">\\[\n{}\n\\]</div>",
+                        content.trim()
+                    )),
+                    ["document", "meta"] | ["comment"] => (),
+                    _ => out.push(format!("<pre>{content}</pre>")),
+                }
+            }
+            NorgAST::RangedTag { name, content, .. } => {
+                if name == &["notes"] && self.speaker_notes.is_some() {
+                    let notes = self.blocks(content);
+                    self.speaker_notes.get_or_insert_default().extend(notes);
+                } else if name != &["comment"] {
+                    out.extend(self.blocks(content));
+                }
+            }
+            NorgAST::InfirmTag { name, parameters } => {
+                match (name.as_slice(), parameters.first()) {
+                    ([image], Some(source)) if image == "image" => {
+                        out.push(format!("<img src=\"{}\" alt=\"\">", escape(source)))
+                    }
+                    ([name], _) if name == "toc" && !self.outline.is_empty() => out.push(format!(
+                        "<nav class=\"toc\">\n{}\n</nav>",
+                        toc(&self.outline)
+                    )),
+                    _ => (),
+                }
+            }
+            NorgAST::DelimitingModifier(DelimitingModifier::HorizontalRule) => {
+                out.push(String::from("<hr>"))
+            }
+            NorgAST::DelimitingModifier(_) => (),
+            NorgAST::NestableDetachedModifier { .. }
+            | NorgAST::RangeableDetachedModifier { .. } => self.push_group(out, &[node]),
+        }
+    }
+}
+
+fn meta_string(meta: &NorgMeta) -> Option<String> {
+    match meta {
+        NorgMeta::Str(value) => Some(escape(value)),
+        NorgMeta::Num(value) => Some(value.to_string()),
+        NorgMeta::Array(values) => {
+            let values: Vec<_> = values.iter().filter_map(meta_string).collect();
+            Some(values.join(", "))
+        }
+        _ => None,
+    }
+}
+
+/// The title, authors and creation date from the metadata of a document, escaped for HTML.
+pub(crate) fn title_block(ast: &[NorgAST]) -> [Option<String>; 3] {
+    let Some(Ok(NorgMeta::Object(entries))) = ast.iter().find_map(|node| match node {
+        NorgAST::VerbatimRangedTag { name, content, .. } if name == &["document", "meta"] => {
+            Some(parse_metadata(content))
+        }
+        _ => None,
+    }) else {
+        return [None, None, None];
+    };
+
+    ["title", "authors", "created"].map(|key| entries.get(key).and_then(meta_string))
+}
+
+/// The `<header>` for the title block of a document, if it has a title.
+pub(crate) fn header(ast: &[NorgAST]) -> Option<String> {
+    let [title, authors, created] = title_block(ast);
+    let mut lines = vec![format!("<h1 class=\"title\">{}</h1>", title?)];
+    if let Some(authors) = authors {
+        lines.push(format!("<p class=\"author\">{authors}</p>"));
+    }
+    if let Some(created) = created {
+        lines.push(format!("<p class=\"date\">{created}</p>"));
+    }
+    Some(lines.join("\n"))
+}
+
+/// Wraps the content of `<head>` and `<body>` into a page.
+pub(crate) fn page(ast: &[NorgAST], head: &str, body: &str) -> String {
+    let [title, ..] = title_block(ast);
+
+    let mut out = String::from("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+    out.push_str("<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n");
+    if let Some(title) = title {
+        out.push_str(&format!("<title>{title}</title>\n"));
+    }
+    if !head.is_empty() {
+        out.push_str(head.trim_end());
+        out.push('\n');
+    }
+    out.push_str("</head>\n<body>\n");
+    if !body.is_empty() {
+        out.push_str(body);
+        out.push('\n');
+    }
+    out.push_str("</body>\n</html>\n");
+    out
+}
+
+/// Renders a document as HTML.
+///
+/// The title, authors and creation date from a top level `@document.meta` tag are rendered as
+/// a `<header>` and the title is used for `<title>`. Headings get the same identifiers as in
+/// [`crate::outline::outline`], so links to them point to the right place.
+///
+/// # Arguments
+///
+/// * `ast` - The document, as returned by [`crate::parse_tree`].
+/// * `options` - Extra markup for `<head>` and whether to render a complete page.
+///
+/// # Returns
+///
+/// * `String` containing the HTML.
+pub fn to_html(ast: &[NorgAST], options: &HtmlOptions) -> String {
+    let mut renderer = Renderer::new(ast);
+    let mut blocks = renderer.blocks(ast);
+    blocks.extend(renderer.footnotes());
+
+    if !options.standalone {
+        return blocks.into_iter().map(|block| block + "\n").collect();
+    }
+
+    if let Some(header) = header(ast) {
+        blocks.insert(0, element("header", &header));
+    }
+    page(ast, &options.head, &blocks.join("\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use insta::assert_snapshot;
+
+    use crate::{
+        html::{to_html, HtmlOptions},
+        parse_tree,
+    };
+
+    #[test]
+    fn document() {
+        let source = "@document.meta
+title: Research notes
+authors: [
+  Ada
+  Grace
+]
+@end
+
+.toc
+* (x) Introduction
+  Some *bold*, /italic/, _underlined_ and -struck- text with `<code>`, see {* Details}.
+  Costs 5$ & 10% of {https://neorg.org/a#b}[the budget]{^ 1}.
+  - ( ) first
+  -- nested
+  - (x) second
+  ~ one
+  > quoted
+** Details
+   $ Term
+   The definition
+   @code rust
+   fn main() -> Vec<u8> {}
+   @end
+   @math
+   e^{i\\pi} + 1 = 0
+   @end
+   : A1
+   a
+   : B1
+   b
+   : A2
+   c
+___
+^ 1
+The note
+";
+        assert_snapshot!(to_html(
+            &parse_tree(source).unwrap(),
+            &HtmlOptions::default()
+        ));
+    }
+
+    #[test]
+    fn options() {
+        let options = HtmlOptions {
+            standalone: false,
+            ..Default::default()
+        };
+        let ast = parse_tree("* One\n** One\n|notes\nSay hi\n|end\n").unwrap();
+        assert_eq!(
+            to_html(&ast, &options),
+            "<h1 id=\"one\">One</h1>\n<h2 id=\"one-1\">One</h2>\n<p>Say hi</p>\n"
+        );
+
+        let options = HtmlOptions {
+            head: String::from("<link rel=\"stylesheet\" href=\"style.css\">\n"),
+            ..Default::default()
+        };
+        assert_eq!(
+            to_html(&[], &options),
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n<link rel=\"stylesheet\" href=\"style.css\">\n</head>\n<body>\n</body>\n</html>\n"
+        );
+    }
+
+    #[test]
+    fn ids() {
+        let options = HtmlOptions {
+            standalone: false,
+            ..Default::default()
+        };
+        let ast = parse_tree("* A\n  |notes\n  n <b>\n  |end\n** B\n<a>\n").unwrap();
+
+        assert_eq!(
+            to_html(&ast, &options),
+            "<h1 id=\"a\">A</h1>\n<p>n <span id=\"b-1\">b</span></p>\n<h2 id=\"b\">B</h2>\n<p><span id=\"a-1\">a</span></p>\n"
+        );
+    }
+
+    #[test]
+    fn math() {
+        let options = HtmlOptions {
+            standalone: false,
+            ..Default::default()
+        };
+        let ast = parse_tree("text $a<b$\n").unwrap();
+
+        assert_eq!(
+            to_html(&ast, &options),
+            "<p>text <span class=\"math inline\">\\(a&lt;b\\)</span></p>\n"
+        );
+    }
+}
@@ -0,0 +1,782 @@
+//! A stable, versioned JSON representation of a document, for tools that aren't written in Rust.
+//!
+//! The AST types implement `Serialize` and `Deserialize` too, but their shape follows serde's
+//! default enum representation and changes whenever a variant does. The types in this module
+//! describe the format documented in `schema/norg-ast.schema.json` instead:
+//!
+//! * Every node is an object with a `type` field in `snake_case`, e.g. `"heading"`.
+//! * Block nodes have a `span` with the `start` and `end` byte offsets of the node in the source,
+//!   covering everything nested below it. Inline nodes have no span.
+//! * Nodes that contain other nodes list them in a `children` array.
+//! * Optional fields are always present and `null` when they have no value.
+//!
+//! The format is versioned by [`SCHEMA_VERSION`], which is bumped on every incompatible change.
+//! Adding a node type or a field is not considered incompatible.
+
+use std::{collections::BTreeMap, ops::Range};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    cst::{BlockSpans, SyntaxKind},
+    error::NorgParseError,
+    format,
+    metadata::{parse_metadata, NorgMeta},
+    parse_tree, CarryoverTag, DelimitingModifier, DetachedModifierExtension, LinkTarget,
+    NestableDetachedModifier, NorgAST, ParagraphSegment, ParagraphSegmentToken,
+    RangeableDetachedModifier, TodoStatus,
+};
+
+/// The version of the JSON format, stored in [`Document::version`].
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// The JSON Schema describing the format.
+pub const SCHEMA: &str = include_str!("../schema/norg-ast.schema.json");
+
+/// The root of the JSON representation.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Document {
+    /// Always [`SCHEMA_VERSION`].
+    pub version: u32,
+    /// The content of the first top level `@document.meta` tag, if there is one.
+    pub meta: Option<Meta>,
+    pub children: Vec<Node>,
+}
+
+/// A range of bytes in the source, `end` being exclusive.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl From<Range<usize>> for Span {
+    fn from(range: Range<usize>) -> Self {
+        Self {
+            start: range.start,
+            end: range.end,
+        }
+    }
+}
+
+/// A block level node.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Node {
+    Heading {
+        span: Span,
+        level: u16,
+        title: Vec<Inline>,
+        extensions: Vec<Extension>,
+        children: Vec<Node>,
+    },
+    Paragraph {
+        span: Span,
+        content: Vec<Inline>,
+    },
+    UnorderedListItem {
+        span: Span,
+        level: u16,
+        extensions: Vec<Extension>,
+        content: Vec<Inline>,
+        children: Vec<Node>,
+    },
+    OrderedListItem {
+        span: Span,
+        level: u16,
+        extensions: Vec<Extension>,
+        content: Vec<Inline>,
+        children: Vec<Node>,
+    },
+    Quote {
+        span: Span,
+        level: u16,
+        extensions: Vec<Extension>,
+        content: Vec<Inline>,
+        children: Vec<Node>,
+    },
+    Definition {
+        span: Span,
+        title: Vec<Inline>,
+        extensions: Vec<Extension>,
+        children: Vec<Node>,
+    },
+    Footnote {
+        span: Span,
+        title: Vec<Inline>,
+        extensions: Vec<Extension>,
+        children: Vec<Node>,
+    },
+    Table {
+        span: Span,
+        title: Vec<Inline>,
+        extensions: Vec<Extension>,
+        children: Vec<Node>,
+    },
+    /// A `+attribute` or `#macro` applied to the single node in `children`.
+    CarryoverTag {
+        span: Span,
+        tag_type: TagType,
+        name: Vec<String>,
+        parameters: Vec<String>,
+        children: Vec<Node>,
+    },
+    VerbatimRangedTag {
+        span: Span,
+        name: Vec<String>,
+        parameters: Vec<String>,
+        content: String,
+    },
+    RangedTag {
+        span: Span,
+        name: Vec<String>,
+        parameters: Vec<String>,
+        children: Vec<Node>,
+    },
+    InfirmTag {
+        span: Span,
+        name: Vec<String>,
+        parameters: Vec<String>,
+    },
+    WeakDelimiter {
+        span: Span,
+    },
+    StrongDelimiter {
+        span: Span,
+    },
+    HorizontalRule {
+        span: Span,
+    },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TagType {
+    Attribute,
+    Macro,
+}
+
+/// A detached modifier extension, e.g. the `x` in `- (x) done`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Extension {
+    Todo {
+        status: Status,
+        /// When a recurring task recurs, e.g. `4th May` in `(+ 4th May)`.
+        recurrence: Option<String>,
+    },
+    Priority {
+        value: String,
+    },
+    Timestamp {
+        value: String,
+    },
+    DueDate {
+        value: String,
+    },
+    StartDate {
+        value: String,
+    },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Status {
+    Undone,
+    Done,
+    NeedsClarification,
+    Paused,
+    Urgent,
+    Recurring,
+    Pending,
+    Canceled,
+}
+
+/// Inline content of paragraphs and titles.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Inline {
+    /// Plain text, with escapes already resolved.
+    Text {
+        value: String,
+    },
+    Bold {
+        children: Vec<Inline>,
+    },
+    Italic {
+        children: Vec<Inline>,
+    },
+    Underline {
+        children: Vec<Inline>,
+    },
+    Strikethrough {
+        children: Vec<Inline>,
+    },
+    /// `%comment%`
+    NullModifier {
+        children: Vec<Inline>,
+    },
+    /// The content of inline verbatim, exactly as written.
+    InlineVerbatim {
+        value: String,
+    },
+    /// The content of inline math, exactly as written.
+    InlineMath {
+        value: String,
+    },
+    Link {
+        file: Option<String>,
+        targets: Vec<Target>,
+        description: Option<Vec<Inline>>,
+    },
+    Anchor {
+        content: Vec<Inline>,
+        description: Option<Vec<Inline>>,
+    },
+    /// An anchor with its target, e.g. `[Neorg]{https://github.com/nvim-neorg/neorg}`. The
+    /// target is always a `link`.
+    AnchorDefinition {
+        content: Vec<Inline>,
+        target: Box<Inline>,
+    },
+    InlineLinkTarget {
+        children: Vec<Inline>,
+    },
+}
+
+/// Where a link points to.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Target {
+    Heading { level: u16, title: Vec<Inline> },
+    Footnote { title: Vec<Inline> },
+    Definition { title: Vec<Inline> },
+    Generic { title: Vec<Inline> },
+    Wiki { title: Vec<Inline> },
+    Extendable { title: Vec<Inline> },
+    Path { value: String },
+    Url { value: String },
+    Timestamp { value: String },
+}
+
+/// A metadata value, as in `@document.meta`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Meta {
+    /// A value the metadata parser couldn't make sense of.
+    Invalid,
+    Nil,
+    Bool {
+        value: bool,
+    },
+    String {
+        value: String,
+    },
+    EmptyKey {
+        key: String,
+    },
+    Number {
+        value: f64,
+    },
+    Array {
+        items: Vec<Meta>,
+    },
+    Object {
+        entries: BTreeMap<String, Meta>,
+    },
+}
+
+impl From<&NorgMeta> for Meta {
+    fn from(meta: &NorgMeta) -> Self {
+        match meta {
+            NorgMeta::Invalid => Meta::Invalid,
+            NorgMeta::Nil => Meta::Nil,
+            NorgMeta::Bool(value) => Meta::Bool { value: *value },
+            NorgMeta::Str(value) => Meta::String {
+                value: value.clone(),
+            },
+            NorgMeta::EmptyKey(key) => Meta::EmptyKey { key: key.clone() },
+            NorgMeta::Num(value) => Meta::Number { value: *value },
+            NorgMeta::Array(items) => Meta::Array {
+                items: items.iter().map(Meta::from).collect(),
+            },
+            NorgMeta::Object(entries) => Meta::Object {
+                entries: entries
+                    .iter()
+                    .map(|(key, value)| (key.clone(), value.into()))
+                    .collect(),
+            },
+        }
+    }
+}
+
+impl From<&DetachedModifierExtension> for Extension {
+    fn from(extension: &DetachedModifierExtension) -> Self {
+        match extension {
+            DetachedModifierExtension::Todo(status) => {
+                let (status, recurrence) = match status {
+                    TodoStatus::Undone => (Status::Undone, None),
+                    TodoStatus::Done => (Status::Done, None),
+                    TodoStatus::NeedsClarification => (Status::NeedsClarification, None),
+                    TodoStatus::Paused => (Status::Paused, None),
+                    TodoStatus::Urgent => (Status::Urgent, None),
+                    TodoStatus::Recurring(recurrence) => (Status::Recurring, recurrence.clone()),
+                    TodoStatus::Pending => (Status::Pending, None),
+                    TodoStatus::Canceled => (Status::Canceled, None),
+                };
+                Extension::Todo { status, recurrence }
+            }
+            DetachedModifierExtension::Priority(value) => Extension::Priority {
+                value: value.clone(),
+            },
+            DetachedModifierExtension::Timestamp(value) => Extension::Timestamp {
+                value: value.clone(),
+            },
+            DetachedModifierExtension::DueDate(value) => Extension::DueDate {
+                value: value.clone(),
+            },
+            DetachedModifierExtension::StartDate(value) => Extension::StartDate {
+                value: value.clone(),
+            },
+        }
+    }
+}
+
+fn extensions(extensions: &[DetachedModifierExtension]) -> Vec<Extension> {
+    extensions.iter().map(Extension::from).collect()
+}
+
+fn target(target: &LinkTarget) -> Target {
+    match target {
+        LinkTarget::Heading { level, title } => Target::Heading {
+            level: *level,
+            title: inlines(title),
+        },
+        LinkTarget::Footnote(title) => Target::Footnote {
+            title: inlines(title),
+        },
+        LinkTarget::Definition(title) => Target::Definition {
+            title: inlines(title),
+        },
+        LinkTarget::Generic(title) => Target::Generic {
+            title: inlines(title),
+        },
+        LinkTarget::Wiki(title) => Target::Wiki {
+            title: inlines(title),
+        },
+        LinkTarget::Extendable(title) => Target::Extendable {
+            title: inlines(title),
+        },
+        LinkTarget::Path(value) => Target::Path {
+            value: value.clone(),
+        },
+        LinkTarget::Url(value) => Target::Url {
+            value: value.clone(),
+        },
+        LinkTarget::Timestamp(value) => Target::Timestamp {
+            value: value.clone(),
+        },
+    }
+}
+
+/// Appends text to the output, merging it with the text before it.
+fn push_text(out: &mut Vec<Inline>, text: &str) {
+    match out.last_mut() {
+        Some(Inline::Text { value }) => value.push_str(text),
+        _ => out.push(Inline::Text {
+            value: text.to_string(),
+        }),
+    }
+}
+
+fn push_inline(out: &mut Vec<Inline>, segment: &ParagraphSegment) {
+    let inline = match segment {
+        ParagraphSegment::Token(token) => {
+            return match token {
+                ParagraphSegmentToken::Text(text) => push_text(out, text),
+                ParagraphSegmentToken::Whitespace => push_text(out, " "),
+                ParagraphSegmentToken::Special(c) | ParagraphSegmentToken::Escape(c) => {
+                    push_text(out, c.encode_utf8(&mut [0; 4]))
+                }
+            }
+        }
+        ParagraphSegment::AttachedModifier {
+            modifier_type,
+            content,
+        } => {
+            let children = inlines(content);
+            match modifier_type {
+                '*' => Inline::Bold { children },
+                '/' => Inline::Italic { children },
+                '_' => Inline::Underline { children },
+                '-' => Inline::Strikethrough { children },
+                '%' => Inline::NullModifier { children },
+                _ => return push_text(out, &format::inline(std::slice::from_ref(segment))),
+            }
+        }
+        ParagraphSegment::InlineVerbatim(tokens) => Inline::InlineVerbatim {
+            value: tokens.iter().map(ToString::to_string).collect(),
+        },
+        ParagraphSegment::InlineMath(tokens) => Inline::InlineMath {
+            value: tokens.iter().map(ToString::to_string).collect(),
+        },
+        ParagraphSegment::Link {
+            filepath,
+            targets,
+            description,
+        } => Inline::Link {
+            file: filepath.clone(),
+            targets: targets.iter().map(target).collect(),
+            description: description.as_deref().map(inlines),
+        },
+        ParagraphSegment::Anchor {
+            content,
+            description,
+        } => Inline::Anchor {
+            content: inlines(content),
+            description: description.as_deref().map(inlines),
+        },
+        ParagraphSegment::AnchorDefinition { content, target } => {
+            let mut target_inlines = Vec::new();
+            push_inline(&mut target_inlines, target);
+            match target_inlines.pop() {
+                Some(target) if target_inlines.is_empty() => Inline::AnchorDefinition {
+                    content: inlines(content),
+                    target: Box::new(target),
+                },
+                _ => return push_text(out, &format::inline(std::slice::from_ref(segment))),
+            }
+        }
+        ParagraphSegment::InlineLinkTarget(content) => Inline::InlineLinkTarget {
+            children: inlines(content),
+        },
+        // Intermediate results of the paragraph parser, which don't make it into the AST.
+        _ => return push_text(out, &format::inline(std::slice::from_ref(segment))),
+    };
+
+    out.push(inline);
+}
+
+fn inlines(segments: &[ParagraphSegment]) -> Vec<Inline> {
+    let mut out = Vec::new();
+    segments
+        .iter()
+        .for_each(|segment| push_inline(&mut out, segment));
+    out
+}
+
+/// The span of a node, extended to cover its children.
+fn span(own: Range<usize>, children: &[Node]) -> Span {
+    let end = children.last().map_or(own.end, |child| child.span().end);
+    Span {
+        start: own.start,
+        end: end.max(own.end),
+    }
+}
+
+fn nodes(ast: &[NorgAST], spans: &mut BlockSpans) -> Vec<Node> {
+    ast.iter().map(|node| Node::new(node, spans)).collect()
+}
+
+impl Node {
+    fn new(node: &NorgAST, spans: &mut BlockSpans) -> Self {
+        match node {
+            NorgAST::Paragraph(content) => Node::Paragraph {
+                span: spans.take(SyntaxKind::Paragraph).into(),
+                content: inlines(content),
+            },
+            NorgAST::Heading {
+                level,
+                title,
+                extensions: heading_extensions,
+                content,
+            } => {
+                let own = spans.take(SyntaxKind::Heading);
+                let children = nodes(content, spans);
+                Node::Heading {
+                    span: span(own, &children),
+                    level: *level,
+                    title: inlines(title),
+                    extensions: extensions(heading_extensions),
+                    children,
+                }
+            }
+            NorgAST::NestableDetachedModifier {
+                modifier_type,
+                level,
+                extensions: item_extensions,
+                text,
+                content,
+            } => {
+                let own = spans.take(SyntaxKind::NestableDetachedModifier);
+                let text = match text.as_ref() {
+                    NorgAST::Paragraph(text) => {
+                        spans.take(SyntaxKind::Paragraph);
+                        inlines(text)
+                    }
+                    _ => Vec::new(),
+                };
+                let children = nodes(content, spans);
+                let (span, level, extensions) =
+                    (span(own, &children), *level, extensions(item_extensions));
+
+                match modifier_type {
+                    NestableDetachedModifier::UnorderedList => Node::UnorderedListItem {
+                        span,
+                        level,
+                        extensions,
+                        content: text,
+                        children,
+                    },
+                    NestableDetachedModifier::OrderedList => Node::OrderedListItem {
+                        span,
+                        level,
+                        extensions,
+                        content: text,
+                        children,
+                    },
+                    NestableDetachedModifier::Quote => Node::Quote {
+                        span,
+                        level,
+                        extensions,
+                        content: text,
+                        children,
+                    },
+                }
+            }
+            NorgAST::RangeableDetachedModifier {
+                modifier_type,
+                title,
+                extensions: modifier_extensions,
+                content,
+            } => {
+                let own = spans.take(SyntaxKind::RangeableDetachedModifier);
+                let children = nodes(content, spans);
+                let (span, title, extensions) = (
+                    span(own, &children),
+                    inlines(title),
+                    extensions(modifier_extensions),
+                );
+
+                match modifier_type {
+                    RangeableDetachedModifier::Definition => Node::Definition {
+                        span,
+                        title,
+                        extensions,
+                        children,
+                    },
+                    RangeableDetachedModifier::Footnote => Node::Footnote {
+                        span,
+                        title,
+                        extensions,
+                        children,
+                    },
+                    RangeableDetachedModifier::Table => Node::Table {
+                        span,
+                        title,
+                        extensions,
+                        children,
+                    },
+                }
+            }
+            NorgAST::CarryoverTag {
+                tag_type,
+                name,
+                parameters,
+                next_object,
+            } => {
+                let own = spans.take(SyntaxKind::CarryoverTag);
+                let children = vec![Node::new(next_object, spans)];
+                Node::CarryoverTag {
+                    span: span(own, &children),
+                    tag_type: match tag_type {
+                        CarryoverTag::Attribute => TagType::Attribute,
+                        CarryoverTag::Macro => TagType::Macro,
+                    },
+                    name: name.clone(),
+                    parameters: parameters.clone(),
+                    children,
+                }
+            }
+            NorgAST::VerbatimRangedTag {
+                name,
+                parameters,
+                content,
+            } => Node::VerbatimRangedTag {
+                span: spans.take(SyntaxKind::VerbatimRangedTag).into(),
+                name: name.clone(),
+                parameters: parameters.clone(),
+                content: content.clone(),
+            },
+            NorgAST::RangedTag {
+                name,
+                parameters,
+                content,
+            } => {
+                let own = spans.take(SyntaxKind::RangedTag);
+                let children = nodes(content, spans);
+                Node::RangedTag {
+                    span: span(own, &children),
+                    name: name.clone(),
+                    parameters: parameters.clone(),
+                    children,
+                }
+            }
+            NorgAST::InfirmTag { name, parameters } => Node::InfirmTag {
+                span: spans.take(SyntaxKind::InfirmTag).into(),
+                name: name.clone(),
+                parameters: parameters.clone(),
+            },
+            NorgAST::DelimitingModifier(delimiter) => {
+                let span = spans.take(SyntaxKind::DelimitingModifier).into();
+                match delimiter {
+                    DelimitingModifier::Weak => Node::WeakDelimiter { span },
+                    DelimitingModifier::Strong => Node::StrongDelimiter { span },
+                    DelimitingModifier::HorizontalRule => Node::HorizontalRule { span },
+                }
+            }
+        }
+    }
+
+    /// The range of the source the node was parsed from.
+    pub fn span(&self) -> Span {
+        match self {
+            Node::Heading { span, .. }
+            | Node::Paragraph { span, .. }
+            | Node::UnorderedListItem { span, .. }
+            | Node::OrderedListItem { span, .. }
+            | Node::Quote { span, .. }
+            | Node::Definition { span, .. }
+            | Node::Footnote { span, .. }
+            | Node::Table { span, .. }
+            | Node::CarryoverTag { span, .. }
+            | Node::VerbatimRangedTag { span, .. }
+            | Node::RangedTag { span, .. }
+            | Node::InfirmTag { span, .. }
+            | Node::WeakDelimiter { span }
+            | Node::StrongDelimiter { span }
+            | Node::HorizontalRule { span } => *span,
+        }
+    }
+}
+
+/// Parses a document into its JSON representation.
+///
+/// Metadata that can't be parsed doesn't fail the whole document, but is represented as
+/// [`Meta::Invalid`].
+///
+/// # Arguments
+///
+/// * `source` - The source of the document.
+///
+/// # Returns
+///
+/// * `Ok(Document)`, ready to be serialized with e.g. `serde_json`.
+/// * `Err(NorgParseError)` if the document can't be parsed.
+pub fn document(source: &str) -> Result<Document, NorgParseError> {
+    let ast = parse_tree(source)?;
+    let mut spans = BlockSpans::new(source)?;
+
+    let meta = ast.iter().find_map(|node| match node {
+        NorgAST::VerbatimRangedTag { name, content, .. } if name == &["document", "meta"] => {
+            Some(parse_metadata(content).map_or(Meta::Invalid, |meta| Meta::from(&meta)))
+        }
+        _ => None,
+    });
+
+    Ok(Document {
+        version: SCHEMA_VERSION,
+        meta,
+        children: nodes(&ast, &mut spans),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use insta::assert_snapshot;
+
+    use crate::json::{document, Document, SCHEMA, SCHEMA_VERSION};
+
+    const EXAMPLE: &str = "@document.meta
+title: Example
+tags: [
+  one
+  2
+]
+@end
+
+* (x|# A) Heading with *bold* and /italic/
+  A paragraph with a {* Heading}[link], `verbatim\\`` and \\*escapes\\*.
+  - ( ) first
+  -- (+ 4th May) nested
+  ~ ordered
+  > quote
+
+  $ Term
+  Definition
+  ^^ Footnote
+  Ranged footnote with [anchor]{https://example.com}
+  ^^
+  : Cell
+  text
+  ---
+
+#name value
++attribute
+paragraph with _underline_, -strike-, $x^2$ and %comment%
+
+|example
+  {:file:# target} [anchor] <inline target> {/ path} {@ Feb 1}
+|end
+
+@code lua
+print(\"hi\")
+@end
+
+.toc Contents
+===
+
+___
+";
+
+    #[test]
+    fn compatible() {
+        // A change to this snapshot is a change to the format. If it's incompatible, bump
+        // `SCHEMA_VERSION` and update the schema.
+        let document = document(EXAMPLE).unwrap();
+        let json = serde_json::to_string_pretty(&document).unwrap();
+        assert_snapshot!(json);
+        assert_eq!(serde_json::from_str::<Document>(&json).unwrap(), document);
+    }
+
+    #[test]
+    fn spans() {
+        let document = document(EXAMPLE).unwrap();
+        let heading = &document.children[1];
+        let span = heading.span();
+
+        assert!(EXAMPLE[span.start..span.end].starts_with("* (x|# A) Heading"));
+        assert!(EXAMPLE[span.start..span.end].ends_with("  : Cell\n  text"));
+    }
+
+    #[test]
+    fn described_by_schema() {
+        let schema: serde_json::Value = serde_json::from_str(SCHEMA).unwrap();
+        assert_eq!(
+            schema["properties"]["version"]["const"],
+            serde_json::json!(SCHEMA_VERSION)
+        );
+
+        let validator = jsonschema::validator_for(&schema).unwrap();
+
+        let mut json = serde_json::to_value(document(EXAMPLE).unwrap()).unwrap();
+        let errors: Vec<_> = validator
+            .iter_errors(&json)
+            .map(|error| format!("{}: {error}", error.instance_path()))
+            .collect();
+        assert!(errors.is_empty(), "{errors:#?}");
+
+        json["children"][0]["type"] = serde_json::json!("unknown");
+        assert!(!validator.is_valid(&json));
+    }
+}
@@ -0,0 +1,672 @@
+//! Renders documents as LaTeX.
+//!
+//! Headings become sectioning commands, lists and quotes become `itemize`, `enumerate` and
+//! `quote` environments, definitions become a `description` environment and footnotes are
+//! rendered with `\footnote` where they are referenced. `@math` blocks are typeset as display
+//! math, `@code` blocks with `verbatim` or `minted`, and inline math (`$x^2$`) as `\(...\)`. A
+//! `$` that doesn't delimit inline math is escaped like any other character.
+//!
+//! Tags without an equivalent in LaTeX are dropped, apart from ranged and carryover tags whose
+//! content is kept. `#comment` tags and null modifiers (`%comment%`) are dropped with their
+//! content.
+
+use crate::{
+    metadata::{parse_metadata, NorgMeta},
+    outline::{slugify, Slugs},
+    pandoc::{cell_position, plain, same_group, References},
+    visit::Visitor,
+    DelimitingModifier, DetachedModifierExtension, LinkTarget, NestableDetachedModifier, NorgAST,
+    ParagraphSegment, ParagraphSegmentToken, RangeableDetachedModifier, TodoStatus,
+};
+
+/// Options for the LaTeX renderer.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LatexOptions {
+    /// The document class, e.g. `article`, `report` or `book`.
+    pub document_class: String,
+    /// Options of the document class, e.g. `a4paper` or `11pt`.
+    pub class_options: Vec<String>,
+    /// The sectioning commands for heading levels 1, 2, ... Deeper headings use the last one.
+    pub sections: Vec<String>,
+    /// Typeset code with the `minted` package instead of `verbatim`. `minted` highlights code
+    /// but needs LaTeX to be run with `-shell-escape`.
+    pub minted: bool,
+    /// Extra preamble, inserted after the packages the output needs.
+    pub preamble: String,
+    /// Render a complete document. If `false`, only the body is rendered, e.g. for `\input`.
+    pub standalone: bool,
+}
+
+impl Default for LatexOptions {
+    fn default() -> Self {
+        Self {
+            document_class: String::from("article"),
+            class_options: Vec::new(),
+            sections: [
+                "section",
+                "subsection",
+                "subsubsection",
+                "paragraph",
+                "subparagraph",
+            ]
+            .map(String::from)
+            .to_vec(),
+            minted: false,
+            preamble: String::new(),
+            standalone: true,
+        }
+    }
+}
+
+/// Escapes the characters with a special meaning in LaTeX.
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' => out.push_str("\\textbackslash{}"),
+            '~' => out.push_str("\\textasciitilde{}"),
+            '^' => out.push_str("\\textasciicircum{}"),
+            '{' | '}' | '#' | '$' | '%' | '&' | '_' => {
+                out.push('\\');
+                out.push(c);
+            }
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Escapes a URL for `\href` and `\url`.
+fn escape_url(url: &str) -> String {
+    let mut out = String::with_capacity(url.len());
+    for c in url.chars() {
+        if matches!(c, '#' | '%' | '\\' | '{' | '}') {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+fn environment(name: &str, content: &str) -> String {
+    format!("\\begin{{{name}}}\n{content}\n\\end{{{name}}}")
+}
+
+struct Renderer<'a> {
+    options: &'a LatexOptions,
+    references: References,
+    slugs: Slugs,
+    /// The footnotes currently being rendered, so a footnote can't contain itself.
+    notes: Vec<String>,
+}
+
+impl Renderer<'_> {
+    fn inlines(&mut self, segments: &[ParagraphSegment]) -> String {
+        let mut out = String::new();
+        for segment in segments {
+            self.push_inline(&mut out, segment);
+        }
+        out
+    }
+
+    fn push_inline(&mut self, out: &mut String, segment: &ParagraphSegment) {
+        match segment {
+            ParagraphSegment::Token(token) => match token {
+                ParagraphSegmentToken::Text(text) => out.push_str(&escape(text)),
+                ParagraphSegmentToken::Whitespace => out.push(' '),
+                ParagraphSegmentToken::Special(c) | ParagraphSegmentToken::Escape(c) => {
+                    out.push_str(&escape(c.encode_utf8(&mut [0; 4])))
+                }
+            },
+            ParagraphSegment::AttachedModifier {
+                modifier_type,
+                content,
+            } => {
+                let command = match modifier_type {
+                    '*' => "textbf",
+                    '/' => "emph",
+                    '_' => "uline",
+                    '-' => "sout",
+                    // Null modifiers are comments.
+                    '%' => return,
+                    _ => return out.push_str(&self.inlines(content)),
+                };
+                let content = self.inlines(content);
+                out.push_str(&format!("\\{command}{{{content}}}"));
+            }
+            ParagraphSegment::InlineVerbatim(tokens) => {
+                let code: String = tokens.iter().map(ToString::to_string).collect();
+                out.push_str(&format!("\\texttt{{{}}}", escape(&code)));
+            }
+            ParagraphSegment::InlineMath(tokens) => {
+                let math: String = tokens.iter().map(ToString::to_string).collect();
+                out.push_str(&format!("\\({math}\\)"));
+            }
+            ParagraphSegment::Link {
+                filepath,
+                targets,
+                description,
+            } => {
+                let link = self.link(filepath, targets, description.as_deref());
+                out.push_str(&link);
+            }
+            ParagraphSegment::AnchorDefinition { content, target } => match target.as_ref() {
+                ParagraphSegment::Link {
+                    filepath, targets, ..
+                } => {
+                    let link = self.link(filepath, targets, Some(content));
+                    out.push_str(&link);
+                }
+                _ => out.push_str(&self.inlines(content)),
+            },
+            ParagraphSegment::Anchor {
+                content,
+                description,
+            } => {
+                let text = description.as_deref().unwrap_or(content);
+                match self.references.anchors.get(&plain(content)).cloned() {
+                    Some(ParagraphSegment::Link {
+                        filepath, targets, ..
+                    }) => {
+                        let link = self.link(&filepath, &targets, Some(text));
+                        out.push_str(&link);
+                    }
+                    _ => out.push_str(&self.inlines(text)),
+                }
+            }
+            ParagraphSegment::InlineLinkTarget(content) => {
+                let label = slugify(&plain(content));
+                let content = self.inlines(content);
+                out.push_str(&format!("\\phantomsection\\label{{{label}}}{content}"));
+            }
+            // Intermediate results of the paragraph parser, which don't make it into the AST.
+            _ => (),
+        }
+    }
+
+    fn link(
+        &mut self,
+        filepath: &Option<String>,
+        targets: &[LinkTarget],
+        description: Option<&[ParagraphSegment]>,
+    ) -> String {
+        let description = description.map(|description| self.inlines(description));
+
+        match targets.first() {
+            Some(LinkTarget::Footnote(title)) if filepath.is_none() => {
+                if let Some(note) = self.note(&plain(title)) {
+                    return note;
+                }
+                let text = description.unwrap_or_else(|| self.inlines(title));
+                format!("\\hyperref[{}]{{{text}}}", slugify(&plain(title)))
+            }
+            Some(LinkTarget::Timestamp(timestamp)) => {
+                description.unwrap_or_else(|| escape(timestamp))
+            }
+            Some(LinkTarget::Url(url)) => match description {
+                Some(text) => format!("\\href{{{}}}{{{text}}}", escape_url(url)),
+                None => format!("\\url{{{}}}", escape_url(url)),
+            },
+            Some(LinkTarget::Path(path)) => {
+                let text = description.unwrap_or_else(|| escape(path));
+                format!("\\href{{{}}}{{{text}}}", escape_url(path))
+            }
+            Some(
+                LinkTarget::Heading { title, .. }
+                | LinkTarget::Footnote(title)
+                | LinkTarget::Definition(title)
+                | LinkTarget::Generic(title)
+                | LinkTarget::Wiki(title)
+                | LinkTarget::Extendable(title),
+            ) => {
+                let text = description.unwrap_or_else(|| self.inlines(title));
+                let slug = slugify(&plain(title));
+                match filepath {
+                    Some(file) => format!("\\href{{{}\\#{slug}}}{{{text}}}", escape_url(file)),
+                    None => format!("\\hyperref[{slug}]{{{text}}}"),
+                }
+            }
+            None => {
+                let file = filepath.clone().unwrap_or_default();
+                let text = description.unwrap_or_else(|| escape(&file));
+                format!("\\href{{{}}}{{{text}}}", escape_url(&file))
+            }
+        }
+    }
+
+    fn note(&mut self, title: &str) -> Option<String> {
+        if self.notes.iter().any(|note| note == title) {
+            return None;
+        }
+        let content = self.references.footnotes.get(title)?.clone();
+
+        self.notes.push(title.to_string());
+        let blocks = self.blocks(&content);
+        self.notes.pop();
+
+        Some(format!("\\footnote{{{}}}", blocks.join("\n\n")))
+    }
+
+    /// Renders nodes into blocks, which are separated by blank lines.
+    fn blocks(&mut self, nodes: &[NorgAST]) -> Vec<String> {
+        let mut out = Vec::new();
+        let mut i = 0;
+
+        while i < nodes.len() {
+            let length = 1 + nodes[i + 1..]
+                .iter()
+                .take_while(|node| same_group(&nodes[i], node))
+                .count();
+            self.push_group(&mut out, &nodes[i..i + length]);
+            i += length;
+        }
+
+        out
+    }
+
+    /// Renders a list item or quote as an `\item`.
+    fn item(&mut self, node: &NorgAST) -> String {
+        let NorgAST::NestableDetachedModifier {
+            extensions,
+            text,
+            content,
+            ..
+        } = node
+        else {
+            return String::new();
+        };
+
+        let marker = match extensions.iter().find_map(|extension| match extension {
+            DetachedModifierExtension::Todo(status) => Some(status),
+            _ => None,
+        }) {
+            Some(TodoStatus::Done) => "[$\\boxtimes$]",
+            Some(_) => "[$\\square$]",
+            None => "",
+        };
+
+        let mut blocks = self.blocks(std::slice::from_ref(text.as_ref()));
+        blocks.extend(self.blocks(content));
+        format!("\\item{marker} {}", blocks.join("\n\n").trim_start())
+    }
+
+    fn push_group(&mut self, out: &mut Vec<String>, group: &[NorgAST]) {
+        match &group[0] {
+            NorgAST::NestableDetachedModifier { modifier_type, .. } => {
+                let name = match modifier_type {
+                    NestableDetachedModifier::UnorderedList => "itemize",
+                    NestableDetachedModifier::OrderedList => "enumerate",
+                    NestableDetachedModifier::Quote => {
+                        let paragraphs: Vec<_> = group
+                            .iter()
+                            .flat_map(|node| match node {
+                                NorgAST::NestableDetachedModifier { text, content, .. } => {
+                                    let mut blocks =
+                                        self.blocks(std::slice::from_ref(text.as_ref()));
+                                    blocks.extend(self.blocks(content));
+                                    blocks
+                                }
+                                _ => Vec::new(),
+                            })
+                            .collect();
+                        return out.push(environment("quote", &paragraphs.join("\n\n")));
+                    }
+                };
+                let items: Vec<_> = group.iter().map(|node| self.item(node)).collect();
+                out.push(environment(name, &items.join("\n")));
+            }
+            NorgAST::RangeableDetachedModifier {
+                modifier_type: RangeableDetachedModifier::Definition,
+                ..
+            } => {
+                let definitions: Vec<_> = group
+                    .iter()
+                    .filter_map(|node| match node {
+                        NorgAST::RangeableDetachedModifier { title, content, .. } => {
+                            Some(self.description_item(title, content))
+                        }
+                        _ => None,
+                    })
+                    .collect();
+                out.push(environment("description", &definitions.join("\n")));
+            }
+            NorgAST::RangeableDetachedModifier {
+                modifier_type: RangeableDetachedModifier::Table,
+                ..
+            } => self.push_table(out, group),
+            // Footnotes are rendered where they are referenced.
+            NorgAST::RangeableDetachedModifier {
+                modifier_type: RangeableDetachedModifier::Footnote,
+                ..
+            } => (),
+            node => self.push_block(out, node),
+        }
+    }
+
+    fn description_item(&mut self, title: &[ParagraphSegment], content: &[NorgAST]) -> String {
+        let title = self.inlines(title);
+        let content = self.blocks(content).join("\n\n");
+        format!("\\item[{{{}}}] {content}", title.trim())
+    }
+
+    /// Turns table cells like `: A1` into a `tabular`. If a cell doesn't name a position, the
+    /// cells are rendered as a `description` environment instead.
+    fn push_table(&mut self, out: &mut Vec<String>, group: &[NorgAST]) {
+        let cells: Vec<_> = group
+            .iter()
+            .filter_map(|node| match node {
+                NorgAST::RangeableDetachedModifier { title, content, .. } => Some((title, content)),
+                _ => None,
+            })
+            .collect();
+
+        let positions: Option<Vec<_>> = cells
+            .iter()
+            .map(|(title, _)| cell_position(&plain(title)))
+            .collect();
+        let Some(positions) = positions else {
+            let items: Vec<_> = cells
+                .into_iter()
+                .map(|(title, content)| self.description_item(title, content))
+                .collect();
+            return out.push(environment("description", &items.join("\n")));
+        };
+
+        let rows = positions.iter().map(|(row, _)| row + 1).max().unwrap_or(0);
+        let columns = positions
+            .iter()
+            .map(|(_, column)| column + 1)
+            .max()
+            .unwrap_or(0);
+        let mut grid = vec![vec![String::new(); columns]; rows];
+        for ((_, content), (row, column)) in cells.into_iter().zip(positions) {
+            grid[row][column] = self.blocks(content).join(" ");
+        }
+
+        let rows: Vec<_> = grid
+            .into_iter()
+            .map(|row| format!("{} \\\\", row.join(" & ")))
+            .collect();
+        out.push(format!(
+            "\\begin{{tabular}}{{{}}}\n{}\n\\end{{tabular}}",
+            "l".repeat(columns),
+            rows.join("\n")
+        ));
+    }
+
+    fn section(&self, level: u16) -> &str {
+        let sections = &self.options.sections;
+        sections
+            .get(usize::from(level.max(1)) - 1)
+            .or(sections.last())
+            .map_or("section", String::as_str)
+    }
+
+    fn push_block(&mut self, out: &mut Vec<String>, node: &NorgAST) {
+        match node {
+            NorgAST::Paragraph(segments) => out.push(self.inlines(segments).trim().to_string()),
+            NorgAST::Heading {
+                level,
+                title,
+                content,
+                ..
+            } => {
+                let label = self.slugs.unique(&plain(title));
+                let title = self.inlines(title);
+                out.push(format!(
+                    "\\{}{{{}}}\\label{{{label}}}",
+                    self.section(*level),
+                    title.trim()
+                ));
+                out.extend(self.blocks(content));
+            }
+            NorgAST::CarryoverTag {
+                name, next_object, ..
+            } => {
+                if name != &["comment"] {
+                    out.extend(self.blocks(std::slice::from_ref(next_object)));
+                }
+            }
+            NorgAST::VerbatimRangedTag {
+                name,
+                parameters,
+                content,
+            } => {
+                let content = content.trim_end_matches('\n');
+                match name
+                    .iter()
+                    .map(String::as_str)
+                    .collect::<Vec<_>>()
+                    .as_slice()
+                {
+                    ["code"] if self.options.minted => out.push(format!(
+                        "\\begin{{minted}}{{{}}}\n{content}\n\\end{{minted}}",
+                        parameters.first().map_or("text", String::as_str)
+                    )),
+                    ["math"] => out.push(format!("\\[\n{}\n\\]", content.trim())),
+                    ["document", "meta"] | ["comment"] => (),
+                    _ => out.push(environment("verbatim", content)),
+                }
+            }
+            NorgAST::RangedTag { name, content, .. } => {
+                if name != &["comment"] {
+                    out.extend(self.blocks(content));
+                }
+            }
+            NorgAST::InfirmTag { name, parameters } => {
+                match (name.as_slice(), parameters.first()) {
+                    ([image], Some(source)) if image == "image" => {
+                        out.push(format!("\\includegraphics{{{source}}}"))
+                    }
+                    ([toc], _) if toc == "toc" => out.push(String::from("\\tableofcontents")),
+                    _ => (),
+                }
+            }
+            NorgAST::DelimitingModifier(DelimitingModifier::HorizontalRule) => {
+                out.push(String::from("\\noindent\\rule{\\linewidth}{0.4pt}"))
+            }
+            NorgAST::DelimitingModifier(_) => (),
+            NorgAST::NestableDetachedModifier { .. }
+            | NorgAST::RangeableDetachedModifier { .. } => {
+                self.push_group(out, std::slice::from_ref(node))
+            }
+        }
+    }
+}
+
+fn meta_string(meta: &NorgMeta) -> Option<String> {
+    match meta {
+        NorgMeta::Str(value) => Some(escape(value)),
+        NorgMeta::Num(value) => Some(value.to_string()),
+        NorgMeta::Array(values) => {
+            let values: Vec<_> = values.iter().filter_map(meta_string).collect();
+            Some(values.join(" \\and "))
+        }
+        _ => None,
+    }
+}
+
+/// The `\title`, `\author` and `\date` commands from the metadata of a document.
+fn title_commands(ast: &[NorgAST]) -> Vec<String> {
+    let Some(Ok(NorgMeta::Object(entries))) = ast.iter().find_map(|node| match node {
+        NorgAST::VerbatimRangedTag { name, content, .. } if name == &["document", "meta"] => {
+            Some(parse_metadata(content))
+        }
+        _ => None,
+    }) else {
+        return Vec::new();
+    };
+
+    [
+        ("title", "title"),
+        ("author", "authors"),
+        ("date", "created"),
+    ]
+    .into_iter()
+    .filter_map(|(command, key)| {
+        let value = meta_string(entries.get(key)?)?;
+        Some(format!("\\{command}{{{value}}}"))
+    })
+    .collect()
+}
+
+/// Renders a document as LaTeX.
+///
+/// The title, authors and creation date from a top level `@document.meta` tag are used for
+/// `\maketitle`. Headings are labelled with the same identifiers as in
+/// [`crate::outline::outline`], so links to them become `\hyperref`s.
+///
+/// # Arguments
+///
+/// * `ast` - The document, as returned by [`crate::parse_tree`].
+/// * `options` - The document class, preamble and how to render code.
+///
+/// # Returns
+///
+/// * `String` containing the LaTeX source.
+pub fn to_latex(ast: &[NorgAST], options: &LatexOptions) -> String {
+    let mut references = References::default();
+    ast.iter().for_each(|node| references.visit_node(node));
+
+    let mut renderer = Renderer {
+        options,
+        references,
+        slugs: Slugs::default(),
+        notes: Vec::new(),
+    };
+    let body = renderer.blocks(ast).join("\n\n");
+
+    if !options.standalone {
+        return body + "\n";
+    }
+
+    let mut out = String::from("\\documentclass");
+    if !options.class_options.is_empty() {
+        out.push_str(&format!("[{}]", options.class_options.join(",")));
+    }
+    out.push_str(&format!("{{{}}}\n", options.document_class));
+    out.push_str(
+        "\\usepackage[T1]{fontenc}\n\\usepackage{amsmath}\n\\usepackage{amssymb}\n\\usepackage[normalem]{ulem}\n\\usepackage{graphicx}\n",
+    );
+    if options.minted {
+        out.push_str("\\usepackage{minted}\n");
+    }
+    out.push_str("\\usepackage{hyperref}\n");
+    if !options.preamble.is_empty() {
+        out.push_str(options.preamble.trim_end());
+        out.push('\n');
+    }
+
+    let title = title_commands(ast);
+    for command in &title {
+        out.push_str(command);
+        out.push('\n');
+    }
+
+    out.push_str("\n\\begin{document}\n\n");
+    if title.iter().any(|command| command.starts_with("\\title")) {
+        out.push_str("\\maketitle\n\n");
+    }
+    if !body.is_empty() {
+        out.push_str(&body);
+        out.push_str("\n\n");
+    }
+    out.push_str("\\end{document}\n");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use insta::assert_snapshot;
+
+    use crate::{
+        latex::{to_latex, LatexOptions},
+        parse_tree,
+    };
+
+    #[test]
+    fn document() {
+        let source = "@document.meta
+title: Research notes
+authors: [
+  Ada
+  Grace
+]
+@end
+
+.toc
+* (x) Introduction
+  Some *bold*, /italic/, _underlined_ and -struck- text with `code`, see {* Details}.
+  Costs 5$ & 10% of {https://neorg.org/a#b}[the budget]{^ 1}.
+  - ( ) first
+  -- nested
+  - (x) second
+  ~ one
+  > quoted
+** Details
+   $ Term
+   The definition
+   @code rust
+   fn main() {}
+   @end
+   @math
+   e^{i\\pi} + 1 = 0
+   @end
+   : A1
+   a
+   : B1
+   b
+   : A2
+   c
+___
+^ 1
+The note
+";
+        assert_snapshot!(to_latex(
+            &parse_tree(source).unwrap(),
+            &LatexOptions::default()
+        ));
+    }
+
+    #[test]
+    fn options() {
+        let options = LatexOptions {
+            document_class: String::from("report"),
+            sections: vec![String::from("chapter"), String::from("section")],
+            minted: true,
+            standalone: false,
+            ..Default::default()
+        };
+        let ast = parse_tree("* One\n** Two\n*** Three\n@code\nx_1\n@end\n").unwrap();
+
+        assert_eq!(
+            to_latex(&ast, &options),
+            "\\chapter{One}\\label{one}\n\n\\section{Two}\\label{two}\n\n\\section{Three}\\label{three}\n\n\\begin{minted}{text}\nx_1\n\\end{minted}\n"
+        );
+
+        let options = LatexOptions {
+            class_options: vec![String::from("a4paper"), String::from("11pt")],
+            preamble: String::from("\\usepackage{lmodern}\n"),
+            ..Default::default()
+        };
+        let latex = to_latex(&[], &options);
+        assert!(latex.starts_with("\\documentclass[a4paper,11pt]{article}\n"));
+        assert!(
+            latex.contains("\\usepackage{hyperref}\n\\usepackage{lmodern}\n\n\\begin{document}")
+        );
+    }
+
+    #[test]
+    fn math() {
+        let options = LatexOptions {
+            standalone: false,
+            ..Default::default()
+        };
+        let ast = parse_tree("text $x^2$, but $5 and $10\n").unwrap();
+
+        assert_eq!(
+            to_latex(&ast, &options),
+            "text \\(x^2\\), but \\$5 and \\$10\n"
+        );
+    }
+}
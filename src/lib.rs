@@ -3,18 +3,46 @@ use error::NorgParseError;
 
 pub use crate::stage_1::stage_1;
 pub use crate::stage_2::stage_2;
-use crate::stage_4::stage_4;
+pub use crate::stage_4::stage_4;
 
 pub use crate::stage_2::ParagraphSegmentToken;
 pub use crate::stage_3::*;
 pub use crate::stage_4::NorgAST;
+pub use crate::stream::{parse_tree_stream, NorgStream};
 
+#[cfg(feature = "parallel")]
+pub use crate::parallel::parse_tree_parallel;
+
+pub mod agenda;
+pub mod builder;
+pub mod cst;
+pub mod edit;
 mod error;
+pub mod format;
+pub mod html;
+pub mod json;
+pub mod latex;
+pub mod lint;
+#[cfg(feature = "markdown")]
+pub mod markdown;
 pub mod metadata;
+pub mod org;
+pub mod outline;
+pub mod pandoc;
+#[cfg(feature = "parallel")]
+mod parallel;
+pub mod plain_text;
+pub mod query;
+#[cfg(feature = "search")]
+pub mod search;
+pub mod slides;
 mod stage_1;
 mod stage_2;
 mod stage_3;
 mod stage_4;
+mod stream;
+pub mod verbatim;
+pub mod visit;
 
 /// Parses the given input string through multiple stages to produce a flattened abstract syntax tree (AST).
 ///
@@ -42,7 +70,10 @@ mod tests {
     use itertools::Itertools;
     use proptest::{prop_oneof, proptest};
 
-    use crate::{parse, parse_tree};
+    use crate::{
+        metadata::{parse_metadata, NorgMeta},
+        parse, parse_tree, NorgAST, NorgASTFlat,
+    };
 
     const TAG_NAME_REGEX: &str = r"[\w_\-\.\d]+";
     const TAG_PARAMETER_REGEX: &str = r"[^\s]+";
@@ -207,11 +238,13 @@ mod tests {
             "* (_) canceled",
             "- (-) pending",
             "* (!) urgent",
+            "- (?) needs clarification",
             "- (+) recurring",
             "~ (+ Friday) recurring with date",
             "** ( |# Low|< Feb 1) undone, low, & before Feb",
-            "** (# Two Words|x| |!|+|_|+ 5th|=|-|< Feb 1|> 2025|@ Jan 1 2025) All of them"
-        ].into_iter()
+            "** (# Two Words|x| |!|+|_|+ 5th|=|-|< Feb 1|> 2025|@ Jan 1 2025) All of them",
+        ]
+        .into_iter()
         .map(|example| example.to_string() + "\n")
         .map(|str| parse(&str))
         .try_collect()
@@ -220,6 +253,30 @@ mod tests {
         assert_yaml_snapshot!(examples);
     }
 
+    #[test]
+    fn invalid_modifier_extensions() {
+        let examples: Vec<_> = [
+            "- (y) unknown status",
+            "* (#A) missing space",
+            "$ (x|) trailing separator\ndefinition",
+            "* Heading\n  - (x|y) nested",
+            "- (see below) item",
+            "* (optional) Heading",
+        ]
+        .into_iter()
+        .map(|example| example.to_string() + "\n")
+        .map(|str| parse_tree(&str))
+        .try_collect()
+        .unwrap();
+
+        assert_yaml_snapshot!(examples);
+    }
+
+    #[test]
+    fn stray_end() {
+        assert_yaml_snapshot!(parse("text\n|end\nmore\n").unwrap());
+    }
+
     #[test]
     fn lists_regressions() {
         [
@@ -527,6 +584,45 @@ mod tests {
             #comment
             comment with id
             ",
+            "
+            * tree
+            #id 123
+            +attribute
+            * another
+              text
+            ",
+        ]
+        .into_iter()
+        .map(|example| example.to_string() + "\n")
+        .map(|str| parse_tree(&str))
+        .try_collect()
+        .unwrap();
+        assert_yaml_snapshot!(examples);
+    }
+
+    #[test]
+    fn ranged_containers_tree() {
+        let examples: Vec<_> = [
+            "
+            |example
+            * Heading
+              content
+            ** Nested
+            - list
+            -- nested list
+            |end
+            ",
+            "
+            $$ Term
+            * Heading in definition
+            - list
+            -- nested list
+            $$
+            ",
+            "
+            - item
+            -- nested
+            ",
         ]
         .into_iter()
         .map(|example| example.to_string() + "\n")
@@ -696,6 +792,8 @@ mod tests {
             "this *is/ a test",
             "this *is*/ a test",
             "this */is/*/ a test",
+            "*unclosed",
+            "%A",
         ]
         .into_iter()
         .map(|example| example.to_string() + "\n")
@@ -747,4 +845,63 @@ mod tests {
 
         assert_yaml_snapshot!(examples);
     }
+
+    #[test]
+    fn inline_math() {
+        let examples: Vec<_> = [
+            "some text $x^2$",
+            "$*not bold*$ and `$not math$`",
+            "$5 and $10",
+            "a $ padded $",
+            "{$ term}",
+            "prices range from US$5 to US$10",
+            "between $5-$10",
+            "a$b$c",
+            "($x$), $y$.",
+        ]
+        .into_iter()
+        .map(|example| example.to_string() + "\n")
+        .map(|str| parse(&str))
+        .try_collect()
+        .unwrap();
+
+        assert_yaml_snapshot!(examples);
+    }
+
+    #[test]
+    fn json_round_trip() {
+        let source = "
+            * (x|# A) Heading with *bold* and `verbatim`
+              - ( ) {:file:* target}[description]
+              -- (+ 4th May) [anchor]{https://example.com} <target>
+              ~ \\*escaped\\*
+            #name value
+            $$ Term
+            > quote
+            $$
+            |example
+            ===
+            |end
+            @code lua
+            print(\"hi\")
+            @end
+            .toc
+            ___
+            ";
+
+        let flat = parse(source).unwrap();
+        let json = serde_json::to_string(&flat).unwrap();
+        assert_eq!(
+            serde_json::from_str::<Vec<NorgASTFlat>>(&json).unwrap(),
+            flat
+        );
+
+        let tree = parse_tree(source).unwrap();
+        let json = serde_json::to_string(&tree).unwrap();
+        assert_eq!(serde_json::from_str::<Vec<NorgAST>>(&json).unwrap(), tree);
+
+        let meta = parse_metadata("title: test\ntags: [\n  one\n  2\n]\nempty:").unwrap();
+        let json = serde_json::to_string(&meta).unwrap();
+        assert_eq!(serde_json::from_str::<NorgMeta>(&json).unwrap(), meta);
+    }
 }
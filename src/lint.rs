@@ -0,0 +1,817 @@
+//! A linter for Norg documents.
+//!
+//! Rules walk the lossless [`crate::cst`] tree, so that diagnostics and fixes can point at the
+//! exact bytes a problem is in. Unlike the AST, the CST also keeps closers the parser couldn't
+//! match and ranged tags that run until the end of the document. Lines the parser reads as text
+//! although they were meant as something else, like `* ` or an `@code` without an `@end`, are
+//! found in paragraphs. The content of verbatim ranged tags (`@code` ... `@end`) is never
+//! linted.
+//!
+//! Which rules run is controlled by a [`LintConfig`], which can also be read from Norg metadata:
+//!
+//! ```norg
+//! disabled: [
+//!   trailing-whitespace
+//!   duplicate-heading
+//! ]
+//! due-date-headings: [
+//!   Work
+//! ]
+//! ```
+
+use std::collections::HashMap;
+
+use itertools::Itertools;
+
+use crate::{
+    cst::{parse_cst, SyntaxElement, SyntaxKind, SyntaxNode, SyntaxToken},
+    edit::{is_todo_section, TextEdit},
+    error::NorgParseError,
+    metadata::NorgMeta,
+    outline::slugify,
+    stage_3::check_extensions,
+};
+
+/// Characters which repeated and followed by whitespace start a heading, list item, quote or
+/// rangeable detached modifier.
+const DETACHED_MODIFIERS: [char; 7] = ['*', '-', '~', '>', '$', '^', ':'];
+
+/// A single lint rule.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Rule {
+    /// A heading more than one level deeper than the one before it, e.g. `***` after `*`.
+    SkippedHeadingLevel,
+    /// A list item more than one level deeper than the one before it, e.g. `---` after `-`.
+    ListLevelJump,
+    /// A heading without a title.
+    EmptyHeading,
+    /// A ranged tag (`|example`, `=macro` or `@code`) without a matching `end`, or an `end`
+    /// without a tag to close.
+    UnclosedTag,
+    /// A ranged detached modifier closed by the wrong closer, e.g. `$$ Term` closed by `^^`.
+    MismatchedCloser,
+    /// An extension section the parser doesn't understand, e.g. `(y)` in `- (y) item`. The
+    /// parser reads it as part of the text instead.
+    InvalidExtension,
+    /// An open todo item without a due date below one of the
+    /// [`LintConfig::due_date_headings`].
+    MissingDueDate,
+    /// A heading with the same title (or rather, the same slug) as an earlier one.
+    DuplicateHeading,
+    /// Spaces or tabs at the end of a line.
+    TrailingWhitespace,
+}
+
+impl Rule {
+    pub const ALL: [Rule; 9] = [
+        Rule::SkippedHeadingLevel,
+        Rule::ListLevelJump,
+        Rule::EmptyHeading,
+        Rule::UnclosedTag,
+        Rule::MismatchedCloser,
+        Rule::InvalidExtension,
+        Rule::MissingDueDate,
+        Rule::DuplicateHeading,
+        Rule::TrailingWhitespace,
+    ];
+
+    /// The name of the rule as used in the configuration, e.g. `trailing-whitespace`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Rule::SkippedHeadingLevel => "skipped-heading-level",
+            Rule::ListLevelJump => "list-level-jump",
+            Rule::EmptyHeading => "empty-heading",
+            Rule::UnclosedTag => "unclosed-tag",
+            Rule::MismatchedCloser => "mismatched-closer",
+            Rule::InvalidExtension => "invalid-extension",
+            Rule::MissingDueDate => "missing-due-date",
+            Rule::DuplicateHeading => "duplicate-heading",
+            Rule::TrailingWhitespace => "trailing-whitespace",
+        }
+    }
+
+    /// Looks up a rule by its name.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|rule| rule.name() == name)
+    }
+}
+
+/// Configures which rules run, and how.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct LintConfig {
+    pub disabled: Vec<Rule>,
+    /// Titles of headings below which every open todo item needs a due date. The
+    /// [`Rule::MissingDueDate`] rule does nothing while this is empty.
+    pub due_date_headings: Vec<String>,
+}
+
+/// Returns the strings in a metadata value that is either a single string or an array of them.
+fn meta_strings(key: &str, value: &NorgMeta) -> Result<Vec<String>, NorgParseError> {
+    match value {
+        NorgMeta::Str(value) => Ok(vec![value.clone()]),
+        NorgMeta::Array(values) => values
+            .iter()
+            .map(|value| match value {
+                NorgMeta::Str(value) => Ok(value.clone()),
+                _ => Err(NorgParseError::Config(format!(
+                    "Expected '{}' to only contain strings.",
+                    key
+                ))),
+            })
+            .collect(),
+        NorgMeta::Nil => Ok(vec![]),
+        _ => Err(NorgParseError::Config(format!(
+            "Expected '{}' to be a string or an array of strings.",
+            key
+        ))),
+    }
+}
+
+impl LintConfig {
+    /// Returns `true` unless the rule is disabled.
+    pub fn is_enabled(&self, rule: Rule) -> bool {
+        !self.disabled.contains(&rule)
+    }
+
+    /// Reads the configuration from metadata, as returned by [`crate::metadata::parse_metadata`].
+    ///
+    /// # Arguments
+    ///
+    /// * `meta` - An object with the optional keys `disabled` (rule names) and
+    ///   `due-date-headings` (heading titles).
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(LintConfig)` if the configuration is valid.
+    /// * `Err(NorgParseError)` for unknown keys or rules, or values of the wrong type.
+    pub fn from_meta(meta: &NorgMeta) -> Result<Self, NorgParseError> {
+        let NorgMeta::Object(object) = meta else {
+            return Err(NorgParseError::Config(String::from(
+                "Expected the lint configuration to be an object.",
+            )));
+        };
+
+        let mut config = Self::default();
+        for (key, value) in object {
+            match key.as_str() {
+                "disabled" => {
+                    config.disabled = meta_strings(key, value)?
+                        .iter()
+                        .map(|name| {
+                            Rule::from_name(name).ok_or_else(|| {
+                                NorgParseError::Config(format!("Unknown lint rule '{}'.", name))
+                            })
+                        })
+                        .collect::<Result<_, _>>()?
+                }
+                "due-date-headings" => config.due_date_headings = meta_strings(key, value)?,
+                _ => {
+                    return Err(NorgParseError::Config(format!(
+                        "Unknown lint option '{}'.",
+                        key
+                    )))
+                }
+            }
+        }
+
+        Ok(config)
+    }
+}
+
+/// A problem found by the linter.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub rule: Rule,
+    /// The zero-based index of the line the problem is on.
+    pub line: usize,
+    pub message: String,
+    /// An edit that fixes the problem, if there is an obvious one.
+    pub fix: Option<TextEdit>,
+}
+
+/// Splits the rest of a detached modifier line into its extension section (without
+/// parentheses) and its title.
+fn split_extensions(rest: &str) -> (Option<&str>, &str) {
+    rest.trim_start()
+        .strip_prefix('(')
+        .and_then(|rest| rest.split_once(')'))
+        .map(|(extensions, title)| (Some(extensions), title.trim()))
+        .unwrap_or((None, rest.trim()))
+}
+
+/// Returns the character a detached modifier is made of and how often it is repeated, e.g.
+/// `('-', 2)` for `-- item`.
+fn modifier(node: &SyntaxNode) -> Option<(char, usize)> {
+    let mut characters = node
+        .children_with_tokens()
+        .map_while(SyntaxElement::into_token)
+        .take_while(|token| token.kind() == SyntaxKind::Special)
+        .filter_map(|token| token.text().chars().next());
+    let first = characters.next()?;
+    Some((first, 1 + characters.take_while(|&c| c == first).count()))
+}
+
+/// Returns the extension section of a heading or nestable detached modifier, without its
+/// parentheses.
+fn extension_section(node: &SyntaxNode) -> Option<String> {
+    node.children()
+        .find(|child| child.kind() == SyntaxKind::ExtensionSection)
+        .map(|section| {
+            let text = section.text();
+            text.strip_prefix('(')
+                .and_then(|text| text.strip_suffix(')'))
+                .unwrap_or(&text)
+                .to_string()
+        })
+}
+
+/// Returns the character and offset of the closer at the end of a ranged detached modifier,
+/// e.g. `^^`.
+fn closer(node: &SyntaxNode) -> Option<(char, usize)> {
+    let children: Vec<_> = node.children_with_tokens().collect();
+    let mut rest = children
+        .iter()
+        .rev()
+        .skip_while(|child| matches!(child.kind(), SyntaxKind::Whitespace | SyntaxKind::Newline));
+
+    let (Some(SyntaxElement::Token(last)), Some(SyntaxElement::Token(first))) =
+        (rest.next(), rest.next())
+    else {
+        return None;
+    };
+    let c = first.text().chars().next()?;
+
+    (first.kind() == SyntaxKind::Special
+        && matches!(c, '$' | '^' | ':')
+        && last.text() == first.text())
+    .then_some((c, first.text_range().start))
+}
+
+#[derive(Default)]
+struct Linter<'a> {
+    config: LintConfig,
+    source: &'a str,
+    /// The offset at which each line starts.
+    lines: Vec<usize>,
+    diagnostics: Vec<Diagnostic>,
+    /// The level and title of every heading the current node is nested in.
+    headings: Vec<(usize, String)>,
+    /// Slugs of all headings seen so far, and the line they are on.
+    slugs: HashMap<String, usize>,
+    list_level: usize,
+    /// The offset of the whitespace since the last token which isn't whitespace.
+    whitespace: Option<usize>,
+}
+
+impl<'a> Linter<'a> {
+    fn report(&mut self, rule: Rule, line: usize, message: String, fix: Option<TextEdit>) {
+        if self.config.is_enabled(rule) {
+            self.diagnostics.push(Diagnostic {
+                rule,
+                line,
+                message,
+                fix,
+            });
+        }
+    }
+
+    /// Returns the zero-based index of the line the given offset is on.
+    fn line(&self, offset: usize) -> usize {
+        self.lines.partition_point(|&start| start <= offset) - 1
+    }
+
+    /// Returns the whitespace between the start of the line and the given offset.
+    fn indent(&self, offset: usize) -> &'a str {
+        &self.source[self.lines[self.line(offset)]..offset]
+    }
+
+    /// Returns an edit that adds a line to the end of the document.
+    fn append(&self, line: String) -> TextEdit {
+        let end = self.source.len();
+        let newline = if self.source.is_empty() || self.source.ends_with('\n') {
+            ""
+        } else {
+            "\n"
+        };
+
+        TextEdit {
+            range: end..end,
+            replacement: format!("{}{}\n", newline, line),
+        }
+    }
+
+    fn check_extensions(&mut self, line: usize, extensions: Option<&str>) {
+        if let Some(Err(message)) = extensions
+            .filter(|extensions| !extensions.is_empty())
+            .map(check_extensions)
+        {
+            self.report(Rule::InvalidExtension, line, message, None);
+        }
+    }
+
+    fn check_due_date(&mut self, line: usize, extensions: Option<&str>) {
+        let Some(extensions) = extensions else {
+            return;
+        };
+
+        let sections: Vec<_> = extensions.split('|').collect();
+        let open = sections.iter().any(|section| {
+            is_todo_section(section) && !matches!(section.chars().next(), Some('x' | '_'))
+        });
+        let due = sections.iter().any(|section| section.starts_with('<'));
+
+        if let Some((_, heading)) = self.headings.iter().find(|(_, title)| {
+            self.config
+                .due_date_headings
+                .iter()
+                .any(|expected| expected == title)
+        }) {
+            if open && !due {
+                let message = format!("Todo items below '{}' need a due date.", heading);
+                self.report(Rule::MissingDueDate, line, message, None);
+            }
+        }
+    }
+
+    fn heading(&mut self, offset: usize, level: usize, extensions: Option<&str>, title: &str) {
+        let line = self.line(offset);
+        let previous = self.headings.last().map_or(0, |(level, _)| *level);
+        if level > previous + 1 {
+            self.report(
+                Rule::SkippedHeadingLevel,
+                line,
+                format!(
+                    "Heading level {} follows level {}, expected level {} at most.",
+                    level,
+                    previous,
+                    previous + 1
+                ),
+                Some(TextEdit {
+                    range: offset..offset + level,
+                    replacement: "*".repeat(previous + 1),
+                }),
+            );
+        }
+
+        if title.is_empty() {
+            self.report(
+                Rule::EmptyHeading,
+                line,
+                String::from("Heading without a title."),
+                None,
+            );
+        }
+
+        let slug = slugify(title);
+        if !slug.is_empty() {
+            if let Some(first) = self.slugs.get(&slug).copied() {
+                self.report(
+                    Rule::DuplicateHeading,
+                    line,
+                    format!(
+                        "Heading '{}' has the same title as the heading on line {}.",
+                        title,
+                        first + 1
+                    ),
+                    None,
+                );
+            } else {
+                self.slugs.insert(slug, line);
+            }
+        }
+
+        self.headings.retain(|(parent, _)| *parent < level);
+        self.check_extensions(line, extensions);
+        self.check_due_date(line, extensions);
+        self.headings.push((level, title.to_string()));
+        self.list_level = 0;
+    }
+
+    fn list_item(&mut self, offset: usize, c: char, level: usize, extensions: Option<&str>) {
+        let line = self.line(offset);
+        if level > self.list_level + 1 {
+            self.report(
+                Rule::ListLevelJump,
+                line,
+                format!(
+                    "List item of level {} follows level {}, expected level {} at most.",
+                    level,
+                    self.list_level,
+                    self.list_level + 1
+                ),
+                Some(TextEdit {
+                    range: offset..offset + level,
+                    replacement: c.to_string().repeat(self.list_level + 1),
+                }),
+            );
+        }
+
+        self.check_extensions(line, extensions);
+        self.check_due_date(line, extensions);
+        self.list_level = level;
+    }
+
+    /// Looks for lines the parser read as text, but which were meant as a heading without a
+    /// title (`* `) or a verbatim ranged tag without an `@end`.
+    fn paragraph(&mut self, node: &SyntaxNode) {
+        let tokens: Vec<_> = node.tokens().collect();
+        for line in tokens.split(|token| token.kind() == SyntaxKind::Newline) {
+            let line: Vec<_> = line
+                .iter()
+                .skip_while(|token| token.kind() == SyntaxKind::Whitespace)
+                .collect();
+            let Some(first) = line.first() else {
+                continue;
+            };
+            let offset = first.text_range().start;
+
+            let level = line
+                .iter()
+                .take_while(|token| token.kind() == SyntaxKind::Special && token.text() == "*")
+                .count();
+            if level > 0
+                && level < line.len()
+                && line[level..]
+                    .iter()
+                    .all(|token| token.kind() == SyntaxKind::Whitespace)
+            {
+                self.heading(offset, level, None, "");
+            } else if first.text() == "@"
+                && line
+                    .get(1)
+                    .is_some_and(|token| token.kind() == SyntaxKind::Text)
+            {
+                // Only a tag at the top level can be closed at the end of the document.
+                let fix = (node.parent().map(|parent| parent.kind()) == Some(SyntaxKind::Document))
+                    .then(|| self.append(format!("{}@end", self.indent(offset))));
+                self.report(
+                    Rule::UnclosedTag,
+                    self.line(offset),
+                    String::from("Verbatim tag without a matching '@end'."),
+                    fix,
+                );
+            }
+        }
+    }
+
+    /// Reports a closer without anything to close, e.g. a stray `|end` or `$$`.
+    fn stray_closer(&mut self, node: &SyntaxNode) {
+        let Some(token) = node
+            .tokens()
+            .find(|token| matches!(token.kind(), SyntaxKind::TagEnd | SyntaxKind::Special))
+        else {
+            return;
+        };
+        let line = self.line(token.text_range().start);
+        let c = token.text().chars().next().unwrap_or_default();
+
+        match (token.kind(), c) {
+            (SyntaxKind::TagEnd, '@') => self.report(
+                Rule::UnclosedTag,
+                line,
+                String::from("'@end' without a verbatim tag to close."),
+                None,
+            ),
+            (SyntaxKind::TagEnd, _) => self.report(
+                Rule::UnclosedTag,
+                line,
+                format!("'{}end' without a ranged tag to close.", c),
+                None,
+            ),
+            _ => self.report(
+                Rule::MismatchedCloser,
+                line,
+                format!("'{0}{0}' without a modifier to close.", c),
+                None,
+            ),
+        }
+    }
+
+    fn ranged_tag_end(&mut self, node: &SyntaxNode) {
+        let closed = node
+            .children_with_tokens()
+            .any(|child| child.kind() == SyntaxKind::TagEnd);
+        let Some((c, _)) = modifier(node).filter(|_| !closed) else {
+            return;
+        };
+
+        let start = node.text_range().start;
+        let fix = self.append(format!("{}{}end", self.indent(start), c));
+        self.report(
+            Rule::UnclosedTag,
+            self.line(start),
+            format!("Ranged tag without a matching '{}end'.", c),
+            Some(fix),
+        );
+    }
+
+    fn ranged_modifier_end(&mut self, node: &SyntaxNode, open: char) {
+        let Some((c, offset)) = closer(node).filter(|(c, _)| *c != open) else {
+            return;
+        };
+
+        self.report(
+            Rule::MismatchedCloser,
+            self.line(offset),
+            format!(
+                "Expected '{0}{0}' to close the modifier on line {1}, found '{2}{2}'.",
+                open,
+                self.line(node.text_range().start) + 1,
+                c
+            ),
+            Some(TextEdit {
+                range: offset..offset + 2,
+                replacement: open.to_string().repeat(2),
+            }),
+        );
+    }
+
+    fn token(&mut self, token: &SyntaxToken) {
+        match token.kind() {
+            SyntaxKind::Whitespace => {
+                self.whitespace.get_or_insert(token.text_range().start);
+            }
+            SyntaxKind::Newline => self.trailing_whitespace(token.text_range().start),
+            _ => self.whitespace = None,
+        }
+    }
+
+    fn trailing_whitespace(&mut self, end: usize) {
+        let Some(start) = self.whitespace.take() else {
+            return;
+        };
+
+        let mut trimmed = start + self.source[start..end].trim_end_matches([' ', '\t']).len();
+        // A detached modifier without content, e.g. `- `, needs one space to stay one.
+        let marker = self.indent(start).trim_start_matches([' ', '\t']);
+        if marker.starts_with(DETACHED_MODIFIERS) && marker.chars().all_equal() {
+            trimmed = trimmed.max(start + 1);
+        }
+        if trimmed < end {
+            self.report(
+                Rule::TrailingWhitespace,
+                self.line(start),
+                String::from("Trailing whitespace."),
+                Some(TextEdit {
+                    range: trimmed..end,
+                    replacement: String::new(),
+                }),
+            );
+        }
+    }
+
+    fn node(&mut self, node: &SyntaxNode) {
+        let start = node.text_range().start;
+        let modifier = modifier(node);
+
+        match (node.kind(), modifier) {
+            (SyntaxKind::Heading, Some((_, level))) => {
+                let title = node
+                    .children()
+                    .find(|child| child.kind() == SyntaxKind::Title)
+                    .map(|title| title.text())
+                    .unwrap_or_default();
+                let extensions = extension_section(node);
+                self.heading(start, level, extensions.as_deref(), title.trim());
+            }
+            (SyntaxKind::NestableDetachedModifier, Some((c, level))) => {
+                let extensions = extension_section(node);
+                self.list_item(start, c, level, extensions.as_deref());
+            }
+            (SyntaxKind::RangeableDetachedModifier, Some((_, level))) => {
+                let text = node.text();
+                let first_line = text.lines().next().unwrap_or_default();
+                self.check_extensions(self.line(start), split_extensions(&first_line[level..]).0);
+            }
+            (SyntaxKind::DelimitingModifier, Some((c, _))) => {
+                match c {
+                    '=' => self.headings.clear(),
+                    '-' => {
+                        self.headings.pop();
+                    }
+                    _ => {}
+                }
+                self.list_level = 0;
+            }
+            (SyntaxKind::Paragraph, _) => self.paragraph(node),
+            (SyntaxKind::Error, _) => self.stray_closer(node),
+            _ => {}
+        }
+
+        for child in node.children_with_tokens() {
+            match child {
+                // The content of verbatim ranged tags is never linted.
+                SyntaxElement::Node(child) if child.kind() == SyntaxKind::VerbatimContent => {
+                    self.whitespace = None
+                }
+                SyntaxElement::Node(child) => self.node(&child),
+                SyntaxElement::Token(token) => self.token(&token),
+            }
+        }
+
+        match (node.kind(), modifier) {
+            (SyntaxKind::RangedTag, _) => self.ranged_tag_end(node),
+            (SyntaxKind::RangeableDetachedModifier, Some((c, 2))) => {
+                self.ranged_modifier_end(node, c)
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Lints a document.
+///
+/// # Arguments
+///
+/// * `source` - The source of the document.
+/// * `config` - Which rules to run.
+///
+/// # Returns
+///
+/// * `Ok(Vec<Diagnostic>)` with the diagnostics for all enabled rules, in the order they were
+///   found.
+/// * `Err(NorgParseError)` if the document can't be parsed into a [`crate::cst`] tree.
+pub fn lint(source: &str, config: &LintConfig) -> Result<Vec<Diagnostic>, NorgParseError> {
+    let cst = parse_cst(source)?;
+
+    let mut linter = Linter {
+        config: config.clone(),
+        source,
+        lines: std::iter::once(0)
+            .chain(source.match_indices('\n').map(|(i, _)| i + 1))
+            .collect(),
+        ..Default::default()
+    };
+
+    linter.node(&cst);
+    linter.trailing_whitespace(source.len());
+    Ok(linter.diagnostics)
+}
+
+/// Applies the fixes of the given diagnostics to the source they were created for. Fixes that
+/// overlap an earlier fix are skipped, fixes at the same position are applied in order.
+pub fn apply_fixes(source: &str, diagnostics: &[Diagnostic]) -> String {
+    let mut fixes: Vec<_> = diagnostics
+        .iter()
+        .filter_map(|diagnostic| diagnostic.fix.as_ref())
+        .collect();
+    fixes.sort_by_key(|fix| fix.range.start);
+
+    let mut result = String::new();
+    let mut position = 0;
+    for fix in fixes {
+        if fix.range.start < position {
+            continue;
+        }
+
+        result.push_str(&source[position..fix.range.start]);
+        result.push_str(&fix.replacement);
+        position = fix.range.end;
+    }
+
+    result.push_str(&source[position..]);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        lint::{apply_fixes, lint, LintConfig, Rule},
+        metadata::parse_metadata,
+        parse,
+    };
+
+    fn rules(source: &str, config: &LintConfig) -> Vec<(Rule, usize)> {
+        lint(source, config)
+            .unwrap()
+            .into_iter()
+            .map(|diagnostic| (diagnostic.rule, diagnostic.line))
+            .collect()
+    }
+
+    #[test]
+    fn structure() {
+        let source = "* One\n*** Three\n** Two\n   - item\n   --- deep item\n   ---\n* \n* One\n  $$ Term\n  definition\n  ^^\n";
+
+        assert_eq!(
+            rules(source, &LintConfig::default()),
+            [
+                (Rule::SkippedHeadingLevel, 1),
+                (Rule::ListLevelJump, 4),
+                (Rule::EmptyHeading, 6),
+                (Rule::DuplicateHeading, 7),
+                (Rule::MismatchedCloser, 10),
+            ]
+        );
+        assert_eq!(
+            apply_fixes(source, &lint(source, &LintConfig::default()).unwrap()),
+            "* One\n** Three\n** Two\n   - item\n   -- deep item\n   ---\n* \n* One\n  $$ Term\n  definition\n  $$\n"
+        );
+    }
+
+    #[test]
+    fn extensions() {
+        let source = "* (#A) Heading\n- (y) unknown status\n- (see below) item\n$ (x|) Term\ndefinition\n- (x|# A) done\n";
+
+        assert_eq!(
+            lint(source, &LintConfig::default()).unwrap()
+                .into_iter()
+                .map(|diagnostic| (diagnostic.line, diagnostic.message))
+                .collect::<Vec<_>>(),
+            [
+                (0, String::from("Invalid detached modifier extension '(#A)': Expected a space followed by a priority after '#'.")),
+                (1, String::from("Invalid detached modifier extension '(y)': Unexpected 'y'.")),
+                (2, String::from("Invalid detached modifier extension '(see below)': Unexpected 'see'.")),
+                (3, String::from("Invalid detached modifier extension '(x|)': Unexpected end of extension.")),
+            ]
+        );
+    }
+
+    #[test]
+    fn tags() {
+        let source = "|example\n  * Heading  \n  @code lua\n  print('ignored')  \n  @end\n  =macro\n  text\n";
+        let diagnostics = lint(source, &LintConfig::default()).unwrap();
+
+        assert_eq!(
+            rules(source, &LintConfig::default()),
+            [
+                (Rule::TrailingWhitespace, 1),
+                (Rule::UnclosedTag, 5),
+                (Rule::UnclosedTag, 0),
+            ]
+        );
+        assert_eq!(
+            apply_fixes(source, &diagnostics),
+            "|example\n  * Heading\n  @code lua\n  print('ignored')  \n  @end\n  =macro\n  text\n  =end\n|end\n"
+        );
+
+        assert_eq!(
+            rules("|end\n@end\n", &LintConfig::default()),
+            [(Rule::UnclosedTag, 0), (Rule::UnclosedTag, 1)]
+        );
+        assert_eq!(
+            apply_fixes(
+                "@code\nprint()",
+                &lint("@code\nprint()", &LintConfig::default()).unwrap()
+            ),
+            "@code\nprint()\n@end\n"
+        );
+    }
+
+    #[test]
+    fn empty_detached_modifiers() {
+        let fix =
+            |source: &str| apply_fixes(source, &lint(source, &LintConfig::default()).unwrap());
+
+        for source in ["- \ntext\n", "* \ntext\n", "  ~ \n", "$ \ndefinition\n"] {
+            assert!(!rules(source, &LintConfig::default()).contains(&(Rule::TrailingWhitespace, 0)));
+        }
+        for source in ["-   \ntext\n", "* \t\n", "- item  \n", "> \t\ntext\n"] {
+            assert_eq!(parse(&fix(source)).unwrap(), parse(source).unwrap());
+        }
+        assert_eq!(fix("-   \ntext\n"), "- \ntext\n");
+        assert_eq!(fix("- item  \n"), "- item\n");
+    }
+
+    #[test]
+    fn unicode() {
+        assert_eq!(rules("é\n", &LintConfig::default()), []);
+        assert_eq!(
+            rules(
+                "* Heading\nünïcode paragraph \n** Überschrift\n",
+                &LintConfig::default()
+            ),
+            [(Rule::TrailingWhitespace, 1)]
+        );
+        assert_eq!(
+            rules("* Ünïcode\n*** Ünïcode\n", &LintConfig::default()),
+            [(Rule::SkippedHeadingLevel, 1), (Rule::DuplicateHeading, 1)]
+        );
+    }
+
+    #[test]
+    fn config() {
+        let config = LintConfig::from_meta(
+            &parse_metadata(
+                "disabled: [
+                   trailing-whitespace
+                 ]
+                 due-date-headings: Work",
+            )
+            .unwrap(),
+        )
+        .unwrap();
+
+        assert_eq!(config.disabled, [Rule::TrailingWhitespace]);
+        assert_eq!(
+            rules(
+                "* Work \n  - ( ) no date\n  - (x) done\n  - (!|< Feb 1) dated\n** (?) heading\n* Home\n  - ( ) no date\n",
+                &config
+            ),
+            [(Rule::MissingDueDate, 1), (Rule::MissingDueDate, 4)]
+        );
+
+        assert!(LintConfig::from_meta(&parse_metadata("disabled: typo-rule").unwrap()).is_err());
+        assert!(LintConfig::from_meta(&parse_metadata("unknown: option").unwrap()).is_err());
+    }
+}
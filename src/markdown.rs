@@ -0,0 +1,819 @@
+//! Converts Markdown into Norg, e.g. to migrate a wiki.
+//!
+//! CommonMark is supported along with the GitHub extensions for tables, task lists,
+//! strikethrough and footnotes. YAML front matter becomes the document's metadata. The result
+//! is a [`Doc`], which can be printed as Norg or turned into an AST.
+//!
+//! Not everything in Markdown can be written in Norg. Each conversion that loses information
+//! is reported as a [`Loss`], e.g. list items with more than one paragraph, which are joined,
+//! or blocks inside list items, which are moved after the list.
+//!
+//! ```
+//! use rust_norg::markdown::from_markdown;
+//!
+//! let import = from_markdown("# Tasks\n\n- [x] Write *docs*\n");
+//! assert_eq!(import.document.to_string(), "* Tasks\n  - (x) Write /docs/\n");
+//! assert!(import.losses.is_empty());
+//! ```
+
+use pulldown_cmark::{Alignment, CodeBlockKind, Event, LinkType, OffsetIter, Options, Parser, Tag};
+use yaml_rust2::{Yaml, YamlLoader};
+
+use std::{iter::Peekable, ops::Range};
+
+use crate::{
+    builder::{escape, Doc, Import, List, Loss},
+    metadata::{print_metadata, retyped_strings, NorgMeta},
+    pandoc::cell_name,
+    NestableDetachedModifier, TodoStatus,
+};
+
+/// Inline markup under construction. Text is collected separately, so it can be escaped as a
+/// whole.
+#[derive(Default)]
+struct Markup {
+    out: String,
+    text: String,
+    /// The position of the attached modifier at the end of `out`, if there is one.
+    modifier: Option<usize>,
+}
+
+impl Markup {
+    fn flush(&mut self) {
+        self.out.push_str(&escape(&std::mem::take(&mut self.text)));
+    }
+
+    fn push(&mut self, markup: &str) {
+        self.flush();
+        self.out.push_str(markup);
+        self.modifier = None;
+    }
+
+    /// Adds text. Returns `false` if an attached modifier right before it had to be dropped,
+    /// because it can't be closed inside a word.
+    fn push_text(&mut self, text: &str) -> bool {
+        let mut kept = true;
+        if let Some(start) = self.modifier.take() {
+            if self.text.is_empty() && text.starts_with(char::is_alphanumeric) {
+                self.out.pop();
+                self.out.remove(start);
+                kept = false;
+            }
+        }
+        self.text.push_str(text);
+        kept
+    }
+
+    fn finish(mut self) -> String {
+        self.flush();
+        self.out.trim().to_string()
+    }
+}
+
+/// The target of a Norg link for a Markdown link destination, e.g. `{:notes:}` for `notes.md`.
+fn link_target(url: &str, link_type: LinkType) -> Option<String> {
+    if url.contains('}') || url.is_empty() {
+        return None;
+    }
+    if link_type == LinkType::Email {
+        return Some(format!("mailto:{url}"));
+    }
+    if url.contains("://") || url.starts_with("mailto:") {
+        return Some(url.to_string());
+    }
+    if let Some(fragment) = url.strip_prefix('#') {
+        return Some(format!("# {}", fragment.replace('-', " ")));
+    }
+    match url
+        .strip_suffix(".md")
+        .or_else(|| url.strip_suffix(".markdown"))
+    {
+        Some(file) if !file.contains('#') => Some(format!(":{file}:")),
+        _ => Some(format!("/ {url}")),
+    }
+}
+
+fn yaml_to_meta(yaml: &Yaml) -> NorgMeta {
+    match yaml {
+        Yaml::Real(value) => value.parse().map_or(NorgMeta::Invalid, NorgMeta::Num),
+        Yaml::Integer(value) => NorgMeta::Num(*value as f64),
+        Yaml::String(value) => NorgMeta::Str(value.clone()),
+        Yaml::Boolean(value) => NorgMeta::Bool(*value),
+        Yaml::Array(values) => NorgMeta::Array(values.iter().map(yaml_to_meta).collect()),
+        Yaml::Hash(entries) => NorgMeta::Object(
+            entries
+                .iter()
+                .map(|(key, value)| {
+                    let key = match key {
+                        Yaml::String(key) | Yaml::Real(key) => key.clone(),
+                        Yaml::Integer(key) => key.to_string(),
+                        Yaml::Boolean(key) => key.to_string(),
+                        _ => String::new(),
+                    };
+                    (key, yaml_to_meta(value))
+                })
+                .collect(),
+        ),
+        Yaml::Null => NorgMeta::Nil,
+        Yaml::Alias(_) | Yaml::BadValue => NorgMeta::Invalid,
+    }
+}
+
+/// A list item or quote paragraph, with the items nested below it.
+struct Item {
+    task: Option<bool>,
+    text: String,
+    children: Vec<Item>,
+}
+
+/// A block that can be written in Norg.
+enum Block {
+    Heading(u16, String),
+    Paragraph(String),
+    Image(String),
+    List(NestableDetachedModifier, Vec<Item>),
+    Code(String, String),
+    Rule,
+    /// Table cells with their names, e.g. `A1`.
+    Table(Vec<(String, String)>),
+    Footnote(String, String),
+}
+
+struct Converter<'a> {
+    source: &'a str,
+    events: Peekable<OffsetIter<'a>>,
+    /// The offset of the last event.
+    offset: usize,
+    meta: Option<NorgMeta>,
+    /// The state of the task list marker of the current list item.
+    task: Option<bool>,
+    /// The sources and markup of the images in the current paragraph.
+    images: Vec<(String, String)>,
+    losses: Vec<Loss>,
+}
+
+impl<'a> Converter<'a> {
+    fn next(&mut self) -> Option<(Event<'a>, Range<usize>)> {
+        let (event, range) = self.events.next()?;
+        self.offset = range.start;
+        Some((event, range))
+    }
+
+    fn lose(&mut self, message: &str) {
+        self.losses.push(Loss {
+            line: self.source[..self.offset].matches('\n').count(),
+            message: message.to_string(),
+        });
+    }
+
+    /// Collects the text of a code block, HTML block or front matter up to its end.
+    fn text(&mut self) -> String {
+        let mut text = String::new();
+        while let Some((event, _)) = self.next() {
+            match event {
+                Event::Text(content) | Event::Html(content) => text.push_str(&content),
+                Event::End(_) => break,
+                _ => (),
+            }
+        }
+        text
+    }
+
+    /// Skips events up to the end of the current tag.
+    fn skip(&mut self) {
+        let mut depth = 0;
+        while let Some((event, _)) = self.next() {
+            match event {
+                Event::Start(_) => depth += 1,
+                Event::End(_) if depth == 0 => break,
+                Event::End(_) => depth -= 1,
+                _ => (),
+            }
+        }
+    }
+
+    fn push_text(&mut self, markup: &mut Markup, text: &str) {
+        if !markup.push_text(text) {
+            self.lose("emphasis inside a word was dropped");
+        }
+    }
+
+    /// Converts the inline events up to the end of the current block or inline tag, which is
+    /// left for the caller.
+    fn inlines(&mut self) -> String {
+        let mut markup = Markup::default();
+
+        while let Some((event, _)) = self.events.peek() {
+            let inline = matches!(
+                event,
+                Event::Start(
+                    Tag::Emphasis
+                        | Tag::Strong
+                        | Tag::Strikethrough
+                        | Tag::Superscript
+                        | Tag::Subscript
+                        | Tag::Link { .. }
+                        | Tag::Image { .. }
+                ) | Event::Text(_)
+                    | Event::Code(_)
+                    | Event::InlineMath(_)
+                    | Event::DisplayMath(_)
+                    | Event::InlineHtml(_)
+                    | Event::FootnoteReference(_)
+                    | Event::SoftBreak
+                    | Event::HardBreak
+                    | Event::TaskListMarker(_)
+            );
+            if !inline {
+                break;
+            }
+
+            let Some((event, _)) = self.next() else { break };
+            match event {
+                Event::Text(text) => self.push_text(&mut markup, &text),
+                Event::Code(code) => {
+                    if code.contains('`') || code.trim().is_empty() {
+                        self.lose("inline code containing a backtick was kept as text");
+                        self.push_text(&mut markup, &code);
+                    } else {
+                        markup.push(&format!("`{code}`"));
+                    }
+                }
+                Event::InlineMath(math) | Event::DisplayMath(math) => {
+                    if math.contains('$') || math.trim() != &*math || math.is_empty() {
+                        self.lose("math containing `$` or surrounding whitespace was kept as text");
+                        self.push_text(&mut markup, &math);
+                    } else {
+                        markup.push(&format!("${math}$"));
+                    }
+                }
+                Event::InlineHtml(html) => {
+                    self.lose("inline HTML was kept as text");
+                    self.push_text(&mut markup, &html);
+                }
+                Event::FootnoteReference(label) => {
+                    markup.push(&format!("{{^ {}}}", escape(&label)))
+                }
+                Event::SoftBreak => self.push_text(&mut markup, " "),
+                Event::HardBreak => {
+                    self.lose("a hard line break became a space");
+                    self.push_text(&mut markup, " ");
+                }
+                Event::TaskListMarker(checked) => self.task = Some(checked),
+                Event::Start(tag) => {
+                    let content = self.inlines();
+                    self.next();
+                    self.push_inline_tag(&mut markup, tag, content);
+                }
+                _ => (),
+            }
+        }
+
+        markup.finish()
+    }
+
+    fn push_inline_tag(&mut self, markup: &mut Markup, tag: Tag, content: String) {
+        let modifier = match tag {
+            Tag::Emphasis => '/',
+            Tag::Strong => '*',
+            Tag::Strikethrough => '-',
+            Tag::Link {
+                link_type,
+                dest_url,
+                ..
+            } => {
+                let Some(target) = link_target(&dest_url, link_type) else {
+                    self.lose("a link to a destination containing `}` was kept as text");
+                    return markup.push(&content);
+                };
+                return if content.is_empty() || content == escape(&dest_url) {
+                    markup.push(&format!("{{{target}}}"))
+                } else {
+                    markup.push(&format!("{{{target}}}[{content}]"))
+                };
+            }
+            Tag::Image { dest_url, .. } => {
+                let source = dest_url.replace(' ', "%20");
+                let link = match content.is_empty() {
+                    true => format!("{{/ {source}}}"),
+                    false => format!("{{/ {source}}}[{content}]"),
+                };
+                self.images.push((source, link.clone()));
+                return markup.push(&link);
+            }
+            _ => return markup.push(&content),
+        };
+
+        if content.is_empty() {
+            return;
+        }
+        markup.flush();
+        if markup.out.ends_with(char::is_alphanumeric) {
+            self.lose("emphasis inside a word was dropped");
+            return markup.push(&content);
+        }
+        let start = markup.out.len();
+        markup.push(&format!("{modifier}{content}{modifier}"));
+        markup.modifier = Some(start);
+    }
+
+    /// Converts a paragraph, which becomes an `.image` tag if it's just an image.
+    fn paragraph(&mut self) -> Block {
+        self.images.clear();
+        let text = self.inlines();
+
+        match self.images.as_slice() {
+            [(source, link)] if *link == text => Block::Image(source.clone()),
+            images => {
+                for _ in 0..images.len() {
+                    self.lose("an image inside a paragraph became a link");
+                }
+                Block::Paragraph(text)
+            }
+        }
+    }
+
+    /// Converts the blocks up to the end of the current tag.
+    fn blocks(&mut self) -> Vec<Block> {
+        let mut blocks = Vec::new();
+
+        while let Some((event, _)) = self.events.peek() {
+            match event {
+                Event::End(_) => {
+                    self.next();
+                    break;
+                }
+                Event::Start(
+                    Tag::Paragraph
+                    | Tag::Heading { .. }
+                    | Tag::BlockQuote(_)
+                    | Tag::CodeBlock(_)
+                    | Tag::HtmlBlock
+                    | Tag::List(_)
+                    | Tag::Item
+                    | Tag::FootnoteDefinition(_)
+                    | Tag::DefinitionList
+                    | Tag::DefinitionListTitle
+                    | Tag::DefinitionListDefinition
+                    | Tag::Table(_)
+                    | Tag::TableHead
+                    | Tag::TableRow
+                    | Tag::TableCell
+                    | Tag::MetadataBlock(_),
+                )
+                | Event::Rule => {
+                    let Some((event, _)) = self.next() else { break };
+                    self.push_block(&mut blocks, event);
+                }
+                // The content of tight list items isn't wrapped in a paragraph.
+                _ => {
+                    let paragraph = self.paragraph();
+                    blocks.push(paragraph);
+                }
+            }
+        }
+
+        blocks
+    }
+
+    fn push_block(&mut self, blocks: &mut Vec<Block>, event: Event) {
+        match event {
+            Event::Start(Tag::Paragraph) => {
+                let paragraph = self.paragraph();
+                self.next();
+                blocks.push(paragraph);
+            }
+            Event::Start(Tag::Heading { level, .. }) => {
+                let title = self.inlines();
+                self.next();
+                if title.is_empty() {
+                    self.lose("an empty heading was dropped");
+                } else {
+                    blocks.push(Block::Heading(level as u16, title));
+                }
+            }
+            Event::Start(Tag::BlockQuote(kind)) => {
+                if kind.is_some() {
+                    self.lose("the kind of an alert became a plain quote");
+                }
+                let content = self.blocks();
+                let (items, moved) = self.quote(content);
+                blocks.push(Block::List(NestableDetachedModifier::Quote, items));
+                blocks.extend(moved);
+            }
+            Event::Start(Tag::CodeBlock(kind)) => {
+                let language = match kind {
+                    CodeBlockKind::Fenced(info) => info
+                        .split_whitespace()
+                        .next()
+                        .unwrap_or_default()
+                        .to_string(),
+                    CodeBlockKind::Indented => String::new(),
+                };
+                let language = if language
+                    .chars()
+                    .all(|c| c.is_alphanumeric() || "+-_.#".contains(c))
+                {
+                    language
+                } else {
+                    self.lose("the language of a code block was dropped");
+                    String::new()
+                };
+                let code = self.code();
+                blocks.push(Block::Code(language, code));
+            }
+            Event::Start(Tag::HtmlBlock) => {
+                let html = self.text();
+                self.lose("HTML was kept as a code block");
+                blocks.push(Block::Code(String::from("html"), html));
+            }
+            Event::Start(Tag::List(start)) => {
+                let modifier_type = match start {
+                    Some(1) => NestableDetachedModifier::OrderedList,
+                    Some(_) => {
+                        self.lose("the start number of an ordered list was dropped");
+                        NestableDetachedModifier::OrderedList
+                    }
+                    None => NestableDetachedModifier::UnorderedList,
+                };
+                let (items, moved) = self.list(&modifier_type);
+                blocks.push(Block::List(modifier_type, items));
+                blocks.extend(moved);
+            }
+            Event::Start(Tag::FootnoteDefinition(label)) => {
+                let content = self.blocks();
+                let (text, moved) = self.join(content, "a footnote");
+                if text.is_empty() {
+                    self.lose("a footnote without text was dropped");
+                } else {
+                    blocks.push(Block::Footnote(escape(&label), text));
+                }
+                blocks.extend(moved);
+            }
+            Event::Start(Tag::Table(alignments)) => {
+                if alignments.iter().any(|a| *a != Alignment::None) {
+                    self.lose("the alignment of table columns was dropped");
+                }
+                let table = self.table();
+                blocks.push(table);
+            }
+            Event::Start(Tag::MetadataBlock(_)) => {
+                let yaml = self.text();
+                match YamlLoader::load_from_str(&yaml) {
+                    Ok(documents) => {
+                        let meta = documents.first().map(yaml_to_meta);
+                        for value in meta.iter().flat_map(retyped_strings) {
+                            self.lose(&format!(
+                                "the metadata string `{value}` is read back as another value"
+                            ));
+                        }
+                        self.meta = meta;
+                    }
+                    Err(_) => self.lose("front matter that isn't valid YAML was dropped"),
+                }
+            }
+            Event::Start(_) => {
+                self.lose("an unsupported block was dropped");
+                self.skip();
+            }
+            Event::Rule => blocks.push(Block::Rule),
+            _ => (),
+        }
+    }
+
+    fn code(&mut self) -> String {
+        let code = self.text();
+        if code.lines().any(|line| line.trim() == "@end") {
+            self.lose("an `@end` line in a code block was dropped");
+            return code
+                .lines()
+                .filter(|line| line.trim() != "@end")
+                .map(|line| format!("{line}\n"))
+                .collect();
+        }
+        code
+    }
+
+    /// Joins the paragraphs of a list item or footnote into a single one. Other blocks are
+    /// returned to be moved after it.
+    fn join(&mut self, blocks: Vec<Block>, object: &str) -> (String, Vec<Block>) {
+        let mut paragraphs = Vec::new();
+        let mut moved = Vec::new();
+        for block in blocks {
+            match block {
+                Block::Paragraph(text) if moved.is_empty() => paragraphs.push(text),
+                block => moved.push(block),
+            }
+        }
+
+        if paragraphs.len() > 1 {
+            self.lose(&format!("the paragraphs of {object} were joined"));
+        }
+        if !moved.is_empty() {
+            self.lose(&format!("blocks inside {object} were moved after it"));
+        }
+        (paragraphs.join(" "), moved)
+    }
+
+    /// Converts the items of a list. Blocks that can't be part of an item are returned to be
+    /// moved after the list.
+    fn list(&mut self, modifier_type: &NestableDetachedModifier) -> (Vec<Item>, Vec<Block>) {
+        let mut items = Vec::new();
+        let mut moved = Vec::new();
+
+        while let Some((event, _)) = self.next() {
+            if event != Event::Start(Tag::Item) {
+                break;
+            }
+
+            // Nested lists have their own task list markers.
+            let outer = self.task.take();
+            let mut content = self.blocks();
+            let task = std::mem::replace(&mut self.task, outer);
+
+            // Nested lists are split off before the paragraphs are joined.
+            let mut children = Vec::new();
+            content.retain_mut(|block| match block {
+                Block::List(nested_type, nested) if children.is_empty() => {
+                    if nested_type != modifier_type {
+                        self.lose("a nested list took the type of the list it is in");
+                    }
+                    children = std::mem::take(nested);
+                    false
+                }
+                _ => true,
+            });
+
+            let (text, item_moved) = self.join(content, "a list item");
+            moved.extend(item_moved);
+
+            if text.is_empty() {
+                self.lose("a list item without text was dropped");
+                items.extend(children);
+            } else {
+                items.push(Item {
+                    task,
+                    text,
+                    children,
+                });
+            }
+        }
+
+        (items, moved)
+    }
+
+    /// Converts the content of a block quote to quote items, a paragraph each.
+    fn quote(&mut self, blocks: Vec<Block>) -> (Vec<Item>, Vec<Block>) {
+        let mut items: Vec<Item> = Vec::new();
+        let mut moved = Vec::new();
+
+        for block in blocks {
+            match block {
+                Block::Paragraph(text) => items.push(Item {
+                    task: None,
+                    text,
+                    children: Vec::new(),
+                }),
+                Block::List(NestableDetachedModifier::Quote, nested) => match items.last_mut() {
+                    Some(item) => item.children.extend(nested),
+                    None => items.extend(nested),
+                },
+                block => moved.push(block),
+            }
+        }
+
+        if !moved.is_empty() {
+            self.lose("blocks inside a quote were moved after it");
+        }
+        (items, moved)
+    }
+
+    fn table(&mut self) -> Block {
+        let mut cells = Vec::new();
+        let mut row = 0;
+
+        while let Some((event, _)) = self.next() {
+            match event {
+                Event::Start(Tag::TableHead | Tag::TableRow) => {
+                    let mut column = 0;
+                    while let Some((event, _)) = self.next() {
+                        if event != Event::Start(Tag::TableCell) {
+                            break;
+                        }
+                        let text = self.paragraph_text();
+                        self.next();
+                        if !text.is_empty() {
+                            cells.push((cell_name(row, column), text));
+                        }
+                        column += 1;
+                    }
+                    row += 1;
+                }
+                _ => break,
+            }
+        }
+
+        Block::Table(cells)
+    }
+
+    /// Converts inline content where an `.image` tag isn't possible.
+    fn paragraph_text(&mut self) -> String {
+        match self.paragraph() {
+            Block::Image(source) => {
+                self.lose("an image inside a paragraph became a link");
+                format!("{{/ {source}}}")
+            }
+            Block::Paragraph(text) => text,
+            _ => String::new(),
+        }
+    }
+}
+
+fn push_items(list: List, items: &[Item]) -> List {
+    items.iter().fold(list, |list, item| {
+        let list = list.item(&item.text);
+        let list = match item.task {
+            Some(true) => list.todo(TodoStatus::Done),
+            Some(false) => list.todo(TodoStatus::Undone),
+            None => list,
+        };
+        if item.children.is_empty() {
+            list
+        } else {
+            list.nested(|list| push_items(list, &item.children))
+        }
+    })
+}
+
+fn push_block(doc: Doc, block: &Block) -> Doc {
+    match block {
+        Block::Heading(level, title) => doc.heading(*level, title),
+        Block::Paragraph(text) => doc.paragraph(text),
+        Block::Image(source) => doc.infirm_tag("image", &[source]),
+        Block::List(NestableDetachedModifier::UnorderedList, items) => {
+            doc.list(|list| push_items(list, items))
+        }
+        Block::List(NestableDetachedModifier::OrderedList, items) => {
+            doc.ordered_list(|list| push_items(list, items))
+        }
+        Block::List(NestableDetachedModifier::Quote, items) => {
+            doc.quote(|list| push_items(list, items))
+        }
+        Block::Code(language, code) => doc.code(language, code),
+        Block::Rule => doc.horizontal_rule(),
+        Block::Table(cells) => cells
+            .iter()
+            .fold(doc, |doc, (cell, text)| doc.table_cell(cell, text)),
+        Block::Footnote(label, text) => doc.footnote(label, text),
+    }
+}
+
+/// Converts a Markdown document into a Norg document.
+///
+/// Headings keep their level, emphasis becomes `/italic/`, strong emphasis `*bold*` and task
+/// list items become todo items. Links to other Markdown files become links to Norg files,
+/// e.g. `{:notes:}` for `notes.md`, and images on their own become `.image` tags. Table cells
+/// are named by their position, like `: A1`.
+///
+/// # Arguments
+///
+/// * `source` - The Markdown document, optionally starting with YAML front matter.
+///
+/// # Returns
+///
+/// * `Import` with the document, its metadata and everything that couldn't be converted
+///   exactly.
+pub fn from_markdown(source: &str) -> Import {
+    let options = Options::ENABLE_TABLES
+        | Options::ENABLE_FOOTNOTES
+        | Options::ENABLE_STRIKETHROUGH
+        | Options::ENABLE_TASKLISTS
+        | Options::ENABLE_GFM
+        | Options::ENABLE_YAML_STYLE_METADATA_BLOCKS;
+
+    let mut converter = Converter {
+        source,
+        events: Parser::new_ext(source, options)
+            .into_offset_iter()
+            .peekable(),
+        offset: 0,
+        meta: None,
+        task: None,
+        images: Vec::new(),
+        losses: Vec::new(),
+    };
+    let blocks = converter.blocks();
+
+    let mut document = Doc::new();
+    if let Some(meta) = &converter.meta {
+        document = document.verbatim("document.meta", &[], &print_metadata(meta));
+    }
+    let document = blocks.iter().fold(document, push_block);
+
+    Import {
+        document,
+        meta: converter.meta,
+        losses: converter.losses,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use insta::assert_snapshot;
+
+    use crate::{builder::Loss, markdown::from_markdown, metadata::NorgMeta, parse};
+
+    #[test]
+    fn wiki_page() {
+        let source = "---
+title: Getting started
+tags: [setup, guide]
+draft: false
+---
+
+# Getting started
+
+Install it with `cargo install`, then read the [guide](guide.md) or
+visit <https://neorg.org>.[^1] Some **bold**, *italic* and ~~old~~ text.
+
+## Tasks
+
+- [x] Install
+- [ ] Configure
+  1. Pick a theme
+  2. Restart
+
+> A quote
+>
+> > nested
+
+```rust
+fn main() {}
+```
+
+| Key | Value |
+| --- | ----- |
+| a   | 1     |
+
+![logo](logo.png)
+
+---
+
+[^1]: The website.
+";
+        let import = from_markdown(source);
+        let text = import.document.to_string();
+
+        assert_eq!(parse(&text).unwrap(), import.document.build());
+        assert!(matches!(import.meta, Some(NorgMeta::Object(_))));
+        assert_eq!(
+            import.losses,
+            vec![Loss {
+                line: 14,
+                message: String::from("a nested list took the type of the list it is in"),
+            }]
+        );
+        assert_snapshot!(text);
+    }
+
+    #[test]
+    fn losses() {
+        let import = from_markdown(
+            "1. one\n\n   two\n\n   ```\n   code\n   ```\n3. three\n\nA<br>b  \nc, *in*word\n",
+        );
+
+        let messages: Vec<_> = import
+            .losses
+            .iter()
+            .map(|loss| (loss.line, loss.message.as_str()))
+            .collect();
+        assert_eq!(
+            messages,
+            [
+                (0, "the paragraphs of a list item were joined"),
+                (0, "blocks inside a list item were moved after it"),
+                (9, "inline HTML was kept as text"),
+                (9, "a hard line break became a space"),
+                (10, "emphasis inside a word was dropped"),
+            ]
+        );
+        assert_eq!(
+            import.document.to_string(),
+            "~ one two\n~ three\n\n@code\ncode\n@end\n\nA\\<br\\>b c, inword\n"
+        );
+
+        let import = from_markdown("---\ntitle: \"2024\"\ndraft: \"true\"\n---\n\nText\n");
+        let messages: Vec<_> = import
+            .losses
+            .iter()
+            .map(|loss| loss.message.as_str())
+            .collect();
+        assert_eq!(
+            messages,
+            [
+                "the metadata string `true` is read back as another value",
+                "the metadata string `2024` is read back as another value",
+            ]
+        );
+    }
+}
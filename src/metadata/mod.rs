@@ -1,3 +1,5 @@
+use std::collections::BTreeMap;
+
 use chumsky::Parser;
 pub use stage_1::NorgMeta;
 
@@ -13,12 +15,107 @@ pub fn parse_metadata(input: &str) -> Result<NorgMeta, NorgParseError> {
     Ok(stage_1::meta_parser().parse(processed)?)
 }
 
+fn push_string(out: &mut String, value: &str) {
+    for c in value.chars() {
+        match c {
+            '{' | '}' | '[' | ']' => (),
+            '\n' => out.push(' '),
+            c => out.push(c),
+        }
+    }
+}
+
+fn push_value(out: &mut String, meta: &NorgMeta, indent: usize) {
+    match meta {
+        NorgMeta::Invalid | NorgMeta::Nil | NorgMeta::EmptyKey(_) => out.push_str("nil"),
+        NorgMeta::Bool(value) => out.push_str(&value.to_string()),
+        NorgMeta::Str(value) => push_string(out, value),
+        NorgMeta::Num(value) => out.push_str(&value.to_string()),
+        NorgMeta::Array(values) if values.is_empty() => out.push_str("[]"),
+        NorgMeta::Array(values) => {
+            out.push_str("[\n");
+            for value in values {
+                out.push_str(&" ".repeat(indent + 2));
+                push_value(out, value, indent + 2);
+                out.push('\n');
+            }
+            out.push_str(&" ".repeat(indent));
+            out.push(']');
+        }
+        NorgMeta::Object(entries) => {
+            out.push_str("{\n");
+            push_entries(out, entries, indent + 2);
+            out.push_str(&" ".repeat(indent));
+            out.push('}');
+        }
+    }
+}
+
+fn push_entries(out: &mut String, entries: &BTreeMap<String, NorgMeta>, indent: usize) {
+    for (key, value) in entries {
+        out.push_str(&" ".repeat(indent));
+        out.extend(key.chars().filter(|c| !"{}[]:\n".contains(*c)));
+        out.push_str(": ");
+        push_value(out, value, indent);
+        out.push('\n');
+    }
+}
+
+/// The strings in `meta` which [`print_metadata`] prints as something that [`parse_metadata`]
+/// reads back as another type of value, e.g. `2024` as a number or `true` as a boolean.
+pub(crate) fn retyped_strings(meta: &NorgMeta) -> Vec<&str> {
+    match meta {
+        NorgMeta::Str(value) => {
+            let mut printed = String::from("value: ");
+            push_string(&mut printed, value);
+            match parse_metadata(&printed) {
+                Ok(NorgMeta::Object(entries))
+                    if matches!(entries.get("value"), Some(NorgMeta::Str(_))) =>
+                {
+                    Vec::new()
+                }
+                _ => vec![value],
+            }
+        }
+        NorgMeta::Array(values) => values.iter().flat_map(retyped_strings).collect(),
+        NorgMeta::Object(entries) => entries.values().flat_map(retyped_strings).collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Prints metadata in the syntax read by [`parse_metadata`], e.g. for the content of a
+/// `@document.meta` tag.
+///
+/// The entries of an object are printed as the top level properties. Characters that can't
+/// appear in keys (`{}[]:` and line breaks) or strings (`{}[]`) are dropped, line breaks in
+/// strings become spaces. The syntax has no quotes, so strings like `2024`, `true` or `nil`
+/// are read back as a number, a boolean or nil.
+///
+/// # Arguments
+///
+/// * `meta` - The metadata, usually an object.
+///
+/// # Returns
+///
+/// * `String` with a property per line.
+pub fn print_metadata(meta: &NorgMeta) -> String {
+    let mut out = String::new();
+    match meta {
+        NorgMeta::Object(entries) => push_entries(&mut out, entries, 0),
+        meta => {
+            push_value(&mut out, meta, 0);
+            out.push('\n');
+        }
+    }
+    out
+}
+
 #[cfg(test)]
 mod tests {
     use insta::assert_yaml_snapshot;
     use itertools::Itertools;
 
-    use crate::metadata::parse_metadata;
+    use crate::metadata::{parse_metadata, print_metadata, retyped_strings, NorgMeta};
 
     #[test]
     fn common_metadata() {
@@ -124,7 +221,7 @@ mod tests {
             "keys: {
               in:
               objects: []
-            }"
+            }",
         ]
         .into_iter()
         .map(|example| example.to_string() + "\n")
@@ -134,4 +231,41 @@ mod tests {
 
         assert_yaml_snapshot!(examples);
     }
+
+    #[test]
+    fn print_round_trip() {
+        let meta = parse_metadata(
+            "title: Notes (draft)
+            authors: [
+              Ada
+              Grace
+            ]
+            version: 1.5
+            published: false
+            tangle: {
+              languages: {
+                lua: ./init.lua
+              }
+              scope: []
+            }
+            updated: 2024-10-27T11:12:32-0500
+            ",
+        )
+        .unwrap();
+
+        let printed = print_metadata(&meta);
+        assert_eq!(parse_metadata(&printed).unwrap(), meta);
+        assert_eq!(print_metadata(&NorgMeta::Str("a [b]\nc".into())), "a b c\n");
+        assert_eq!(retyped_strings(&meta), Vec::<&str>::new());
+
+        let meta = NorgMeta::Array(vec![
+            NorgMeta::Str("2024".into()),
+            NorgMeta::Str(" true ".into()),
+            NorgMeta::Str("nil".into()),
+            NorgMeta::Str("[]".into()),
+            NorgMeta::Str("2024-10-27".into()),
+            NorgMeta::Num(2024.0),
+        ]);
+        assert_eq!(retyped_strings(&meta), ["2024", " true ", "nil", "[]"]);
+    }
 }
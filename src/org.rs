@@ -0,0 +1,2262 @@
+//! Converts between Org-mode and Norg.
+//!
+//! [`from_org`] imports an Org document as a [`Doc`] and [`to_org`] exports a document as Org.
+//! Headings keep their TODO keyword, priority and SCHEDULED and DEADLINE dates, which map to
+//! the todo status, priority, start date and due date extensions. Source blocks become `@code`
+//! tags, the property drawer and `#+KEYWORD:` lines of a document become its metadata and the
+//! property drawers of headings become `+attribute` carryover tags.
+//!
+//! Not everything can be written in the other format. Both directions report what couldn't be
+//! converted exactly, e.g. CLOSED dates or TODO keywords without a Norg status on import and
+//! todo statuses without an Org keyword on export.
+//!
+//! ```
+//! use rust_norg::{org::{from_org, to_org}, parse_tree};
+//!
+//! let import = from_org("* TODO [#A] Release\nDEADLINE: <2025-03-01 Sat>\n");
+//! let norg = import.document.to_string();
+//! assert_eq!(norg, "* ( |# A|< 2025-03-01 Sat) Release\n");
+//!
+//! let export = to_org(&parse_tree(&norg).unwrap());
+//! assert_eq!(export.org, "* TODO [#A] Release\nDEADLINE: <2025-03-01 Sat>\n");
+//! ```
+
+use std::collections::{btree_map::Entry, BTreeMap};
+
+use textwrap::indent;
+
+use crate::{
+    builder::{escape, Doc, Import, List, Loss},
+    metadata::{parse_metadata, print_metadata, retyped_strings, NorgMeta},
+    pandoc::{cell_name, cell_position, plain, same_group, References},
+    stage_1::SPECIAL_CHARS,
+    visit::Visitor,
+    CarryoverTag, DelimitingModifier, DetachedModifierExtension, LinkTarget,
+    NestableDetachedModifier, NorgAST, ParagraphSegment, ParagraphSegmentToken,
+    RangeableDetachedModifier, TodoStatus,
+};
+
+/// Characters that may come before the opening marker of emphasis, apart from whitespace.
+const PRE: &str = "-({'\"";
+/// Characters that may come after the closing marker of emphasis, apart from whitespace.
+const POST: &str = "-.,;:!?')}[\"\\";
+/// Keywords which belong to the element after them, e.g. `#+CAPTION:`.
+const AFFILIATED: [&str; 6] = ["CAPTION", "HEADER", "NAME", "PLOT", "RESULTS", "TBLFM"];
+const IMAGES: [&str; 8] = ["png", "jpg", "jpeg", "gif", "svg", "webp", "bmp", "tiff"];
+/// Written before a line of a paragraph that would be read as another element, e.g. `* text`.
+const ZERO_WIDTH_SPACE: char = '\u{200b}';
+
+/// The status of a TODO keyword. Keywords after the `|` in a `#+TODO:` line are done states.
+fn keyword_status(keyword: &str, done: bool) -> TodoStatus {
+    match keyword {
+        "CANCELLED" | "CANCELED" | "CANCEL" => TodoStatus::Canceled,
+        _ if done => TodoStatus::Done,
+        "NEXT" | "STARTED" | "DOING" | "ACTIVE" | "INPROGRESS" | "IN-PROGRESS" => {
+            TodoStatus::Pending
+        }
+        "WAITING" | "WAIT" | "HOLD" | "ON-HOLD" | "PAUSED" | "SOMEDAY" => TodoStatus::Paused,
+        _ => TodoStatus::Undone,
+    }
+}
+
+/// The TODO keyword written for a status.
+fn status_keyword(status: &TodoStatus) -> &'static str {
+    match status {
+        TodoStatus::Done => "DONE",
+        TodoStatus::Pending => "NEXT",
+        TodoStatus::Paused => "WAITING",
+        TodoStatus::Canceled => "CANCELLED",
+        _ => "TODO",
+    }
+}
+
+/// The TODO keywords set by `#+TODO:` lines, or `TODO` and `DONE` if there are none.
+fn todo_keywords(lines: &[&str]) -> Vec<(String, TodoStatus)> {
+    let mut keywords = Vec::new();
+
+    for line in lines {
+        let Some((key, value)) = keyword(line) else {
+            continue;
+        };
+        if !matches!(key.as_str(), "TODO" | "SEQ_TODO" | "TYP_TODO") {
+            continue;
+        }
+
+        // Fast access keys and logging settings, e.g. `WAIT(w@/!)`.
+        let words: Vec<_> = value
+            .split_whitespace()
+            .map(|word| word.split('(').next().unwrap_or_default())
+            .filter(|word| !word.is_empty())
+            .collect();
+        // Without a `|`, the last keyword is the only done state.
+        let separator = words
+            .iter()
+            .position(|word| *word == "|")
+            .unwrap_or(words.len().saturating_sub(1));
+        for (i, word) in words.into_iter().enumerate() {
+            if word != "|" {
+                keywords.push((word.to_string(), keyword_status(word, i >= separator)));
+            }
+        }
+    }
+
+    if keywords.is_empty() {
+        keywords = vec![
+            (String::from("TODO"), TodoStatus::Undone),
+            (String::from("DONE"), TodoStatus::Done),
+        ];
+    }
+    keywords
+}
+
+/// The level and the rest of a heading line, e.g. `** Title`.
+fn heading(line: &str) -> Option<(u16, &str)> {
+    let rest = line.trim_start_matches('*');
+    let level = line.len() - rest.len();
+    (level > 0 && (rest.is_empty() || rest.starts_with([' ', '\t'])))
+        .then(|| (level.min(usize::from(u16::MAX)) as u16, rest.trim()))
+}
+
+/// The lowercase name and the parameters of the first line of a block, e.g. `#+BEGIN_SRC rust`.
+fn block_start(line: &str) -> Option<(String, &str)> {
+    let line = line.trim();
+    line.get(..8)
+        .filter(|start| start.eq_ignore_ascii_case("#+begin_"))?;
+    let (name, parameters) = line[8..]
+        .split_once(char::is_whitespace)
+        .unwrap_or((&line[8..], ""));
+    (!name.is_empty()).then(|| (name.to_lowercase(), parameters.trim()))
+}
+
+/// The uppercase key and the value of a keyword line, e.g. `#+TITLE: Notes`.
+fn keyword(line: &str) -> Option<(String, &str)> {
+    let (key, value) = line.trim().strip_prefix("#+")?.split_once(':')?;
+    (!key.is_empty() && !key.contains(char::is_whitespace))
+        .then(|| (key.to_uppercase(), value.trim()))
+}
+
+/// The name of the drawer a line starts, e.g. `LOGBOOK` for `:LOGBOOK:`.
+fn drawer(line: &str) -> Option<&str> {
+    let name = line.trim().strip_prefix(':')?.strip_suffix(':')?;
+    (!name.is_empty()
+        && !name.eq_ignore_ascii_case("end")
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '_' || c == '-'))
+    .then_some(name)
+}
+
+fn is_rule(line: &str) -> bool {
+    let line = line.trim();
+    line.len() >= 5 && line.chars().all(|c| c == '-')
+}
+
+fn is_fixed_width(line: &str) -> bool {
+    let line = line.trim();
+    line == ":" || line.starts_with(": ")
+}
+
+fn is_comment(line: &str) -> bool {
+    let line = line.trim();
+    line == "#" || line.starts_with("# ")
+}
+
+/// Whether text starts with a date like `2025-03-01`, as timestamps do.
+fn is_date(text: &str) -> bool {
+    let bytes = text.as_bytes();
+    bytes.len() >= 10
+        && bytes[..10].iter().enumerate().all(|(i, byte)| match i {
+            4 | 7 => *byte == b'-',
+            _ => byte.is_ascii_digit(),
+        })
+}
+
+/// The label and the text of a footnote definition, e.g. `[fn:1] text`.
+fn footnote(line: &str) -> Option<(&str, &str)> {
+    let (label, text) = line.strip_prefix("[fn:")?.split_once(']')?;
+    (!label.is_empty()
+        && label
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '_' || c == '-'))
+    .then(|| (label, text.trim()))
+}
+
+/// The start of a list item.
+struct Bullet<'a> {
+    indent: usize,
+    ordered: bool,
+    text: &'a str,
+}
+
+fn bullet(line: &str) -> Option<Bullet<'_>> {
+    let text = line.trim_start();
+    let indent = line.len() - text.len();
+
+    let digits = text.len() - text.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+    let length = if digits > 0 && text[digits..].starts_with(['.', ')']) {
+        digits + 1
+    } else if text.starts_with(['-', '+']) || (indent > 0 && text.starts_with('*')) {
+        1
+    } else {
+        return None;
+    };
+
+    let rest = &text[length..];
+    (rest.is_empty() || rest.starts_with([' ', '\t'])).then(|| Bullet {
+        indent,
+        ordered: digits > 0,
+        text: rest.trim(),
+    })
+}
+
+/// The status of the checkbox at the start of a list item, and the text after it.
+fn checkbox(text: &str) -> (Option<TodoStatus>, &str) {
+    let status = match text.get(..3) {
+        Some("[ ]") => TodoStatus::Undone,
+        Some("[X]" | "[x]") => TodoStatus::Done,
+        Some("[-]") => TodoStatus::Pending,
+        _ => return (None, text),
+    };
+    (Some(status), text[3..].trim_start())
+}
+
+/// The source of a link on its own, if it's an image, e.g. `[[file:diagram.png]]`.
+fn image(text: &str) -> Option<String> {
+    let target = text.strip_prefix("[[")?.strip_suffix("]]")?;
+    if target.contains(['[', ']']) {
+        return None;
+    }
+    let source = target.strip_prefix("file:").unwrap_or(target);
+    let (_, extension) = source.rsplit_once('.')?;
+    IMAGES
+        .contains(&extension.to_lowercase().as_str())
+        .then(|| source.replace(' ', "%20"))
+}
+
+/// Removes the comma that protects a line of a block, e.g. `,* not a heading`.
+fn unescape_line(line: &str) -> String {
+    let content = line.trim_start();
+    match content.strip_prefix(',') {
+        Some(rest)
+            if rest.trim_start_matches(',').starts_with(['*'])
+                || rest.trim_start_matches(',').starts_with("#+") =>
+        {
+            format!("{}{rest}", &line[..line.len() - content.len()])
+        }
+        _ => line.to_string(),
+    }
+}
+
+/// Protects a line of a block with a comma, if it would be read as a heading or keyword.
+fn escape_line(line: &str) -> String {
+    let content = line.trim_start();
+    let protected = content.trim_start_matches(',');
+    if protected.starts_with('*') || protected.starts_with("#+") {
+        format!("{},{content}", &line[..line.len() - content.len()])
+    } else {
+        line.to_string()
+    }
+}
+
+/// A heading, for links to it.
+struct Heading {
+    title: String,
+    level: u16,
+    custom_id: Option<String>,
+}
+
+/// The parts of a heading line after the stars.
+struct Title<'a> {
+    status: Option<(&'a str, TodoStatus)>,
+    priority: Option<&'a str>,
+    title: &'a str,
+    tags: Vec<&'a str>,
+}
+
+/// A list item or quote paragraph, with the items nested below it.
+struct Item {
+    task: Option<TodoStatus>,
+    text: String,
+    children: Vec<Item>,
+}
+
+/// A block that can be written in Norg.
+enum Block {
+    Heading {
+        level: u16,
+        title: String,
+        extensions: Vec<DetachedModifierExtension>,
+        /// Carryover tags for the tags and properties of the heading.
+        attributes: Vec<(String, Vec<String>)>,
+    },
+    Paragraph(String),
+    Image(String),
+    List(NestableDetachedModifier, Vec<Item>),
+    Definitions(Vec<(String, String)>),
+    Code(String, String),
+    Math(String),
+    Comment(String),
+    /// A special block, e.g. `#+BEGIN_NOTE`, with its name and parameters.
+    Tag(String, Vec<String>, Vec<Block>),
+    Toc,
+    Rule,
+    /// Table cells with their names, e.g. `A1`.
+    Table(Vec<(String, String)>),
+    Footnote(String, String),
+}
+
+struct Converter<'a> {
+    lines: Vec<&'a str>,
+    /// The index of the next line.
+    line: usize,
+    /// The line of the element being converted, for losses.
+    element: usize,
+    keywords: Vec<(String, TodoStatus)>,
+    headings: Vec<Heading>,
+    meta: BTreeMap<String, NorgMeta>,
+    losses: Vec<Loss>,
+}
+
+impl<'a> Converter<'a> {
+    fn lose(&mut self, message: &str) {
+        self.losses.push(Loss {
+            line: self.element,
+            message: message.to_string(),
+        });
+    }
+
+    fn split_heading<'b>(&self, rest: &'b str) -> Title<'b> {
+        let mut rest = rest.trim();
+
+        let status = rest.split_whitespace().next().and_then(|word| {
+            let (keyword, status) = self.keywords.iter().find(|(keyword, _)| keyword == word)?;
+            Some((&rest[..keyword.len()], status.clone()))
+        });
+        if let Some((keyword, _)) = status {
+            rest = rest[keyword.len()..].trim_start();
+        }
+
+        let priority = rest
+            .strip_prefix("[#")
+            .and_then(|priority| Some(&priority[..priority.find(']')?]))
+            .filter(|priority| !priority.is_empty() && !priority.contains(char::is_whitespace));
+        if let Some(priority) = priority {
+            rest = rest[priority.len() + 3..].trim_start();
+        }
+
+        let (title, last) = rest.rsplit_once(char::is_whitespace).unwrap_or(("", rest));
+        let tags: Vec<_> = last
+            .strip_prefix(':')
+            .and_then(|tags| tags.strip_suffix(':'))
+            .map(|tags| tags.split(':').collect())
+            .unwrap_or_default();
+        let valid = !tags.is_empty()
+            && tags.iter().all(|tag| {
+                !tag.is_empty()
+                    && tag
+                        .chars()
+                        .all(|c| c.is_alphanumeric() || "_@#%".contains(c))
+            });
+
+        match valid {
+            true => Title {
+                status,
+                priority,
+                title: title.trim(),
+                tags,
+            },
+            false => Title {
+                status,
+                priority,
+                title: rest,
+                tags: Vec::new(),
+            },
+        }
+    }
+
+    /// Collects the headings of the document, so links to them can be resolved.
+    fn headings(&self) -> Vec<Heading> {
+        let mut headings: Vec<Heading> = Vec::new();
+        for line in &self.lines {
+            if let Some((level, rest)) = heading(line) {
+                headings.push(Heading {
+                    title: self.split_heading(rest).title.to_string(),
+                    level,
+                    custom_id: None,
+                });
+            } else if let Some((key, value)) = line.trim().split_once(": ") {
+                if let Some(heading) = headings.last_mut() {
+                    if key.eq_ignore_ascii_case(":custom_id") {
+                        heading.custom_id = Some(value.trim().to_string());
+                    }
+                }
+            }
+        }
+        headings
+    }
+
+    /// The index of the line that ends a block started at line `start`, before line `end`.
+    /// Blocks can't contain headings.
+    fn block_end(&self, start: usize, end: usize, name: &str) -> Option<usize> {
+        let close = format!("#+end_{name}");
+        (start + 1..end)
+            .take_while(|i| heading(self.lines[*i]).is_none())
+            .find(|i| self.lines[*i].trim().eq_ignore_ascii_case(&close))
+    }
+
+    fn drawer_end(&self, start: usize, end: usize) -> Option<usize> {
+        (start + 1..end)
+            .take_while(|i| heading(self.lines[*i]).is_none())
+            .find(|i| self.lines[*i].trim().eq_ignore_ascii_case(":end:"))
+    }
+
+    /// The index of the line that ends display math started at line `start`.
+    fn math_end(&self, start: usize, end: usize) -> Option<usize> {
+        let first = self.lines[start].trim().strip_prefix("\\[")?;
+        if first.contains("\\]") {
+            return Some(start);
+        }
+        (start + 1..end)
+            .take_while(|i| !self.lines[*i].trim().is_empty())
+            .find(|i| self.lines[*i].contains("\\]"))
+    }
+
+    /// Whether a line starts an element, which ends a paragraph before it.
+    fn starts_element(&self, i: usize, end: usize) -> bool {
+        let line = self.lines[i];
+        heading(line).is_some()
+            || block_start(line).is_some_and(|(name, _)| self.block_end(i, end, &name).is_some())
+            || keyword(line).is_some()
+            || drawer(line).is_some_and(|_| self.drawer_end(i, end).is_some())
+            || is_rule(line)
+            || is_fixed_width(line)
+            || is_comment(line)
+            || self.math_end(i, end).is_some()
+            || line.trim_start().starts_with('|')
+            || footnote(line).is_some()
+            || bullet(line).is_some()
+    }
+
+    /// Joins the lines of a paragraph.
+    fn join(&mut self, lines: &[&str]) -> String {
+        let mut text = Vec::new();
+        for line in lines {
+            let line = line.trim().trim_start_matches(ZERO_WIDTH_SPACE);
+            match line.strip_suffix("\\\\") {
+                Some(line) => {
+                    self.lose("a line break became a space");
+                    text.push(line.trim_end());
+                }
+                None => text.push(line),
+            }
+        }
+        text.join(" ")
+    }
+
+    /// Collects the lines up to the end of a paragraph.
+    fn paragraph_lines(&mut self, end: usize) -> Vec<&'a str> {
+        let start = self.line;
+        let mut lines = Vec::new();
+        while self.line < end {
+            let line = self.lines[self.line];
+            if line.trim().is_empty() || (self.line > start && self.starts_element(self.line, end))
+            {
+                break;
+            }
+            lines.push(line);
+            self.line += 1;
+        }
+        lines
+    }
+
+    /// Collects the lines of a block or drawer, dropping `@end` lines, which would end the
+    /// Norg tag.
+    fn verbatim(&mut self, lines: &[&str], object: &str) -> String {
+        let mut content = String::new();
+        for line in lines {
+            if line.trim() == "@end" {
+                self.lose(&format!("an `@end` line in {object} was dropped"));
+                continue;
+            }
+            content.push_str(&unescape_line(line));
+            content.push('\n');
+        }
+        // Trailing blank lines aren't kept by the exporter either.
+        while content.ends_with("\n\n") || content == "\n" {
+            content.pop();
+        }
+        content
+    }
+
+    fn insert_meta(&mut self, key: &str, value: &str) {
+        let key = match key {
+            "AUTHOR" => String::from("authors"),
+            "DATE" => String::from("created"),
+            "FILETAGS" => String::from("categories"),
+            key => key.to_lowercase(),
+        };
+        let value = if key == "categories" {
+            NorgMeta::Array(
+                value
+                    .split(':')
+                    .map(str::trim)
+                    .filter(|tag| !tag.is_empty())
+                    .map(|tag| NorgMeta::Str(tag.to_string()))
+                    .collect(),
+            )
+        } else if value.is_empty() {
+            NorgMeta::Nil
+        } else {
+            NorgMeta::Str(value.to_string())
+        };
+        for value in retyped_strings(&value) {
+            self.lose(&format!(
+                "the metadata string `{value}` is read back as another value"
+            ));
+        }
+
+        // Repeated keywords, e.g. several `#+AUTHOR:` lines, become an array.
+        match self.meta.entry(key) {
+            Entry::Vacant(entry) => {
+                entry.insert(value);
+            }
+            Entry::Occupied(mut entry) => match (entry.get_mut(), value) {
+                (NorgMeta::Array(values), NorgMeta::Array(more)) => values.extend(more),
+                (NorgMeta::Array(values), value) => values.push(value),
+                (old, value) => {
+                    *old = NorgMeta::Array(vec![std::mem::replace(old, NorgMeta::Nil), value])
+                }
+            },
+        }
+    }
+
+    /// Converts the lines up to `end` into blocks.
+    fn blocks(&mut self, end: usize) -> Vec<Block> {
+        let mut blocks = Vec::new();
+        while self.line < end {
+            self.push_block(&mut blocks, end);
+        }
+        blocks
+    }
+
+    fn push_block(&mut self, blocks: &mut Vec<Block>, end: usize) {
+        let line = self.lines[self.line];
+        self.element = self.line;
+
+        if line.trim().is_empty() {
+            self.line += 1;
+        } else if let Some((level, rest)) = heading(line) {
+            self.line += 1;
+            self.push_heading(blocks, level, rest);
+        } else if let Some((name, close)) = block_start(line)
+            .and_then(|(name, _)| Some((name.clone(), self.block_end(self.line, end, &name)?)))
+        {
+            let parameters = block_start(line).map_or("", |(_, parameters)| parameters);
+            self.push_special_block(blocks, &name, parameters, close);
+        } else if let Some((key, value)) = keyword(line) {
+            self.line += 1;
+            self.push_keyword(blocks, &key, value);
+        } else if let Some((name, close)) =
+            drawer(line).and_then(|name| Some((name, self.drawer_end(self.line, end)?)))
+        {
+            let start = self.line + 1;
+            self.line = close + 1;
+            // Only a property drawer before any other element belongs to the document.
+            if name.eq_ignore_ascii_case("properties")
+                && blocks.is_empty()
+                && end == self.lines.len()
+            {
+                for i in start..close {
+                    if let Some((key, value)) = self.lines[i]
+                        .trim()
+                        .strip_prefix(':')
+                        .and_then(|property| property.split_once(':'))
+                    {
+                        self.insert_meta(&key.to_uppercase(), value.trim());
+                    }
+                }
+            } else {
+                self.lose(&format!("the {name} drawer was dropped"));
+            }
+        } else if is_rule(line) {
+            self.line += 1;
+            blocks.push(Block::Rule);
+        } else if is_fixed_width(line) {
+            let mut lines = Vec::new();
+            while self.line < end && is_fixed_width(self.lines[self.line]) {
+                let line = self.lines[self.line].trim();
+                lines.push(line.strip_prefix(": ").unwrap_or_default());
+                self.line += 1;
+            }
+            let code = self.verbatim(&lines, "a fixed width area");
+            blocks.push(Block::Code(String::new(), code));
+        } else if is_comment(line) {
+            let mut lines = Vec::new();
+            while self.line < end && is_comment(self.lines[self.line]) {
+                let line = self.lines[self.line].trim();
+                lines.push(line.strip_prefix("# ").unwrap_or_default());
+                self.line += 1;
+            }
+            let comment = self.verbatim(&lines, "a comment");
+            blocks.push(Block::Comment(comment));
+        } else if let Some(close) = self.math_end(self.line, end) {
+            let math = self.lines[self.line..=close].join("\n");
+            self.line = close + 1;
+            let math = math.trim().trim_start_matches("\\[");
+            let math = math.split("\\]").next().unwrap_or_default();
+            blocks.push(Block::Math(math.trim().to_string()));
+        } else if line.trim_start().starts_with('|') {
+            self.push_table(blocks, end);
+        } else if let Some((label, text)) = footnote(line) {
+            self.line += 1;
+            let mut lines = vec![text];
+            lines.extend(self.paragraph_lines(end));
+            let text = self.join(&lines);
+            let text = self.inlines(&text);
+            if text.is_empty() {
+                self.lose("a footnote without text was dropped");
+            } else {
+                blocks.push(Block::Footnote(escape(label), text));
+            }
+        } else if bullet(line).is_some() {
+            let (list, moved) = self.list(end);
+            blocks.extend(list);
+            blocks.extend(moved);
+        } else {
+            let lines = self.paragraph_lines(end);
+            let text = self.join(&lines);
+            match image(&text) {
+                Some(source) => blocks.push(Block::Image(source)),
+                None => blocks.push(Block::Paragraph(self.inlines(&text))),
+            }
+        }
+    }
+
+    fn push_heading(&mut self, blocks: &mut Vec<Block>, level: u16, rest: &str) {
+        let Title {
+            status,
+            priority,
+            title,
+            tags,
+        } = self.split_heading(rest);
+
+        let mut extensions = Vec::new();
+        if let Some((keyword, status)) = status {
+            if keyword != status_keyword(&status) {
+                self.lose(&format!(
+                    "the TODO keyword {keyword} became {}",
+                    status_keyword(&status)
+                ));
+            }
+            extensions.push(DetachedModifierExtension::Todo(status));
+        }
+        if let Some(priority) = priority {
+            extensions.push(DetachedModifierExtension::Priority(priority.to_string()));
+        }
+
+        let mut attributes = Vec::new();
+        if !tags.is_empty() {
+            attributes.push((
+                String::from("tags"),
+                tags.iter().map(|tag| tag.to_string()).collect(),
+            ));
+        }
+
+        let planning = self.lines.get(self.line).map_or("", |line| line.trim());
+        if ["SCHEDULED:", "DEADLINE:", "CLOSED:"]
+            .iter()
+            .any(|keyword| planning.starts_with(keyword))
+        {
+            self.element = self.line;
+            self.line += 1;
+            self.push_planning(planning, &mut extensions);
+        }
+
+        let drawer = self.lines.get(self.line).map_or("", |line| line.trim());
+        if drawer.eq_ignore_ascii_case(":properties:") {
+            if let Some(close) = self.drawer_end(self.line, self.lines.len()) {
+                self.element = self.line;
+                for i in self.line + 1..close {
+                    let Some((key, value)) = self.lines[i]
+                        .trim()
+                        .strip_prefix(':')
+                        .and_then(|property| property.split_once(':'))
+                    else {
+                        continue;
+                    };
+                    if key.is_empty()
+                        || !key
+                            .chars()
+                            .all(|c| c.is_alphanumeric() || c == '_' || c == '-')
+                    {
+                        self.element = i;
+                        self.lose(&format!("the property {key} was dropped"));
+                        continue;
+                    }
+                    attributes.push((
+                        key.to_lowercase(),
+                        value.split_whitespace().map(String::from).collect(),
+                    ));
+                }
+                self.line = close + 1;
+            }
+        }
+
+        if title.is_empty() {
+            self.lose("a heading without a title was dropped");
+            return;
+        }
+        let title = self.inlines(title);
+        blocks.push(Block::Heading {
+            level,
+            title,
+            extensions,
+            attributes,
+        });
+    }
+
+    /// Converts the SCHEDULED and DEADLINE dates of a heading.
+    fn push_planning(&mut self, line: &str, extensions: &mut Vec<DetachedModifierExtension>) {
+        for keyword in ["SCHEDULED", "DEADLINE"] {
+            let Some(position) = line.find(&format!("{keyword}:")) else {
+                continue;
+            };
+            let date = line[position + keyword.len() + 1..]
+                .trim_start()
+                .strip_prefix('<')
+                .and_then(|date| date.split_once('>'))
+                .map(|(date, _)| date.trim())
+                .filter(|date| !date.is_empty() && !date.contains(['|', '(', ')']));
+            match (keyword, date) {
+                ("SCHEDULED", Some(date)) => {
+                    extensions.push(DetachedModifierExtension::StartDate(date.to_string()))
+                }
+                (_, Some(date)) => {
+                    extensions.push(DetachedModifierExtension::DueDate(date.to_string()))
+                }
+                (_, None) => self.lose(&format!("the {keyword} date of a heading was dropped")),
+            }
+        }
+        if line.contains("CLOSED:") {
+            self.lose("the CLOSED date of a heading was dropped");
+        }
+    }
+
+    fn push_keyword(&mut self, blocks: &mut Vec<Block>, key: &str, value: &str) {
+        match key {
+            // Read before the document is converted.
+            "TODO" | "SEQ_TODO" | "TYP_TODO" => (),
+            "TOC" => {
+                if !value.starts_with("headlines") {
+                    self.lose("a table of contents of something other than headings became one of headings");
+                }
+                blocks.push(Block::Toc);
+            }
+            "CALL" | "INCLUDE" => self.lose(&format!("the #+{key} keyword was dropped")),
+            key if AFFILIATED.contains(&key) || key.starts_with("ATTR_") => {
+                self.lose(&format!("the #+{key} keyword was dropped"))
+            }
+            key => self.insert_meta(key, value),
+        }
+    }
+
+    fn push_special_block(
+        &mut self,
+        blocks: &mut Vec<Block>,
+        name: &str,
+        parameters: &str,
+        close: usize,
+    ) {
+        let start = self.line + 1;
+        let lines = self.lines[start..close].to_vec();
+        self.line = close + 1;
+
+        match name {
+            "src" | "example" | "export" => {
+                let mut parameters = parameters.split_whitespace();
+                let language = match name {
+                    "example" => "",
+                    _ => parameters.next().unwrap_or_default(),
+                };
+                if name == "export" {
+                    self.lose("an export block became a code block");
+                }
+                if parameters.next().is_some() {
+                    self.lose(&format!(
+                        "the switches and header arguments of {} block were dropped",
+                        if name == "src" {
+                            "a source"
+                        } else {
+                            "an example"
+                        }
+                    ));
+                }
+                let language = if language
+                    .chars()
+                    .all(|c| c.is_alphanumeric() || "+-_.#".contains(c))
+                {
+                    language
+                } else {
+                    self.lose("the language of a code block was dropped");
+                    ""
+                };
+                let code = self.verbatim(&lines, "a code block");
+                blocks.push(Block::Code(language.to_string(), code));
+            }
+            "comment" => {
+                let comment = self.verbatim(&lines, "a comment");
+                blocks.push(Block::Comment(comment));
+            }
+            "quote" => {
+                self.line = start;
+                let content = self.blocks(close);
+                self.line = close + 1;
+                let (items, moved) = self.quote(content);
+                blocks.push(Block::List(NestableDetachedModifier::Quote, items));
+                blocks.extend(moved);
+            }
+            _ => {
+                self.line = start;
+                let content = self.blocks(close);
+                self.line = close + 1;
+                let parameters = parameters.split_whitespace().map(String::from).collect();
+                blocks.push(Block::Tag(name.to_string(), parameters, content));
+            }
+        }
+    }
+
+    fn push_table(&mut self, blocks: &mut Vec<Block>, end: usize) {
+        let mut cells = Vec::new();
+        let mut row = 0;
+
+        while self.line < end && self.lines[self.line].trim_start().starts_with('|') {
+            let line = self.lines[self.line].trim();
+            self.line += 1;
+            if line.starts_with("|-") {
+                continue;
+            }
+            let line = &line[1..];
+            let line = line.strip_suffix('|').unwrap_or(line);
+            for (column, cell) in line.split('|').enumerate() {
+                let text = self.inlines(cell.trim());
+                if !text.is_empty() {
+                    cells.push((cell_name(row, column), text));
+                }
+            }
+            row += 1;
+        }
+
+        blocks.push(Block::Table(cells));
+    }
+
+    /// Converts the list starting at the current line. Blocks inside its items are returned
+    /// to be moved after it.
+    fn list(&mut self, end: usize) -> (Option<Block>, Vec<Block>) {
+        let Some(first) = bullet(self.lines[self.line]) else {
+            return (None, Vec::new());
+        };
+        let modifier_type = match first.ordered {
+            true => NestableDetachedModifier::OrderedList,
+            false => NestableDetachedModifier::UnorderedList,
+        };
+        let descriptive =
+            !first.ordered && (first.text.contains(" :: ") || first.text.ends_with(" ::"));
+
+        let mut items = Vec::new();
+        let mut definitions = Vec::new();
+        let mut moved = Vec::new();
+
+        while let Some(item) = self
+            .lines
+            .get(self.line)
+            .filter(|_| self.line < end)
+            .and_then(|line| bullet(line))
+            .filter(|item| item.indent == first.indent && item.ordered == first.ordered)
+        {
+            self.element = self.line;
+            self.line += 1;
+
+            let mut text = item.text;
+            if let Some(counter) = text
+                .strip_prefix("[@")
+                .and_then(|rest| rest.split_once(']'))
+            {
+                self.lose("the counter of a list item was dropped");
+                text = counter.1.trim_start();
+            }
+            let (task, text) = checkbox(text);
+
+            let mut lines = vec![text];
+            let mut children = Vec::new();
+            let mut blank = 0;
+            let mut joined = false;
+            let mut item_moved = false;
+
+            // The content of an item is indented more than its bullet.
+            while self.line < end {
+                let line = self.lines[self.line];
+                if line.trim().is_empty() {
+                    blank += 1;
+                    // Two blank lines end a list.
+                    if blank == 2 {
+                        break;
+                    }
+                    self.line += 1;
+                    continue;
+                }
+                if line.len() - line.trim_start().len() <= first.indent {
+                    break;
+                }
+
+                if bullet(line).is_some() {
+                    match self.list(end) {
+                        (Some(Block::List(nested_type, nested)), nested_moved) => {
+                            if nested_type != modifier_type {
+                                self.lose("a nested list took the type of the list it is in");
+                            }
+                            children.extend(nested);
+                            moved.extend(nested_moved);
+                        }
+                        (block, nested_moved) => {
+                            moved.extend(block);
+                            moved.extend(nested_moved);
+                            item_moved = true;
+                        }
+                    }
+                } else if self.starts_element(self.line, end) {
+                    let element = self.element;
+                    self.push_block(&mut moved, end);
+                    self.element = element;
+                    item_moved = true;
+                } else {
+                    joined |= blank > 0 || !children.is_empty();
+                    lines.push(line);
+                    self.line += 1;
+                }
+                blank = 0;
+            }
+
+            if joined {
+                self.lose("the paragraphs of a list item were joined");
+            }
+            if item_moved {
+                self.lose("blocks inside a list item were moved after the list");
+            }
+            let text = self.join(&lines);
+
+            if descriptive {
+                if task.is_some() {
+                    self.lose("the checkbox of a description was dropped");
+                }
+                if !children.is_empty() {
+                    self.lose("a list nested in a description was moved after the list");
+                    moved.push(Block::List(modifier_type.clone(), children));
+                }
+                let (term, description) = text
+                    .split_once(" :: ")
+                    .or_else(|| text.strip_suffix(" ::").map(|term| (term, "")))
+                    .unwrap_or(("", &text));
+                let term = self.inlines(term.trim());
+                let description = self.inlines(description.trim());
+                if term.is_empty() || description.is_empty() {
+                    self.lose("a description without a term or text was dropped");
+                } else {
+                    definitions.push((term, description));
+                }
+            } else {
+                let text = self.inlines(&text);
+                if text.is_empty() {
+                    self.lose("a list item without text was dropped");
+                    items.extend(children);
+                } else {
+                    items.push(Item {
+                        task,
+                        text,
+                        children,
+                    });
+                }
+            }
+
+            if blank == 2 {
+                break;
+            }
+        }
+
+        let block = match descriptive {
+            true => Block::Definitions(definitions),
+            false => Block::List(modifier_type, items),
+        };
+        (Some(block), moved)
+    }
+
+    /// Converts the content of a quote block to quote items, a paragraph each.
+    fn quote(&mut self, blocks: Vec<Block>) -> (Vec<Item>, Vec<Block>) {
+        let mut items: Vec<Item> = Vec::new();
+        let mut moved = Vec::new();
+
+        for block in blocks {
+            match block {
+                Block::Paragraph(text) => items.push(Item {
+                    task: None,
+                    text,
+                    children: Vec::new(),
+                }),
+                Block::List(NestableDetachedModifier::Quote, nested) => match items.last_mut() {
+                    Some(item) => item.children.extend(nested),
+                    None => items.extend(nested),
+                },
+                block => moved.push(block),
+            }
+        }
+
+        if !moved.is_empty() {
+            self.lose("blocks inside a quote were moved after it");
+        }
+        (items, moved)
+    }
+
+    /// Converts inline markup.
+    fn inlines(&mut self, text: &str) -> String {
+        let mut out = String::new();
+        let mut literal = String::new();
+        let mut i = 0;
+
+        while let Some(c) = text[i..].chars().next() {
+            match self.inline(text, i) {
+                Some((markup, next)) => {
+                    out.push_str(&escape(&std::mem::take(&mut literal)));
+                    out.push_str(&markup);
+                    i = next;
+                }
+                None => {
+                    literal.push(c);
+                    i += c.len_utf8();
+                }
+            }
+        }
+
+        out.push_str(&escape(&literal));
+        out
+    }
+
+    /// Converts the object starting at byte `i` of the text, e.g. a link. Returns its markup
+    /// and the byte after it.
+    fn inline(&mut self, text: &str, i: usize) -> Option<(String, usize)> {
+        let rest = &text[i..];
+
+        if let Some(inner) = rest.strip_prefix("[[") {
+            let close = inner.find("]]")?;
+            let (target, description) = match inner[..close].split_once("][") {
+                Some((target, description)) => (target, Some(description)),
+                None => (&inner[..close], None),
+            };
+            return Some((self.link(target, description), i + close + 4));
+        }
+
+        if let Some(inner) = rest.strip_prefix("[fn:") {
+            let label = &inner[..inner.find(']')?];
+            if label.contains(':') {
+                self.lose("an inline footnote definition was kept as text");
+                return None;
+            }
+            return (!label.is_empty())
+                .then(|| (format!("{{^ {}}}", escape(label)), i + label.len() + 5));
+        }
+
+        for (open, close) in [("<<<", ">>>"), ("<<", ">>")] {
+            if let Some(inner) = rest.strip_prefix(open) {
+                let target = &inner[..inner.find(close)?];
+                if target.is_empty() || target.contains(['<', '>']) {
+                    return None;
+                }
+                if open == "<<<" {
+                    self.lose("a radio target became a link target");
+                }
+                // All punctuation is escaped, since the paragraph parser can't recover from an
+                // unclosed modifier inside an inline link target.
+                let target: String = target
+                    .chars()
+                    .flat_map(|c| [c.is_ascii_punctuation().then_some('\\'), Some(c)])
+                    .flatten()
+                    .collect();
+                let end = i + open.len() + inner.find(close)? + close.len();
+                return Some((format!("<{target}>"), end));
+            }
+        }
+
+        if let Some(inner) = rest.strip_prefix('<').filter(|inner| is_date(inner)) {
+            let timestamp = &inner[..inner.find('>')?];
+            return (!timestamp.contains('}'))
+                .then(|| (format!("{{@ {timestamp}}}"), i + timestamp.len() + 2));
+        }
+
+        match rest.chars().next()? {
+            marker @ ('*' | '/' | '_' | '+' | '=' | '~') => self.emphasis(text, i, marker),
+            _ => None,
+        }
+    }
+
+    /// Converts emphasis or verbatim text, e.g. `*bold*` or `=code=`.
+    fn emphasis(&mut self, text: &str, i: usize, marker: char) -> Option<(String, usize)> {
+        if text[..i]
+            .chars()
+            .next_back()
+            .is_some_and(|c| !c.is_whitespace() && !PRE.contains(c))
+        {
+            return None;
+        }
+        let start = i + 1;
+        if text[start..].chars().next().is_none_or(char::is_whitespace) {
+            return None;
+        }
+
+        let close = text[start..]
+            .char_indices()
+            .skip(1)
+            .find_map(|(offset, c)| {
+                let j = start + offset;
+                (c == marker
+                    && !text[..j].ends_with(char::is_whitespace)
+                    && text[j + 1..]
+                        .chars()
+                        .next()
+                        .is_none_or(|c| c.is_whitespace() || POST.contains(c)))
+                .then_some(j)
+            })?;
+        let content = &text[start..close];
+
+        // Norg only closes attached modifiers before whitespace and punctuation it knows.
+        if text[close + 1..]
+            .chars()
+            .next()
+            .is_some_and(|c| !c.is_whitespace() && !SPECIAL_CHARS.contains(c))
+        {
+            self.lose("emphasis before a character Norg doesn't allow after it was kept as text");
+            return None;
+        }
+
+        let markup = match marker {
+            // A backslash at the end would escape the closing backtick.
+            '=' | '~' if content.contains('`') || content.ends_with('\\') => {
+                self.lose(
+                    "inline code containing a backtick or ending in a backslash was kept as text",
+                );
+                return None;
+            }
+            '=' | '~' => {
+                // Norg has a single kind of inline verbatim, which is exported as `~code~`.
+                if marker == '=' {
+                    self.lose(&format!(
+                        "the verbatim text ={content}= became inline code, which is exported as ~{content}~"
+                    ));
+                }
+                format!("`{content}`")
+            }
+            marker => {
+                let modifier = if marker == '+' { '-' } else { marker };
+                format!("{modifier}{}{modifier}", self.inlines(content))
+            }
+        };
+        Some((markup, close + 1))
+    }
+
+    fn link(&mut self, target: &str, description: Option<&str>) -> String {
+        let description = description
+            .map(|description| self.inlines(description))
+            .filter(|description| !description.is_empty());
+
+        match (self.link_target(target), description) {
+            (Some(target), Some(description)) => format!("{{{target}}}[{description}]"),
+            (Some(target), None) => format!("{{{target}}}"),
+            (None, description) => description.unwrap_or_else(|| escape(target)),
+        }
+    }
+
+    /// The target of a Norg link for an Org link, e.g. `:notes:* Ideas` for
+    /// `file:notes.org::*Ideas`.
+    fn link_target(&mut self, target: &str) -> Option<String> {
+        if target.is_empty() || target.contains(['{', '}']) {
+            self.lose("a link to a target containing braces was kept as text");
+            return None;
+        }
+        if target.contains("://") || target.starts_with("mailto:") {
+            return Some(target.to_string());
+        }
+
+        let file = target
+            .strip_prefix("file:")
+            .or_else(|| target.starts_with(['/', '.', '~']).then_some(target));
+        if let Some(file) = file {
+            let (path, search) = match file.split_once("::") {
+                Some((path, search)) => (path, Some(search)),
+                None => (file, None),
+            };
+            if let Some(name) = path.strip_suffix(".org") {
+                let search = search.map_or(String::new(), |search| self.search(search, false));
+                return Some(format!(":{name}:{search}"));
+            }
+            if search.is_some() {
+                self.lose("the search option of a link to a file was dropped");
+            }
+            return Some(format!("/ {path}"));
+        }
+
+        if let Some((scheme, _)) = target.split_once(':') {
+            if !scheme.is_empty()
+                && scheme
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-')
+            {
+                self.lose(&format!("a link of type {scheme} was kept as text"));
+                return None;
+            }
+        }
+        Some(self.search(target, true))
+    }
+
+    /// The Norg link target for a search option, e.g. `** Ideas` for `*Ideas`. Only headings
+    /// of this document have a known level.
+    fn search(&self, search: &str, local: bool) -> String {
+        let headings = self.headings.iter().filter(|_| local);
+        let heading = match (search.strip_prefix('*'), search.strip_prefix('#')) {
+            (Some(title), _) => {
+                let title = title.trim();
+                return match headings.into_iter().find(|heading| heading.title == title) {
+                    Some(heading) => {
+                        format!("{} {}", "*".repeat(heading.level.into()), escape(title))
+                    }
+                    None => format!("* {}", escape(title)),
+                };
+            }
+            (_, Some(id)) => headings
+                .into_iter()
+                .find(|heading| heading.custom_id.as_deref() == Some(id)),
+            _ => headings.into_iter().find(|heading| heading.title == search),
+        };
+
+        match heading {
+            Some(heading) => format!(
+                "{} {}",
+                "*".repeat(heading.level.into()),
+                escape(&heading.title)
+            ),
+            None => format!("# {}", escape(search.trim_start_matches('#'))),
+        }
+    }
+}
+
+fn push_items(list: List, items: &[Item]) -> List {
+    items.iter().fold(list, |list, item| {
+        let list = list.item(&item.text);
+        let list = match &item.task {
+            Some(status) => list.todo(status.clone()),
+            None => list,
+        };
+        if item.children.is_empty() {
+            list
+        } else {
+            list.nested(|list| push_items(list, &item.children))
+        }
+    })
+}
+
+fn push_block(doc: Doc, block: &Block) -> Doc {
+    match block {
+        Block::Heading {
+            level,
+            title,
+            extensions,
+            attributes,
+        } => {
+            let doc = attributes.iter().fold(doc, |doc, (name, parameters)| {
+                let parameters: Vec<_> = parameters.iter().map(String::as_str).collect();
+                doc.carryover_tag(CarryoverTag::Attribute, name, &parameters)
+            });
+            extensions
+                .iter()
+                .cloned()
+                .fold(doc.heading(*level, title), Doc::extension)
+        }
+        Block::Paragraph(text) => doc.paragraph(text),
+        Block::Image(source) => doc.infirm_tag("image", &[source]),
+        Block::List(NestableDetachedModifier::UnorderedList, items) => {
+            doc.list(|list| push_items(list, items))
+        }
+        Block::List(NestableDetachedModifier::OrderedList, items) => {
+            doc.ordered_list(|list| push_items(list, items))
+        }
+        Block::List(NestableDetachedModifier::Quote, items) => {
+            doc.quote(|list| push_items(list, items))
+        }
+        Block::Definitions(definitions) => definitions
+            .iter()
+            .fold(doc, |doc, (term, text)| doc.definition(term, text)),
+        Block::Code(language, code) => doc.code(language, code),
+        Block::Math(math) => doc.verbatim("math", &[], math),
+        Block::Comment(comment) => doc.verbatim("comment", &[], comment),
+        Block::Tag(name, parameters, content) => {
+            let parameters: Vec<_> = parameters.iter().map(String::as_str).collect();
+            doc.ranged_tag(name, &parameters, |doc| {
+                content.iter().fold(doc, push_block)
+            })
+        }
+        Block::Toc => doc.infirm_tag("toc", &[]),
+        Block::Rule => doc.horizontal_rule(),
+        Block::Table(cells) => cells
+            .iter()
+            .fold(doc, |doc, (cell, text)| doc.table_cell(cell, text)),
+        Block::Footnote(label, text) => doc.footnote(label, text),
+    }
+}
+
+/// Converts an Org document into a Norg document.
+///
+/// TODO keywords become todo statuses, using the keywords of `#+TODO:` lines. Keywords after
+/// the `|` are done, or canceled if they're called `CANCELLED`, and keywords like `NEXT` and
+/// `WAITING` are pending and paused. Emphasis and links keep their meaning, e.g.
+/// `[[file:notes.org::*Ideas]]` becomes `{:notes:* Ideas}` and `+deleted+` becomes
+/// `-deleted-`. Table cells are named by their position, like `: A1`.
+///
+/// # Arguments
+///
+/// * `source` - The Org document.
+///
+/// # Returns
+///
+/// * `Import` with the document, its metadata and everything that couldn't be converted
+///   exactly.
+pub fn from_org(source: &str) -> Import {
+    let lines: Vec<_> = source.lines().collect();
+    let mut converter = Converter {
+        keywords: todo_keywords(&lines),
+        lines,
+        line: 0,
+        element: 0,
+        headings: Vec::new(),
+        meta: BTreeMap::new(),
+        losses: Vec::new(),
+    };
+    converter.headings = converter.headings();
+    let blocks = converter.blocks(converter.lines.len());
+
+    let meta = (!converter.meta.is_empty()).then_some(NorgMeta::Object(converter.meta));
+    let mut document = Doc::new();
+    if let Some(meta) = &meta {
+        document = document.verbatim("document.meta", &[], &print_metadata(meta));
+    }
+    let document = blocks.iter().fold(document, push_block);
+
+    Import {
+        document,
+        meta,
+        losses: converter.losses,
+    }
+}
+
+/// The result of converting a document into Org.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Export {
+    pub org: String,
+    /// What couldn't be converted exactly. There are no line numbers, as the AST has none.
+    pub losses: Vec<String>,
+}
+
+/// The label of a footnote, which can't contain whitespace in Org.
+fn label(title: &[ParagraphSegment]) -> String {
+    plain(title)
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+/// Protects a paragraph that would be read as another element, e.g. `- text`.
+fn protect(text: String) -> String {
+    let misread = heading(&text).is_some()
+        || bullet(&text).is_some()
+        || block_start(&text).is_some()
+        || keyword(&text).is_some()
+        || drawer(&text).is_some()
+        || footnote(&text).is_some()
+        || is_rule(&text)
+        || is_fixed_width(&text)
+        || is_comment(&text)
+        || text.starts_with(['|', '#'])
+        || text.starts_with("\\[");
+    match misread {
+        true => format!("{ZERO_WIDTH_SPACE}{text}"),
+        false => text,
+    }
+}
+
+fn push_token(out: &mut String, token: &ParagraphSegmentToken) {
+    match token {
+        ParagraphSegmentToken::Text(text) => out.push_str(text),
+        ParagraphSegmentToken::Whitespace => out.push(' '),
+        ParagraphSegmentToken::Special(c) | ParagraphSegmentToken::Escape(c) => out.push(*c),
+    }
+}
+
+/// Whether a node becomes a list item, e.g. `- text` or `- term :: text`.
+fn is_list(node: &NorgAST) -> bool {
+    matches!(
+        node,
+        NorgAST::NestableDetachedModifier {
+            modifier_type: NestableDetachedModifier::UnorderedList
+                | NestableDetachedModifier::OrderedList,
+            ..
+        } | NorgAST::RangeableDetachedModifier {
+            modifier_type: RangeableDetachedModifier::Definition,
+            ..
+        }
+    )
+}
+
+fn block(name: &str, parameters: &[String], content: &str) -> String {
+    let mut start = format!("#+BEGIN_{name}");
+    for parameter in parameters {
+        start.push(' ');
+        start.push_str(parameter);
+    }
+    match content.is_empty() {
+        true => format!("{start}\n#+END_{name}"),
+        false => format!("{start}\n{content}\n#+END_{name}"),
+    }
+}
+
+struct Exporter {
+    references: References,
+    /// Attribute carryover tags waiting for the heading they apply to.
+    attributes: Vec<(String, Vec<String>)>,
+    /// Whether a status without a default Org keyword is used, which needs a `#+TODO:` line.
+    keywords: bool,
+    losses: Vec<String>,
+}
+
+impl Exporter {
+    fn lose(&mut self, message: &str) {
+        self.losses.push(message.to_string());
+    }
+
+    fn inlines(&mut self, segments: &[ParagraphSegment]) -> String {
+        let mut out = String::new();
+        for segment in segments {
+            self.push_inline(&mut out, segment);
+        }
+        out
+    }
+
+    fn push_inline(&mut self, out: &mut String, segment: &ParagraphSegment) {
+        match segment {
+            ParagraphSegment::Token(token) => push_token(out, token),
+            ParagraphSegment::AttachedModifier {
+                modifier_type,
+                content,
+            } => {
+                let marker = match modifier_type {
+                    '*' | '/' | '_' => *modifier_type,
+                    '-' => '+',
+                    // Null modifiers are comments.
+                    '%' => return,
+                    _ => return out.push_str(&self.inlines(content)),
+                };
+                let content = self.inlines(content);
+                out.push_str(&format!("{marker}{content}{marker}"));
+            }
+            ParagraphSegment::InlineVerbatim(tokens) => {
+                let code: String = tokens.iter().map(ToString::to_string).collect();
+                match ['~', '=']
+                    .into_iter()
+                    .find(|marker| !code.contains(*marker))
+                {
+                    Some(marker) => out.push_str(&format!("{marker}{code}{marker}")),
+                    None => {
+                        self.lose("inline code containing both `~` and `=` was kept as text");
+                        out.push_str(&code);
+                    }
+                }
+            }
+            ParagraphSegment::InlineMath(tokens) => {
+                let math: String = tokens.iter().map(ToString::to_string).collect();
+                out.push_str(&format!("\\({math}\\)"));
+            }
+            ParagraphSegment::Link {
+                filepath,
+                targets,
+                description,
+            } => {
+                let link = self.link(filepath, targets, description.as_deref());
+                out.push_str(&link);
+            }
+            ParagraphSegment::AnchorDefinition { content, target } => match target.as_ref() {
+                ParagraphSegment::Link {
+                    filepath, targets, ..
+                } => {
+                    let link = self.link(filepath, targets, Some(content));
+                    out.push_str(&link);
+                }
+                _ => out.push_str(&self.inlines(content)),
+            },
+            ParagraphSegment::Anchor {
+                content,
+                description,
+            } => {
+                let text = description.as_deref().unwrap_or(content);
+                match self.references.anchors.get(&plain(content)).cloned() {
+                    Some(ParagraphSegment::Link {
+                        filepath, targets, ..
+                    }) => {
+                        let link = self.link(&filepath, &targets, Some(text));
+                        out.push_str(&link);
+                    }
+                    _ => out.push_str(&self.inlines(text)),
+                }
+            }
+            ParagraphSegment::InlineLinkTarget(content) => {
+                let content = self.inlines(content);
+                out.push_str(&format!("<<{content}>>"));
+            }
+            // Intermediate results of the paragraph parser, which are left in the AST when an
+            // attached modifier isn't closed. They are rendered as the text they came from.
+            ParagraphSegment::AttachedModifierOpener((left, modifiers, right))
+            | ParagraphSegment::AttachedModifierOpenerFail((left, modifiers, right)) => {
+                if let Some(left) = left {
+                    push_token(out, left);
+                }
+                out.extend(modifiers);
+                push_token(out, right);
+            }
+            ParagraphSegment::AttachedModifierCloserCandidate((left, modifiers, right)) => {
+                self.push_inline(out, left);
+                out.extend(modifiers);
+                if let Some(right) = right {
+                    self.push_inline(out, right);
+                }
+            }
+            ParagraphSegment::AttachedModifierCloser(c) => out.push(*c),
+            ParagraphSegment::AttachedModifierCandidate {
+                modifier_type,
+                content,
+                closer,
+            } => {
+                out.push(*modifier_type);
+                out.push_str(&self.inlines(content));
+                if let Some(closer) = closer {
+                    self.push_inline(out, closer);
+                }
+            }
+        }
+    }
+
+    fn link(
+        &mut self,
+        filepath: &Option<String>,
+        targets: &[LinkTarget],
+        description: Option<&[ParagraphSegment]>,
+    ) -> String {
+        if targets.len() > 1 {
+            self.lose("a link with more than one target kept only the first one");
+        }
+        let description = description.map(|description| self.inlines(description));
+        let link = |target: &str, description: Option<String>| match description {
+            Some(description) => format!("[[{target}][{description}]]"),
+            None => format!("[[{target}]]"),
+        };
+
+        let search = match targets.first() {
+            Some(LinkTarget::Footnote(title)) if filepath.is_none() => {
+                if description.is_some() {
+                    self.lose("the description of a link to a footnote was dropped");
+                }
+                return format!("[fn:{}]", label(title));
+            }
+            Some(LinkTarget::Timestamp(timestamp)) => {
+                if description.is_some() {
+                    self.lose("the description of a timestamp was dropped");
+                }
+                return format!("<{timestamp}>");
+            }
+            Some(LinkTarget::Url(url)) => match url.strip_prefix("# ") {
+                Some(text) => Some(text.to_string()),
+                None => return link(url, description),
+            },
+            Some(LinkTarget::Path(path)) => return link(&format!("file:{path}"), description),
+            Some(LinkTarget::Heading { title, .. }) => Some(format!("*{}", plain(title))),
+            Some(
+                LinkTarget::Footnote(title)
+                | LinkTarget::Definition(title)
+                | LinkTarget::Generic(title)
+                | LinkTarget::Wiki(title)
+                | LinkTarget::Extendable(title),
+            ) => Some(plain(title)),
+            None => None,
+        };
+
+        let target = match (filepath, search) {
+            (Some(file), Some(search)) => format!("file:{file}.org::{search}"),
+            (Some(file), None) => format!("file:{file}.org"),
+            (None, Some(search)) => search,
+            (None, None) => return description.unwrap_or_default(),
+        };
+        link(&target, description)
+    }
+
+    /// Converts nodes into blocks, which are separated by blank lines.
+    fn blocks(&mut self, nodes: &[NorgAST]) -> Vec<String> {
+        let mut out: Vec<String> = Vec::new();
+        let mut i = 0;
+
+        while i < nodes.len() {
+            let length = 1 + nodes[i + 1..]
+                .iter()
+                .take_while(|node| same_group(&nodes[i], node))
+                .count();
+            if matches!(
+                nodes[i],
+                NorgAST::DelimitingModifier(DelimitingModifier::Weak | DelimitingModifier::Strong)
+            ) && i > 0
+                && i + 1 < nodes.len()
+                && matches!(nodes[i - 1], NorgAST::Heading { .. })
+            {
+                self.lose(
+                    "content after a delimiting modifier became part of the heading before it",
+                );
+            }
+            // Two blank lines end a list, which would otherwise continue with the next one.
+            if i > 0 && is_list(&nodes[i - 1]) && is_list(&nodes[i]) {
+                if let Some(list) = out.last_mut() {
+                    list.push('\n');
+                }
+            }
+            self.push_group(&mut out, &nodes[i..i + length]);
+            i += length;
+        }
+
+        out
+    }
+
+    /// Converts a list item, with the items nested below it.
+    fn item(&mut self, node: &NorgAST, bullet: &str) -> String {
+        let NorgAST::NestableDetachedModifier {
+            extensions,
+            text,
+            content,
+            ..
+        } = node
+        else {
+            return String::new();
+        };
+
+        let mut checkbox = "";
+        for extension in extensions {
+            match extension {
+                DetachedModifierExtension::Todo(status) => {
+                    checkbox = match status {
+                        TodoStatus::Done => "[X] ",
+                        TodoStatus::Pending => "[-] ",
+                        TodoStatus::Undone => "[ ] ",
+                        _ => {
+                            self.lose(&format!(
+                                "a task with the status ({status}) became an unchecked checkbox"
+                            ));
+                            "[ ] "
+                        }
+                    }
+                }
+                _ => self.lose("an extension of a list item was dropped"),
+            }
+        }
+
+        let text = match text.as_ref() {
+            NorgAST::Paragraph(segments) => self.inlines(segments),
+            _ => String::new(),
+        };
+        let mut out = format!("{bullet} {checkbox}{}", text.trim());
+        let prefix = " ".repeat(bullet.len() + 1);
+        for block in self.blocks(content) {
+            out.push('\n');
+            out.push_str(&indent(&block, &prefix));
+        }
+        out
+    }
+
+    fn push_group(&mut self, out: &mut Vec<String>, group: &[NorgAST]) {
+        match &group[0] {
+            NorgAST::NestableDetachedModifier {
+                modifier_type: NestableDetachedModifier::Quote,
+                ..
+            } => {
+                let paragraphs: Vec<_> = group
+                    .iter()
+                    .flat_map(|node| match node {
+                        NorgAST::NestableDetachedModifier { text, content, .. } => {
+                            let mut blocks = self.blocks(std::slice::from_ref(text.as_ref()));
+                            blocks.extend(self.blocks(content));
+                            blocks
+                        }
+                        _ => Vec::new(),
+                    })
+                    .collect();
+                out.push(block("QUOTE", &[], &paragraphs.join("\n\n")));
+            }
+            NorgAST::NestableDetachedModifier { modifier_type, .. } => {
+                let items: Vec<_> = group
+                    .iter()
+                    .enumerate()
+                    .map(|(i, node)| match modifier_type {
+                        NestableDetachedModifier::OrderedList => {
+                            self.item(node, &format!("{}.", i + 1))
+                        }
+                        _ => self.item(node, "-"),
+                    })
+                    .collect();
+                out.push(items.join("\n"));
+            }
+            NorgAST::RangeableDetachedModifier {
+                modifier_type: RangeableDetachedModifier::Definition,
+                ..
+            } => {
+                let definitions: Vec<_> = group
+                    .iter()
+                    .filter_map(|node| match node {
+                        NorgAST::RangeableDetachedModifier { title, content, .. } => {
+                            Some(self.description(title, content))
+                        }
+                        _ => None,
+                    })
+                    .collect();
+                out.push(definitions.join("\n"));
+            }
+            NorgAST::RangeableDetachedModifier {
+                modifier_type: RangeableDetachedModifier::Table,
+                ..
+            } => self.push_table(out, group),
+            NorgAST::RangeableDetachedModifier {
+                modifier_type: RangeableDetachedModifier::Footnote,
+                title,
+                content,
+                ..
+            } => {
+                let content = self.blocks(content).join("\n\n");
+                out.push(format!("[fn:{}] {content}", label(title)));
+            }
+            node => self.push_block(out, node),
+        }
+    }
+
+    /// Converts a definition or table cell into a description list item, e.g. `- term ::
+    /// text`.
+    fn description(&mut self, title: &[ParagraphSegment], content: &[NorgAST]) -> String {
+        let title = self.inlines(title);
+        let content = self.blocks(content).join("\n\n");
+        format!(
+            "- {} :: {}",
+            title.trim(),
+            indent(&content, "  ").trim_start()
+        )
+    }
+
+    /// Turns table cells like `: A1` into a table. If a cell doesn't name a position, the
+    /// cells become a description list instead.
+    fn push_table(&mut self, out: &mut Vec<String>, group: &[NorgAST]) {
+        let cells: Vec<_> = group
+            .iter()
+            .filter_map(|node| match node {
+                NorgAST::RangeableDetachedModifier { title, content, .. } => Some((title, content)),
+                _ => None,
+            })
+            .collect();
+
+        let positions: Option<Vec<_>> = cells
+            .iter()
+            .map(|(title, _)| cell_position(&plain(title)))
+            .collect();
+        let Some(positions) = positions else {
+            self.lose("table cells that aren't named by their position became a description list");
+            let items: Vec<_> = cells
+                .into_iter()
+                .map(|(title, content)| self.description(title, content))
+                .collect();
+            return out.push(items.join("\n"));
+        };
+
+        let rows = positions.iter().map(|(row, _)| row + 1).max().unwrap_or(0);
+        let columns = positions
+            .iter()
+            .map(|(_, column)| column + 1)
+            .max()
+            .unwrap_or(0);
+        let mut grid = vec![vec![String::new(); columns]; rows];
+        for ((_, content), (row, column)) in cells.into_iter().zip(positions) {
+            grid[row][column] = self.blocks(content).join(" ").replace('|', "\\vert{}");
+        }
+
+        let rows: Vec<_> = grid
+            .into_iter()
+            .map(|row| format!("| {} |", row.join(" | ")))
+            .collect();
+        out.push(rows.join("\n"));
+    }
+
+    fn heading(
+        &mut self,
+        level: u16,
+        title: &[ParagraphSegment],
+        extensions: &[DetachedModifierExtension],
+    ) -> String {
+        let mut line = "*".repeat(level.into());
+        let mut planning = Vec::new();
+
+        for extension in extensions {
+            match extension {
+                DetachedModifierExtension::Todo(status) => {
+                    match status {
+                        TodoStatus::Pending | TodoStatus::Paused | TodoStatus::Canceled => {
+                            self.keywords = true
+                        }
+                        TodoStatus::Undone | TodoStatus::Done => (),
+                        status => {
+                            self.lose(&format!("a heading with the status ({status}) became TODO"))
+                        }
+                    }
+                    line.push(' ');
+                    line.push_str(status_keyword(status));
+                }
+                DetachedModifierExtension::Priority(priority) => {
+                    let valid = priority.len() == 1
+                        && priority.chars().all(|c| c.is_ascii_uppercase())
+                        || priority.parse::<u8>().is_ok_and(|priority| priority <= 64);
+                    if valid {
+                        line.push_str(&format!(" [#{priority}]"));
+                    } else {
+                        self.lose(&format!(
+                            "the priority {priority} isn't an Org priority and was dropped"
+                        ));
+                    }
+                }
+                DetachedModifierExtension::StartDate(date)
+                | DetachedModifierExtension::DueDate(date) => {
+                    let keyword = match extension {
+                        DetachedModifierExtension::StartDate(_) => "SCHEDULED",
+                        _ => "DEADLINE",
+                    };
+                    if !is_date(date) {
+                        self.lose(&format!("the date {date} isn't an Org timestamp"));
+                    }
+                    planning.push(format!("{keyword}: <{date}>"));
+                }
+                DetachedModifierExtension::Timestamp(_) => {
+                    self.lose("the timestamp of a heading was dropped")
+                }
+            }
+        }
+
+        line.push(' ');
+        line.push_str(self.inlines(title).trim());
+
+        let mut properties = Vec::new();
+        for (name, parameters) in std::mem::take(&mut self.attributes) {
+            if name == "tags" {
+                line.push_str(&format!(" :{}:", parameters.join(":")));
+            } else {
+                properties.push(
+                    format!(":{}: {}", name.to_uppercase(), parameters.join(" "))
+                        .trim_end()
+                        .to_string(),
+                );
+            }
+        }
+
+        let mut lines = vec![line];
+        if !planning.is_empty() {
+            lines.push(planning.join(" "));
+        }
+        if !properties.is_empty() {
+            lines.push(String::from(":PROPERTIES:"));
+            lines.extend(properties);
+            lines.push(String::from(":END:"));
+        }
+        lines.join("\n")
+    }
+
+    fn push_block(&mut self, out: &mut Vec<String>, node: &NorgAST) {
+        match node {
+            NorgAST::Paragraph(segments) => {
+                let text = self.inlines(segments).trim().to_string();
+                out.push(protect(text));
+            }
+            NorgAST::Heading {
+                level,
+                title,
+                extensions,
+                content,
+            } => {
+                let heading = self.heading(*level, title, extensions);
+                out.push(heading);
+                out.extend(self.blocks(content));
+            }
+            NorgAST::CarryoverTag {
+                tag_type,
+                name,
+                parameters,
+                next_object,
+            } => {
+                let name = name.join(".");
+                match tag_type {
+                    CarryoverTag::Attribute => self.attributes.push((name, parameters.clone())),
+                    CarryoverTag::Macro if name == "comment" => {
+                        return self.lose("an object commented out with #comment was dropped")
+                    }
+                    CarryoverTag::Macro => self.lose(&format!("the #{name} tag was dropped")),
+                }
+                self.push_group(out, std::slice::from_ref(next_object));
+                if !self.attributes.is_empty() {
+                    self.attributes.clear();
+                    self.lose("attribute tags that don't belong to a heading were dropped");
+                }
+            }
+            NorgAST::VerbatimRangedTag {
+                name,
+                parameters,
+                content,
+            } => {
+                let content: Vec<_> = content
+                    .trim_end_matches('\n')
+                    .lines()
+                    .map(escape_line)
+                    .collect();
+                let content = content.join("\n");
+                match name
+                    .iter()
+                    .map(String::as_str)
+                    .collect::<Vec<_>>()
+                    .as_slice()
+                {
+                    ["code"] if parameters.is_empty() => out.push(block("EXAMPLE", &[], &content)),
+                    ["code"] => out.push(block("SRC", parameters, &content)),
+                    ["math"] => out.push(format!("\\[\n{}\n\\]", content.trim())),
+                    ["comment"] => out.push(block("COMMENT", &[], &content)),
+                    // Becomes keywords at the start of the document.
+                    ["document", "meta"] => (),
+                    _ => {
+                        self.lose(&format!(
+                            "the @{} tag became an example block",
+                            name.join(".")
+                        ));
+                        out.push(block("EXAMPLE", &[], &content));
+                    }
+                }
+            }
+            NorgAST::RangedTag {
+                name,
+                parameters,
+                content,
+            } => {
+                let content = self.blocks(content).join("\n\n");
+                out.push(block(&name.join(".").to_uppercase(), parameters, &content));
+            }
+            NorgAST::InfirmTag { name, parameters } => {
+                match (name.as_slice(), parameters.first()) {
+                    ([image], Some(source)) if image == "image" => {
+                        let source = source.replace("%20", " ");
+                        match source.contains("://") {
+                            true => out.push(format!("[[{source}]]")),
+                            false => out.push(format!("[[file:{source}]]")),
+                        }
+                    }
+                    ([toc], _) if toc == "toc" => out.push(String::from("#+TOC: headlines")),
+                    _ => self.lose(&format!("the .{} tag was dropped", name.join("."))),
+                }
+            }
+            NorgAST::DelimitingModifier(DelimitingModifier::HorizontalRule) => {
+                out.push(String::from("-----"))
+            }
+            NorgAST::DelimitingModifier(_) => (),
+            NorgAST::NestableDetachedModifier { .. }
+            | NorgAST::RangeableDetachedModifier { .. } => {
+                self.push_group(out, std::slice::from_ref(node))
+            }
+        }
+    }
+
+    /// The keywords for the metadata of a document, e.g. `#+TITLE: Notes`.
+    fn keywords(&mut self, meta: &NorgMeta) -> Vec<String> {
+        let NorgMeta::Object(entries) = meta else {
+            return Vec::new();
+        };
+
+        // The title goes first, as is customary.
+        let entries = entries
+            .iter()
+            .filter(|(key, _)| *key == "title")
+            .chain(entries.iter().filter(|(key, _)| *key != "title"));
+
+        let mut lines = Vec::new();
+        for (key, value) in entries {
+            let keyword = match key.as_str() {
+                "authors" => String::from("AUTHOR"),
+                "created" => String::from("DATE"),
+                "categories" => String::from("FILETAGS"),
+                key => key.to_uppercase(),
+            };
+            if keyword.is_empty() || keyword.contains(|c: char| c.is_whitespace() || c == ':') {
+                self.lose(&format!("the metadata {key} was dropped"));
+                continue;
+            }
+
+            let scalar = |value: &NorgMeta| match value {
+                NorgMeta::Str(value) => Some(value.replace('\n', " ")),
+                NorgMeta::Num(value) => Some(value.to_string()),
+                NorgMeta::Bool(value) => Some(value.to_string()),
+                NorgMeta::Nil => Some(String::new()),
+                _ => None,
+            };
+            let values = match value {
+                NorgMeta::Array(values) if keyword == "FILETAGS" => values
+                    .iter()
+                    .map(scalar)
+                    .collect::<Option<Vec<_>>>()
+                    .map(|tags| vec![format!(":{}:", tags.join(":"))]),
+                NorgMeta::Array(values) => values.iter().map(scalar).collect(),
+                value => scalar(value).map(|value| vec![value]),
+            };
+            match values {
+                Some(values) => lines.extend(
+                    values
+                        .into_iter()
+                        .map(|value| format!("#+{keyword}: {value}").trim_end().to_string()),
+                ),
+                None => self.lose(&format!("the metadata {key} was dropped")),
+            }
+        }
+        lines
+    }
+}
+
+/// Converts a document into Org.
+///
+/// Todo statuses become the TODO keywords `TODO`, `NEXT`, `WAITING`, `DONE` and `CANCELLED`,
+/// with a `#+TODO:` line if keywords other than `TODO` and `DONE` are used. The metadata of a
+/// top level `@document.meta` tag becomes keywords like `#+TITLE:` and `+attribute` tags of
+/// headings become properties. Table cells named by their position become a table.
+///
+/// # Arguments
+///
+/// * `ast` - The document, as returned by [`crate::parse_tree`].
+///
+/// # Returns
+///
+/// * `Export` with the Org document and everything that couldn't be converted exactly.
+pub fn to_org(ast: &[NorgAST]) -> Export {
+    let mut references = References::default();
+    ast.iter().for_each(|node| references.visit_node(node));
+
+    let mut exporter = Exporter {
+        references,
+        attributes: Vec::new(),
+        keywords: false,
+        losses: Vec::new(),
+    };
+    let body = exporter.blocks(ast);
+
+    let meta = ast.iter().find_map(|node| match node {
+        NorgAST::VerbatimRangedTag { name, content, .. } if name == &["document", "meta"] => {
+            parse_metadata(content).ok()
+        }
+        _ => None,
+    });
+    let mut header = meta.map_or(Vec::new(), |meta| exporter.keywords(&meta));
+    if exporter.keywords {
+        header.push(String::from("#+TODO: TODO NEXT WAITING | DONE CANCELLED"));
+    }
+
+    let mut blocks = Vec::new();
+    if !header.is_empty() {
+        blocks.push(header.join("\n"));
+    }
+    blocks.extend(body);
+
+    Export {
+        org: blocks.join("\n\n") + "\n",
+        losses: exporter.losses,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use insta::assert_snapshot;
+
+    use crate::{
+        org::{from_org, to_org},
+        parse, parse_tree,
+    };
+
+    #[test]
+    fn planner() {
+        let source = "#+TITLE: Planner
+#+AUTHOR: Ada
+#+FILETAGS: :work:planning:
+#+TODO: TODO STARTED | DONE CANCELLED
+
+* STARTED [#B] Release 2.0 :work:
+SCHEDULED: <2025-02-24 Mon> DEADLINE: <2025-03-01 Sat>
+:PROPERTIES:
+:OWNER: ada
+:END:
+
+We ship on *Saturday*, see [[*Checklist][the checklist]] and [[https://example.com][the site]].
+
+** DONE Checklist
+CLOSED: [2025-02-20 Thu]
+
+- [X] Write the =CHANGELOG=
+- [ ] Tag the release
+  - run +make+ =just= release
+
+#+BEGIN_SRC rust
+fn main() {}
+#+END_SRC
+
+* Notes
+:LOGBOOK:
+- Note taken
+:END:
+
+#+BEGIN_QUOTE
+Release early, release often.
+#+END_QUOTE
+
+- term :: its meaning
+
+| a | b |
+|---+---|
+| 1 | 2 |
+
+A footnote[fn:1] and <<target>>.
+
+[fn:1] The text.
+";
+        let import = from_org(source);
+        let norg = import.document.to_string();
+        assert_snapshot!(norg);
+        assert_eq!(parse(&norg).unwrap(), import.document.build());
+
+        let losses: Vec<_> = import
+            .losses
+            .iter()
+            .map(|loss| (loss.line, loss.message.as_str()))
+            .collect();
+        assert_eq!(
+            losses,
+            [
+                (5, "the TODO keyword STARTED became NEXT"),
+                (14, "the CLOSED date of a heading was dropped"),
+                (
+                    16,
+                    "the verbatim text =CHANGELOG= became inline code, which is exported as ~CHANGELOG~"
+                ),
+                (
+                    18,
+                    "the verbatim text =just= became inline code, which is exported as ~just~"
+                ),
+                (25, "the LOGBOOK drawer was dropped"),
+            ]
+        );
+
+        let import = from_org("#+TITLE: Planner\n#+DATE: 2025\n");
+        let losses: Vec<_> = import
+            .losses
+            .iter()
+            .map(|loss| (loss.line, loss.message.as_str()))
+            .collect();
+        assert_eq!(
+            losses,
+            [(
+                1,
+                "the metadata string `2025` is read back as another value"
+            )]
+        );
+    }
+
+    #[test]
+    fn export() {
+        let norg = "@document.meta
+title: Export
+authors: [
+  Ada
+  Grace
+]
+@end
+
+* (-|# A|< 2025-03-01) Ship
+  Some /text/ with `code`, a {https://example.com}[link] and a note{^ 1}.
+
+  - ( ) one
+  -- (x) nested
+  - (=) two
+
+  ~ first
+  ~ second
+
+  @code lua
+  print(\"hi\")
+  @end
+
+  $ term
+  definition
+
+  : A1
+  x
+  : B1
+  y
+
+  ^ 1
+  The note.
+";
+        let export = to_org(&parse_tree(norg).unwrap());
+        assert_snapshot!(export.org);
+        assert_eq!(
+            export.losses,
+            ["a task with the status (=) became an unchecked checkbox"]
+        );
+    }
+
+    #[test]
+    fn round_trip() {
+        let org = "#+TITLE: Round trip
+#+TODO: TODO NEXT WAITING | DONE CANCELLED
+
+* NEXT [#A] Task :home:
+DEADLINE: <2025-03-01 Sat>
+:PROPERTIES:
+:EFFORT: 1h
+:END:
+
+Text with *bold*, /italic/, +strike+ and ~code~.
+
+** WAITING Sub task
+
+- [X] done
+- [ ] open
+  - nested
+
+
+1. first
+2. second
+
+* Links
+
+[[file:notes.org::*Ideas][ideas]] and [[*Task]].
+
+#+BEGIN_NOTE
+A note.
+#+END_NOTE
+
+-----
+";
+        let import = from_org(org);
+        assert_eq!(import.losses, []);
+        let export = to_org(&parse_tree(&import.document.to_string()).unwrap());
+        assert_eq!(export.losses, Vec::<String>::new());
+        assert_eq!(export.org, org);
+    }
+}
@@ -0,0 +1,170 @@
+//! Generates a heading outline (table of contents) from a [`NorgAST`].
+
+use std::collections::HashSet;
+
+use serde::Serialize;
+
+use crate::{
+    plain_text::{inline_to_plain_text, PlainTextOptions},
+    NorgAST, ParagraphSegment,
+};
+
+/// A single heading in the outline.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct OutlineEntry {
+    pub level: u16,
+    /// The title of the heading as plain text.
+    pub title: String,
+    /// An anchor for the heading, unique across the whole outline.
+    pub slug: String,
+    pub children: Vec<OutlineEntry>,
+}
+
+/// Turns a heading title into an anchor the same way GitHub does: the title is lowercased,
+/// whitespace becomes `-` and everything apart from letters, digits, `-` and `_` is dropped.
+pub fn slugify(title: &str) -> String {
+    title
+        .trim()
+        .chars()
+        .filter_map(|c| {
+            if c.is_whitespace() {
+                Some('-')
+            } else if c.is_alphanumeric() || c == '-' || c == '_' {
+                Some(c)
+            } else {
+                None
+            }
+        })
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Hands out slugs, appending `-1`, `-2`, ... to titles that have been seen before.
+#[derive(Default)]
+pub(crate) struct Slugs(HashSet<String>);
+
+impl Slugs {
+    pub(crate) fn unique(&mut self, title: &str) -> String {
+        let mut base = slugify(title);
+        if base.is_empty() {
+            base = String::from("heading");
+        }
+
+        let mut slug = base.clone();
+        let mut i = 1;
+        while self.0.contains(&slug) {
+            slug = format!("{base}-{i}");
+            i += 1;
+        }
+
+        self.0.insert(slug.clone());
+        slug
+    }
+
+    /// Marks a slug as taken without handing it out, so that [`Slugs::unique`] avoids it.
+    pub(crate) fn reserve(&mut self, slug: &str) {
+        self.0.insert(slug.to_string());
+    }
+}
+
+fn entry(
+    level: u16,
+    title: &[ParagraphSegment],
+    content: &[NorgAST],
+    slugs: &mut Slugs,
+) -> OutlineEntry {
+    let title = inline_to_plain_text(title, &PlainTextOptions::default());
+    let slug = slugs.unique(&title);
+
+    OutlineEntry {
+        level,
+        title,
+        slug,
+        children: entries(content, slugs),
+    }
+}
+
+fn heading(node: &NorgAST) -> Option<(u16, &[ParagraphSegment], &[NorgAST])> {
+    match node {
+        NorgAST::Heading {
+            level,
+            title,
+            content,
+            ..
+        } => Some((*level, title, content)),
+        NorgAST::CarryoverTag { next_object, .. } => heading(next_object),
+        _ => None,
+    }
+}
+
+fn entries(ast: &[NorgAST], slugs: &mut Slugs) -> Vec<OutlineEntry> {
+    ast.iter()
+        .filter_map(heading)
+        .map(|(level, title, content)| entry(level, title, content, slugs))
+        .collect()
+}
+
+/// Builds the outline of a document.
+///
+/// Nesting follows the tree built by [`crate::parse_tree`], so sections closed by weak (`---`)
+/// or strong (`===`) delimiting modifiers end up where they belong. Headings inside of other
+/// constructs, e.g. an `|example` tag, are not part of the outline.
+///
+/// Slugs are unique across the entire outline. Duplicate titles get `-1`, `-2` and so on
+/// appended, in document order.
+pub fn outline(ast: &[NorgAST]) -> Vec<OutlineEntry> {
+    entries(ast, &mut Slugs::default())
+}
+
+#[cfg(test)]
+mod tests {
+    use insta::assert_yaml_snapshot;
+    use itertools::Itertools;
+
+    use crate::{
+        outline::{outline, slugify},
+        parse_tree,
+    };
+
+    #[test]
+    fn outlines() {
+        let examples: Vec<_> = [
+            "* One
+             ** Two
+             *** Three
+             ** Two again
+             * Four",
+            "* Intro
+             ** Intro
+             * Intro
+             * Intro 1",
+            "* *Bold* /title/ with {https://example.com}[a link]
+               content
+               ---
+             #id 1
+             * Tagged
+               ===
+             |example
+             * Not in the outline
+             |end",
+            "#id 1
+             +attr
+             * Chained
+             ** Child",
+        ]
+        .into_iter()
+        .map(|example| example.to_string() + "\n")
+        .map(|str| parse_tree(&str).map(|ast| outline(&ast)))
+        .try_collect()
+        .unwrap();
+
+        assert_yaml_snapshot!(examples);
+    }
+
+    #[test]
+    fn slugs() {
+        assert_eq!(slugify("Hello, World!"), "hello-world");
+        assert_eq!(slugify("  Ünïcode  Title "), "ünïcode--title");
+        assert_eq!(slugify("snake_case and-dashes"), "snake_case-and-dashes");
+    }
+}
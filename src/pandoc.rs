@@ -0,0 +1,837 @@
+//! Exports documents to Pandoc's JSON AST, so `pandoc -f json` can turn them into PDF, DOCX,
+//! LaTeX, EPUB and everything else Pandoc supports.
+//!
+//! Only the parts of the Pandoc AST the exporter produces are modelled here. Serializing a
+//! [`Pandoc`] with e.g. `serde_json` gives the format of version 1.23 of `pandoc-types`, used by
+//! Pandoc 3.
+//!
+//! Constructs Pandoc has no equivalent for (tags apart from `@code`, `@math`, `.image` and
+//! `#comment`) become a `Div` with the name of the tag as its class and its parameters in a
+//! `parameters` attribute. Footnotes are rendered as notes where they are referenced, with
+//! `{^ title}`. `@math` blocks become display math and `$...$` becomes `InlineMath`.
+
+use std::collections::{BTreeMap, HashMap};
+
+use serde::Serialize;
+
+use crate::{
+    metadata::{parse_metadata, NorgMeta},
+    outline::{slugify, Slugs},
+    plain_text::{inline_to_plain_text, PlainTextOptions},
+    visit::{walk_node, walk_segment, Visitor},
+    DelimitingModifier, DetachedModifierExtension, LinkTarget, NestableDetachedModifier, NorgAST,
+    ParagraphSegment, ParagraphSegmentToken, RangeableDetachedModifier, TodoStatus,
+};
+
+/// The version of `pandoc-types` the output conforms to.
+pub const API_VERSION: [u32; 3] = [1, 23, 1];
+
+/// An identifier, classes and key-value pairs.
+pub type Attr = (String, Vec<String>, Vec<(String, String)>);
+
+/// A whole Pandoc document.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Pandoc {
+    #[serde(rename = "pandoc-api-version")]
+    pub api_version: [u32; 3],
+    pub meta: BTreeMap<String, MetaValue>,
+    pub blocks: Vec<Block>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(tag = "t", content = "c")]
+pub enum MetaValue {
+    MetaMap(BTreeMap<String, MetaValue>),
+    MetaList(Vec<MetaValue>),
+    MetaBool(bool),
+    MetaString(String),
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(tag = "t", content = "c")]
+pub enum Block {
+    Plain(Vec<Inline>),
+    Para(Vec<Inline>),
+    CodeBlock(Attr, String),
+    BlockQuote(Vec<Block>),
+    OrderedList(ListAttributes, Vec<Vec<Block>>),
+    BulletList(Vec<Vec<Block>>),
+    DefinitionList(Vec<(Vec<Inline>, Vec<Vec<Block>>)>),
+    Header(u16, Attr, Vec<Inline>),
+    HorizontalRule,
+    Table(
+        Attr,
+        Caption,
+        Vec<ColSpec>,
+        Box<TableHead>,
+        Vec<TableBody>,
+        Box<TableFoot>,
+    ),
+    Div(Attr, Vec<Block>),
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(tag = "t", content = "c")]
+pub enum Inline {
+    Str(String),
+    Emph(Vec<Inline>),
+    Underline(Vec<Inline>),
+    Strong(Vec<Inline>),
+    Strikeout(Vec<Inline>),
+    Space,
+    Code(Attr, String),
+    Math(MathType, String),
+    /// The content and the URL and title of the target.
+    Link(Attr, Vec<Inline>, (String, String)),
+    Image(Attr, Vec<Inline>, (String, String)),
+    Note(Vec<Block>),
+    Span(Attr, Vec<Inline>),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(tag = "t")]
+pub enum MathType {
+    DisplayMath,
+    InlineMath,
+}
+
+/// The number to start at, the style and the delimiter of an ordered list.
+pub type ListAttributes = (u32, ListNumberStyle, ListNumberDelim);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(tag = "t")]
+pub enum ListNumberStyle {
+    Decimal,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(tag = "t")]
+pub enum ListNumberDelim {
+    Period,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(tag = "t")]
+pub enum Alignment {
+    AlignDefault,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(tag = "t")]
+pub enum ColWidth {
+    ColWidthDefault,
+}
+
+/// A short caption and the full caption of a table.
+pub type Caption = (Option<Vec<Inline>>, Vec<Block>);
+pub type ColSpec = (Alignment, ColWidth);
+/// A cell with its alignment, the rows and the columns it spans and its content.
+pub type Cell = (Attr, Alignment, u32, u32, Vec<Block>);
+pub type Row = (Attr, Vec<Cell>);
+pub type TableHead = (Attr, Vec<Row>);
+/// The number of row header columns, the header rows and the rows of a table body.
+pub type TableBody = (Attr, u32, Vec<Row>, Vec<Row>);
+pub type TableFoot = (Attr, Vec<Row>);
+
+fn attr(classes: Vec<String>, attributes: Vec<(String, String)>) -> Attr {
+    (String::new(), classes, attributes)
+}
+
+/// The attributes of an unmapped tag.
+fn tag_attr(name: &[String], parameters: &[String]) -> Attr {
+    let attributes = if parameters.is_empty() {
+        Vec::new()
+    } else {
+        vec![("parameters".to_string(), parameters.join(" "))]
+    };
+    attr(vec![name.join(".")], attributes)
+}
+
+pub(crate) fn plain(segments: &[ParagraphSegment]) -> String {
+    inline_to_plain_text(segments, &PlainTextOptions::default())
+}
+
+/// The footnotes and anchor definitions of a document, which may be referenced from anywhere.
+#[derive(Default)]
+pub(crate) struct References {
+    pub(crate) footnotes: HashMap<String, Vec<NorgAST>>,
+    pub(crate) anchors: HashMap<String, ParagraphSegment>,
+}
+
+impl Visitor for References {
+    fn visit_node(&mut self, node: &NorgAST) {
+        if let NorgAST::RangeableDetachedModifier {
+            modifier_type: RangeableDetachedModifier::Footnote,
+            title,
+            content,
+            ..
+        } = node
+        {
+            self.footnotes
+                .entry(plain(title))
+                .or_insert_with(|| content.clone());
+        }
+        walk_node(self, node)
+    }
+
+    fn visit_segment(&mut self, segment: &ParagraphSegment) {
+        if let ParagraphSegment::AnchorDefinition { content, target } = segment {
+            self.anchors
+                .entry(plain(content))
+                .or_insert_with(|| target.as_ref().clone());
+        }
+        walk_segment(self, segment)
+    }
+}
+
+/// The classes and attributes for detached modifier extensions, e.g. `todo="done"`.
+fn extension_attributes(extensions: &[DetachedModifierExtension]) -> Vec<(String, String)> {
+    extensions
+        .iter()
+        .map(|extension| {
+            let (key, value) = match extension {
+                DetachedModifierExtension::Todo(status) => ("todo", status_name(status)),
+                DetachedModifierExtension::Priority(value) => ("priority", value.clone()),
+                DetachedModifierExtension::Timestamp(value) => ("timestamp", value.clone()),
+                DetachedModifierExtension::DueDate(value) => ("due", value.clone()),
+                DetachedModifierExtension::StartDate(value) => ("start", value.clone()),
+            };
+            (key.to_string(), value)
+        })
+        .collect()
+}
+
+fn status_name(status: &TodoStatus) -> String {
+    match status {
+        TodoStatus::Undone => "undone",
+        TodoStatus::Done => "done",
+        TodoStatus::NeedsClarification => "needs-clarification",
+        TodoStatus::Paused => "paused",
+        TodoStatus::Urgent => "urgent",
+        TodoStatus::Recurring(_) => "recurring",
+        TodoStatus::Pending => "pending",
+        TodoStatus::Canceled => "canceled",
+    }
+    .to_string()
+}
+
+/// Removes spaces at the start and the end.
+fn trim(mut inlines: Vec<Inline>) -> Vec<Inline> {
+    while inlines.last() == Some(&Inline::Space) {
+        inlines.pop();
+    }
+    let start = inlines
+        .iter()
+        .take_while(|inline| **inline == Inline::Space)
+        .count();
+    inlines.split_off(start)
+}
+
+/// Parses the title of a table cell, e.g. `B3`, into its row and column.
+pub(crate) fn cell_position(title: &str) -> Option<(usize, usize)> {
+    let title = title.trim();
+    let split = title.find(|c: char| !c.is_ascii_alphabetic())?;
+    let (letters, digits) = title.split_at(split);
+    if letters.is_empty() {
+        return None;
+    }
+
+    let column = letters.chars().fold(0, |column, c| {
+        column * 26 + (c.to_ascii_uppercase() as usize - 'A' as usize + 1)
+    });
+    let row: usize = digits.parse().ok()?;
+
+    Some((row.checked_sub(1)?, column - 1))
+}
+
+/// The name of the cell in a row and column, e.g. `B3`.
+pub(crate) fn cell_name(row: usize, column: usize) -> String {
+    let mut letters = Vec::new();
+    let mut column = column + 1;
+    while column > 0 {
+        letters.push((b'A' + ((column - 1) % 26) as u8) as char);
+        column = (column - 1) / 26;
+    }
+    letters.iter().rev().collect::<String>() + &(row + 1).to_string()
+}
+
+fn meta_value(meta: &NorgMeta) -> Option<MetaValue> {
+    match meta {
+        NorgMeta::Bool(value) => Some(MetaValue::MetaBool(*value)),
+        NorgMeta::Str(value) => Some(MetaValue::MetaString(value.clone())),
+        NorgMeta::Num(value) => Some(MetaValue::MetaString(value.to_string())),
+        NorgMeta::Array(values) => Some(MetaValue::MetaList(
+            values.iter().filter_map(meta_value).collect(),
+        )),
+        NorgMeta::Object(entries) => Some(MetaValue::MetaMap(meta_map(entries))),
+        NorgMeta::Invalid | NorgMeta::Nil | NorgMeta::EmptyKey(_) => None,
+    }
+}
+
+fn meta_map(entries: &BTreeMap<String, NorgMeta>) -> BTreeMap<String, MetaValue> {
+    entries
+        .iter()
+        .filter_map(|(key, value)| Some((key.clone(), meta_value(value)?)))
+        .collect()
+}
+
+struct Exporter {
+    references: References,
+    slugs: Slugs,
+    /// The footnotes currently being rendered, so a footnote can't contain itself.
+    notes: Vec<String>,
+}
+
+impl Exporter {
+    fn push_text(out: &mut Vec<Inline>, text: &str) {
+        match out.last_mut() {
+            Some(Inline::Str(str)) => str.push_str(text),
+            _ => out.push(Inline::Str(text.to_string())),
+        }
+    }
+
+    fn inlines(&mut self, segments: &[ParagraphSegment]) -> Vec<Inline> {
+        let mut out = Vec::new();
+        for segment in segments {
+            self.push_inline(&mut out, segment);
+        }
+        out
+    }
+
+    fn push_inline(&mut self, out: &mut Vec<Inline>, segment: &ParagraphSegment) {
+        let inline = match segment {
+            ParagraphSegment::Token(token) => {
+                return match token {
+                    ParagraphSegmentToken::Text(text) => Self::push_text(out, text),
+                    ParagraphSegmentToken::Whitespace => out.push(Inline::Space),
+                    ParagraphSegmentToken::Special(c) | ParagraphSegmentToken::Escape(c) => {
+                        Self::push_text(out, c.encode_utf8(&mut [0; 4]))
+                    }
+                };
+            }
+            ParagraphSegment::AttachedModifier {
+                modifier_type,
+                content,
+            } => {
+                let content = self.inlines(content);
+                match modifier_type {
+                    '*' => Inline::Strong(content),
+                    '/' => Inline::Emph(content),
+                    '_' => Inline::Underline(content),
+                    '-' => Inline::Strikeout(content),
+                    // Null modifiers are comments.
+                    '%' => return,
+                    c => Inline::Span(attr(vec![c.to_string()], Vec::new()), content),
+                }
+            }
+            ParagraphSegment::InlineVerbatim(tokens) => Inline::Code(
+                attr(Vec::new(), Vec::new()),
+                tokens.iter().map(ToString::to_string).collect(),
+            ),
+            ParagraphSegment::InlineMath(tokens) => Inline::Math(
+                MathType::InlineMath,
+                tokens.iter().map(ToString::to_string).collect(),
+            ),
+            ParagraphSegment::Link {
+                filepath,
+                targets,
+                description,
+            } => self.link(filepath, targets, description.as_deref()),
+            ParagraphSegment::AnchorDefinition { content, target } => match target.as_ref() {
+                ParagraphSegment::Link {
+                    filepath, targets, ..
+                } => self.link(filepath, targets, Some(content)),
+                _ => Inline::Span(attr(Vec::new(), Vec::new()), self.inlines(content)),
+            },
+            ParagraphSegment::Anchor {
+                content,
+                description,
+            } => {
+                let text = description.as_deref().unwrap_or(content);
+                match self.references.anchors.get(&plain(content)).cloned() {
+                    Some(ParagraphSegment::Link {
+                        filepath, targets, ..
+                    }) => self.link(&filepath, &targets, Some(text)),
+                    _ => Inline::Span(
+                        attr(vec!["anchor".to_string()], Vec::new()),
+                        self.inlines(text),
+                    ),
+                }
+            }
+            ParagraphSegment::InlineLinkTarget(content) => Inline::Span(
+                (slugify(&plain(content)), Vec::new(), Vec::new()),
+                self.inlines(content),
+            ),
+            // Intermediate results of the paragraph parser, which don't make it into the AST.
+            _ => return,
+        };
+
+        out.push(inline);
+    }
+
+    fn link(
+        &mut self,
+        filepath: &Option<String>,
+        targets: &[LinkTarget],
+        description: Option<&[ParagraphSegment]>,
+    ) -> Inline {
+        let description = description.map(|description| self.inlines(description));
+
+        let (url, text) = match targets.first() {
+            Some(LinkTarget::Footnote(title)) if filepath.is_none() => {
+                if let Some(note) = self.note(&plain(title)) {
+                    return note;
+                }
+                (format!("#{}", slugify(&plain(title))), self.inlines(title))
+            }
+            Some(LinkTarget::Timestamp(timestamp)) => {
+                return Inline::Span(
+                    attr(vec!["timestamp".to_string()], Vec::new()),
+                    description.unwrap_or_else(|| vec![Inline::Str(timestamp.clone())]),
+                );
+            }
+            Some(LinkTarget::Url(url) | LinkTarget::Path(url)) => {
+                (url.clone(), vec![Inline::Str(url.clone())])
+            }
+            Some(
+                LinkTarget::Heading { title, .. }
+                | LinkTarget::Footnote(title)
+                | LinkTarget::Definition(title)
+                | LinkTarget::Generic(title)
+                | LinkTarget::Wiki(title)
+                | LinkTarget::Extendable(title),
+            ) => (
+                format!(
+                    "{}#{}",
+                    filepath.as_deref().unwrap_or_default(),
+                    slugify(&plain(title))
+                ),
+                self.inlines(title),
+            ),
+            None => {
+                let file = filepath.clone().unwrap_or_default();
+                (file.clone(), vec![Inline::Str(file)])
+            }
+        };
+
+        Inline::Link(
+            attr(Vec::new(), Vec::new()),
+            description.unwrap_or(text),
+            (url, String::new()),
+        )
+    }
+
+    fn note(&mut self, title: &str) -> Option<Inline> {
+        if self.notes.iter().any(|note| note == title) {
+            return None;
+        }
+        let content = self.references.footnotes.get(title)?.clone();
+
+        self.notes.push(title.to_string());
+        let blocks = self.blocks(&content);
+        self.notes.pop();
+
+        Some(Inline::Note(blocks))
+    }
+
+    fn blocks(&mut self, nodes: &[NorgAST]) -> Vec<Block> {
+        let mut out = Vec::new();
+        let mut i = 0;
+
+        while i < nodes.len() {
+            let length = 1 + nodes[i + 1..]
+                .iter()
+                .take_while(|node| same_group(&nodes[i], node))
+                .count();
+            self.push_group(&mut out, &nodes[i..i + length]);
+            i += length;
+        }
+
+        out
+    }
+
+    /// The blocks of a list item or quote.
+    fn item(&mut self, node: &NorgAST) -> Vec<Block> {
+        let NorgAST::NestableDetachedModifier {
+            extensions,
+            text,
+            content,
+            ..
+        } = node
+        else {
+            return Vec::new();
+        };
+
+        let mut blocks = match text.as_ref() {
+            NorgAST::Paragraph(segments) => {
+                let mut inlines = Vec::new();
+                // The same markers Pandoc uses for GitHub task lists.
+                match extensions.iter().find_map(|extension| match extension {
+                    DetachedModifierExtension::Todo(status) => Some(status),
+                    _ => None,
+                }) {
+                    Some(TodoStatus::Done) => {
+                        inlines.extend([Inline::Str("☒".into()), Inline::Space])
+                    }
+                    Some(_) => inlines.extend([Inline::Str("☐".into()), Inline::Space]),
+                    None => (),
+                }
+                inlines.extend(trim(self.inlines(segments)));
+                vec![Block::Plain(inlines)]
+            }
+            text => self.blocks(std::slice::from_ref(text)),
+        };
+        blocks.extend(self.blocks(content));
+        blocks
+    }
+
+    fn push_group(&mut self, out: &mut Vec<Block>, group: &[NorgAST]) {
+        match &group[0] {
+            NorgAST::NestableDetachedModifier { modifier_type, .. } => {
+                let items = group.iter().map(|node| self.item(node)).collect();
+                out.push(match modifier_type {
+                    NestableDetachedModifier::UnorderedList => Block::BulletList(items),
+                    NestableDetachedModifier::OrderedList => Block::OrderedList(
+                        (1, ListNumberStyle::Decimal, ListNumberDelim::Period),
+                        items,
+                    ),
+                    NestableDetachedModifier::Quote => Block::BlockQuote(
+                        items
+                            .into_iter()
+                            .flatten()
+                            .map(|block| match block {
+                                Block::Plain(inlines) => Block::Para(inlines),
+                                block => block,
+                            })
+                            .collect(),
+                    ),
+                });
+            }
+            NorgAST::RangeableDetachedModifier {
+                modifier_type: RangeableDetachedModifier::Definition,
+                ..
+            } => {
+                let definitions = group
+                    .iter()
+                    .filter_map(|node| match node {
+                        NorgAST::RangeableDetachedModifier { title, content, .. } => {
+                            Some((trim(self.inlines(title)), vec![self.blocks(content)]))
+                        }
+                        _ => None,
+                    })
+                    .collect();
+                out.push(Block::DefinitionList(definitions));
+            }
+            NorgAST::RangeableDetachedModifier {
+                modifier_type: RangeableDetachedModifier::Table,
+                ..
+            } => self.push_table(out, group),
+            // Footnotes are rendered where they are referenced.
+            NorgAST::RangeableDetachedModifier {
+                modifier_type: RangeableDetachedModifier::Footnote,
+                ..
+            } => (),
+            node => self.push_block(out, node),
+        }
+    }
+
+    /// Turns table cells like `: A1` into a table. Cells which don't name a position are kept
+    /// as a `Div` each.
+    fn push_table(&mut self, out: &mut Vec<Block>, cells: &[NorgAST]) {
+        let cells: Vec<_> = cells
+            .iter()
+            .filter_map(|node| match node {
+                NorgAST::RangeableDetachedModifier { title, content, .. } => {
+                    Some((plain(title), content))
+                }
+                _ => None,
+            })
+            .collect();
+
+        let positions: Option<Vec<_>> = cells
+            .iter()
+            .map(|(title, _)| cell_position(title))
+            .collect();
+        let Some(positions) = positions else {
+            for (title, content) in cells {
+                let blocks = self.blocks(content);
+                out.push(Block::Div(
+                    attr(
+                        vec!["table-cell".to_string()],
+                        vec![("cell".to_string(), title.trim().to_string())],
+                    ),
+                    blocks,
+                ));
+            }
+            return;
+        };
+
+        let rows = positions.iter().map(|(row, _)| row + 1).max().unwrap_or(0);
+        let columns = positions
+            .iter()
+            .map(|(_, column)| column + 1)
+            .max()
+            .unwrap_or(0);
+        let mut grid = vec![vec![Vec::new(); columns]; rows];
+        for ((_, content), (row, column)) in cells.into_iter().zip(positions) {
+            grid[row][column] = self.blocks(content);
+        }
+
+        let empty = || attr(Vec::new(), Vec::new());
+        let rows = grid
+            .into_iter()
+            .map(|row| {
+                let cells = row
+                    .into_iter()
+                    .map(|blocks| (empty(), Alignment::AlignDefault, 1, 1, blocks))
+                    .collect();
+                (empty(), cells)
+            })
+            .collect();
+
+        out.push(Block::Table(
+            empty(),
+            (None, Vec::new()),
+            vec![(Alignment::AlignDefault, ColWidth::ColWidthDefault); columns],
+            Box::new((empty(), Vec::new())),
+            vec![(empty(), 0, Vec::new(), rows)],
+            Box::new((empty(), Vec::new())),
+        ));
+    }
+
+    fn push_block(&mut self, out: &mut Vec<Block>, node: &NorgAST) {
+        match node {
+            NorgAST::Paragraph(segments) => out.push(Block::Para(self.inlines(segments))),
+            NorgAST::Heading {
+                level,
+                title,
+                extensions,
+                content,
+            } => {
+                let id = self.slugs.unique(&plain(title));
+                out.push(Block::Header(
+                    *level,
+                    (id, Vec::new(), extension_attributes(extensions)),
+                    trim(self.inlines(title)),
+                ));
+                out.extend(self.blocks(content));
+            }
+            NorgAST::CarryoverTag {
+                name,
+                parameters,
+                next_object,
+                ..
+            } => {
+                if name != &["comment"] {
+                    let blocks = self.blocks(std::slice::from_ref(next_object));
+                    out.push(Block::Div(tag_attr(name, parameters), blocks));
+                }
+            }
+            NorgAST::VerbatimRangedTag {
+                name,
+                parameters,
+                content,
+            } => {
+                let content = content.trim_end_matches('\n').to_string();
+                match name
+                    .iter()
+                    .map(String::as_str)
+                    .collect::<Vec<_>>()
+                    .as_slice()
+                {
+                    ["code"] => out.push(Block::CodeBlock(
+                        attr(parameters.iter().take(1).cloned().collect(), Vec::new()),
+                        content,
+                    )),
+                    ["math"] => out.push(Block::Para(vec![Inline::Math(
+                        MathType::DisplayMath,
+                        content.trim().to_string(),
+                    )])),
+                    ["document", "meta"] | ["comment"] => (),
+                    _ => out.push(Block::Div(
+                        tag_attr(name, parameters),
+                        vec![Block::CodeBlock(attr(Vec::new(), Vec::new()), content)],
+                    )),
+                }
+            }
+            NorgAST::RangedTag {
+                name,
+                parameters,
+                content,
+            } => {
+                if name != &["comment"] {
+                    let blocks = self.blocks(content);
+                    out.push(Block::Div(tag_attr(name, parameters), blocks));
+                }
+            }
+            NorgAST::InfirmTag { name, parameters } => {
+                match (name.as_slice(), parameters.first()) {
+                    ([image], Some(source)) if image == "image" => {
+                        out.push(Block::Para(vec![Inline::Image(
+                            attr(Vec::new(), Vec::new()),
+                            Vec::new(),
+                            (source.clone(), String::new()),
+                        )]))
+                    }
+                    _ => out.push(Block::Div(tag_attr(name, parameters), Vec::new())),
+                }
+            }
+            NorgAST::DelimitingModifier(DelimitingModifier::HorizontalRule) => {
+                out.push(Block::HorizontalRule)
+            }
+            NorgAST::DelimitingModifier(_) => (),
+            NorgAST::NestableDetachedModifier { .. }
+            | NorgAST::RangeableDetachedModifier { .. } => {
+                self.push_group(out, std::slice::from_ref(node))
+            }
+        }
+    }
+}
+
+/// Returns `true` if `node` continues the list, quote, definition list or table started by
+/// `first`.
+pub(crate) fn same_group(first: &NorgAST, node: &NorgAST) -> bool {
+    match (first, node) {
+        (
+            NorgAST::NestableDetachedModifier {
+                modifier_type: a, ..
+            },
+            NorgAST::NestableDetachedModifier {
+                modifier_type: b, ..
+            },
+        ) => a == b,
+        (
+            NorgAST::RangeableDetachedModifier {
+                modifier_type: a, ..
+            },
+            NorgAST::RangeableDetachedModifier {
+                modifier_type: b, ..
+            },
+        ) => a == b && *a != RangeableDetachedModifier::Footnote,
+        _ => false,
+    }
+}
+
+/// Converts a document into a Pandoc document.
+///
+/// Metadata from a top level `@document.meta` tag becomes the metadata of the Pandoc document.
+/// Headings get the same identifiers as in [`crate::outline::outline`].
+///
+/// # Arguments
+///
+/// * `ast` - The document, as returned by [`crate::parse_tree`].
+///
+/// # Returns
+///
+/// * `Pandoc` that can be serialized to JSON and read with `pandoc -f json`.
+pub fn to_pandoc(ast: &[NorgAST]) -> Pandoc {
+    let mut references = References::default();
+    ast.iter().for_each(|node| references.visit_node(node));
+
+    let meta = ast
+        .iter()
+        .find_map(|node| match node {
+            NorgAST::VerbatimRangedTag { name, content, .. } if name == &["document", "meta"] => {
+                match parse_metadata(content) {
+                    Ok(NorgMeta::Object(entries)) => Some(meta_map(&entries)),
+                    _ => None,
+                }
+            }
+            _ => None,
+        })
+        .unwrap_or_default();
+
+    let mut exporter = Exporter {
+        references,
+        slugs: Slugs::default(),
+        notes: Vec::new(),
+    };
+
+    Pandoc {
+        api_version: API_VERSION,
+        meta,
+        blocks: exporter.blocks(ast),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use insta::assert_snapshot;
+    use serde_json::json;
+
+    use crate::{pandoc::to_pandoc, parse_tree};
+
+    fn export(source: &str) -> serde_json::Value {
+        serde_json::to_value(to_pandoc(&parse_tree(source).unwrap())).unwrap()
+    }
+
+    #[test]
+    fn document() {
+        let source = "@document.meta
+title: Notes
+draft: true
+@end
+
+* (x) Introduction
+  Some *bold*, /italic/ and `code`, see {* Details}[details].
+  - ( ) first
+  -- nested
+  - (x) second
+  ~ one
+  > quoted
+** Details
+   $ Term
+   The definition
+   @code rust
+   fn main() {}
+   @end
+   @math
+   e^{i\\pi} + 1 = 0
+   @end
+   : A1
+   a
+   : B1
+   b
+   : A2
+   c
+   +label value
+   .toc
+___
+";
+        assert_snapshot!(serde_json::to_string_pretty(&export(source)).unwrap());
+    }
+
+    #[test]
+    fn footnotes_and_links() {
+        let json = export(
+            "Text{^ 1} and [neorg] and {https://neorg.org}.\n^ 1\nThe note\n\n[neorg]{https://github.com/nvim-neorg}\n",
+        );
+
+        assert_eq!(json["blocks"].as_array().unwrap().len(), 2);
+        assert_eq!(
+            json["blocks"][0]["c"][1],
+            json!({"t": "Note", "c": [{"t": "Para", "c": [
+                {"t": "Str", "c": "The"}, {"t": "Space"}, {"t": "Str", "c": "note"}
+            ]}]})
+        );
+        assert_eq!(
+            json["blocks"][0]["c"][5],
+            json!({"t": "Link", "c": [
+                ["", [], []],
+                [{"t": "Str", "c": "neorg"}],
+                ["https://github.com/nvim-neorg", ""]
+            ]})
+        );
+        assert_eq!(json["pandoc-api-version"], json!([1, 23, 1]));
+    }
+
+    #[test]
+    fn math() {
+        let json = export("text $x^2$\n");
+
+        assert_eq!(
+            json["blocks"][0]["c"][2],
+            json!({"t": "Math", "c": [{"t": "InlineMath"}, "x^2"]})
+        );
+    }
+}
@@ -0,0 +1,151 @@
+//! Parallel parsing of independent top-level sections.
+//!
+//! Stages 1 and 2 run serially, after which the list of blocks is split at boundaries that
+//! stages 3 and 4 can never look across: before a level one heading (including any carryover
+//! tags attached to it) and after a strong delimiting modifier. The resulting chunks are parsed
+//! on rayon's thread pool and stitched back together in order.
+
+use chumsky::Parser as _;
+use rayon::prelude::*;
+
+use crate::{
+    error::NorgParseError, stage_1, stage_2, stage_2::NorgBlock, stage_3, stage_4, NorgAST,
+};
+
+/// Returns the indices at which the given blocks may be split into independently parseable
+/// chunks. The first index is always `0`.
+fn chunk_boundaries(blocks: &[NorgBlock]) -> Vec<usize> {
+    let mut boundaries = vec![0];
+    let mut depth = 0usize;
+    let mut carryover_start = None;
+
+    for (i, block) in blocks.iter().enumerate() {
+        match block {
+            NorgBlock::CarryoverTag { .. } => {
+                carryover_start.get_or_insert(i);
+                continue;
+            }
+            NorgBlock::Heading { level: 1, .. } if depth == 0 => {
+                let start = carryover_start.unwrap_or(i);
+                if start > *boundaries.last().unwrap() {
+                    boundaries.push(start);
+                }
+            }
+            NorgBlock::DelimitingModifier('=') if depth == 0 && carryover_start.is_none() => {
+                boundaries.push(i + 1);
+            }
+            NorgBlock::RangedTag { .. }
+            | NorgBlock::RangeableDetachedModifier { ranged: true, .. } => depth += 1,
+            NorgBlock::RangedTagEnd(_) | NorgBlock::RangeableDetachedModifierClose(_) => {
+                depth = depth.saturating_sub(1)
+            }
+            _ => {}
+        }
+
+        carryover_start = None;
+    }
+
+    boundaries.dedup();
+    boundaries.retain(|&boundary| boundary < blocks.len());
+    boundaries
+}
+
+/// Parses the given input into a tree, running stages 3 and 4 for independent top-level
+/// sections in parallel.
+///
+/// The result is identical to that of [`crate::parse_tree`].
+///
+/// # Arguments
+///
+/// * `input` - A string slice that holds the input to be parsed.
+///
+/// # Returns
+///
+/// * `Ok(Vec<NorgAST>)` if parsing is successful.
+/// * `Err(NorgParseError)` if any stage of parsing fails.
+pub fn parse_tree_parallel(input: &str) -> Result<Vec<NorgAST>, NorgParseError> {
+    let blocks = stage_2().parse(stage_1().parse(input)?)?;
+
+    let boundaries = chunk_boundaries(&blocks);
+    let ends = boundaries.iter().skip(1).copied().chain([blocks.len()]);
+    let chunks: Vec<_> = boundaries.iter().copied().zip(ends).collect();
+
+    let results: Vec<_> = chunks
+        .par_iter()
+        .map(|&(start, end)| {
+            stage_3()
+                .then_ignore(chumsky::primitive::end())
+                .parse(blocks[start..end].to_vec())
+                .map(stage_4)
+        })
+        .collect();
+
+    let mut ast = vec![];
+    for (&(start, _), result) in chunks.iter().zip(results) {
+        match result {
+            Ok(nodes) => ast.extend(nodes),
+            // The serial parser stops at the first block it cannot make sense of. Reproduce
+            // that by parsing everything from this chunk onwards in one go.
+            Err(_) => {
+                match stage_3().parse(blocks[start..].to_vec()) {
+                    Ok(flat) => ast.extend(stage_4(flat)),
+                    Err(err) if ast.is_empty() => return Err(err.into()),
+                    Err(_) => {}
+                }
+                break;
+            }
+        }
+    }
+
+    Ok(ast)
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::{collection::vec, prop_oneof, proptest, strategy::Strategy as _};
+
+    use crate::{parallel::parse_tree_parallel, parse_tree};
+
+    #[test]
+    fn parallel_matches_serial() {
+        [
+            "* One\n  content\n* Two\n** Nested\n   text\n",
+            "#id 123\n* tree\n** nested\n#id there\n* another\n",
+            "* tree\n#id 123\n+attribute\n* another\n  text\n",
+            "* One\n  ===\nafter\n* Two\n",
+            "- list\n-- nested\n* Heading\n- list again\n",
+            "|example\n* Hello world!\n|end\n* After\n",
+            "$$ Term\n* inside\n$$\n* outside\n",
+            "* One\n$$ Term\ntext\n^^\n* Two\n",
+            "- - invalid\n* Two\n",
+        ]
+        .into_iter()
+        .for_each(|input| {
+            assert_eq!(
+                parse_tree_parallel(input).ok(),
+                parse_tree(input).ok(),
+                "input: {input:?}"
+            );
+        });
+    }
+
+    proptest! {
+        #[test]
+        fn parallel_matches_serial_proptests(lines in vec(prop_oneof![
+            "\\*{1,3} [a-z ]{1,10}",
+            "-{1,3} [a-z ]{1,10}",
+            "#id [a-z]{1,5}",
+            "(===|---|___)",
+            "\\|example",
+            "\\|end",
+            "\\$\\$ term",
+            "\\$\\$",
+            "[a-z ]{1,20}",
+            "",
+        ].prop_map(String::from), 1..30)) {
+            let input = lines.join("\n") + "\n";
+
+            assert_eq!(parse_tree_parallel(&input).ok(), parse_tree(&input).ok());
+        }
+    }
+}
@@ -0,0 +1,334 @@
+//! Renders inline content and whole documents as plain text, e.g. for search indexing, word
+//! counts or heading slugs.
+
+use crate::{LinkTarget, NorgAST, ParagraphSegment, ParagraphSegmentToken};
+
+/// What to render for a link.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum LinkText {
+    /// The description of the link, falling back to its target if there is none.
+    #[default]
+    Description,
+    /// Only the target of the link.
+    Target,
+    /// The description followed by the target in parentheses, e.g. `Neorg (https://...)`.
+    Both,
+}
+
+/// Options for the plain text renderer.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PlainTextOptions {
+    pub links: LinkText,
+    /// Keep the content of null modifiers (`%comment%`) and of objects marked with a
+    /// `#comment` tag.
+    pub include_comments: bool,
+    /// Keep the content of verbatim ranged tags (`@code` and friends).
+    pub include_verbatim: bool,
+}
+
+impl Default for PlainTextOptions {
+    fn default() -> Self {
+        Self {
+            links: LinkText::Description,
+            include_comments: false,
+            include_verbatim: true,
+        }
+    }
+}
+
+fn push_token(out: &mut String, token: &ParagraphSegmentToken) {
+    match token {
+        ParagraphSegmentToken::Text(text) => out.push_str(text),
+        ParagraphSegmentToken::Whitespace => out.push(' '),
+        ParagraphSegmentToken::Special(c) | ParagraphSegmentToken::Escape(c) => out.push(*c),
+    }
+}
+
+fn push_link_target(out: &mut String, target: &LinkTarget, options: &PlainTextOptions) {
+    match target {
+        LinkTarget::Heading { title: content, .. }
+        | LinkTarget::Footnote(content)
+        | LinkTarget::Definition(content)
+        | LinkTarget::Generic(content)
+        | LinkTarget::Wiki(content)
+        | LinkTarget::Extendable(content) => push_segments(out, content, options),
+        LinkTarget::Path(text) | LinkTarget::Url(text) | LinkTarget::Timestamp(text) => {
+            out.push_str(text)
+        }
+    }
+}
+
+/// Renders the target of a link, which is the file path if there is no target in the file.
+fn link_target_text(
+    filepath: &Option<String>,
+    targets: &[LinkTarget],
+    options: &PlainTextOptions,
+) -> String {
+    let mut out = String::new();
+    match targets.first() {
+        Some(target) => push_link_target(&mut out, target, options),
+        None => out.push_str(filepath.as_deref().unwrap_or_default()),
+    }
+    out
+}
+
+/// Renders either a description, a target or both, as configured.
+fn push_described(
+    out: &mut String,
+    description: Option<String>,
+    target: String,
+    options: &PlainTextOptions,
+) {
+    match (description, &options.links) {
+        (Some(description), LinkText::Description) => out.push_str(&description),
+        (Some(description), LinkText::Both) if !target.is_empty() => {
+            out.push_str(&description);
+            out.push_str(" (");
+            out.push_str(&target);
+            out.push(')');
+        }
+        (Some(description), LinkText::Both) => out.push_str(&description),
+        (_, _) => out.push_str(&target),
+    }
+}
+
+fn push_segment(out: &mut String, segment: &ParagraphSegment, options: &PlainTextOptions) {
+    match segment {
+        ParagraphSegment::Token(token) => push_token(out, token),
+        ParagraphSegment::AttachedModifier {
+            modifier_type: '%', ..
+        } if !options.include_comments => {}
+        ParagraphSegment::AttachedModifier { content, .. }
+        | ParagraphSegment::InlineLinkTarget(content) => push_segments(out, content, options),
+        ParagraphSegment::Link {
+            filepath,
+            targets,
+            description,
+        } => push_described(
+            out,
+            description
+                .as_ref()
+                .map(|description| inline_to_plain_text(description, options)),
+            link_target_text(filepath, targets, options),
+            options,
+        ),
+        ParagraphSegment::Anchor {
+            content,
+            description,
+        } => push_described(
+            out,
+            description
+                .as_ref()
+                .map(|description| inline_to_plain_text(description, options)),
+            inline_to_plain_text(content, options),
+            options,
+        ),
+        ParagraphSegment::AnchorDefinition { content, target } => {
+            let mut target_text = String::new();
+            if let ParagraphSegment::Link {
+                filepath, targets, ..
+            } = target.as_ref()
+            {
+                target_text = link_target_text(filepath, targets, options);
+            }
+
+            push_described(
+                out,
+                Some(inline_to_plain_text(content, options)),
+                target_text,
+                options,
+            )
+        }
+        ParagraphSegment::InlineVerbatim(tokens) | ParagraphSegment::InlineMath(tokens) => tokens
+            .iter()
+            .for_each(|token| out.push_str(&token.to_string())),
+        // The remaining variants only exist while parsing and never make it into the final
+        // tree, render them as the text they were created from.
+        ParagraphSegment::AttachedModifierOpener((left, modifiers, right))
+        | ParagraphSegment::AttachedModifierOpenerFail((left, modifiers, right)) => {
+            if let Some(left) = left {
+                push_token(out, left);
+            }
+            out.extend(modifiers);
+            push_token(out, right);
+        }
+        ParagraphSegment::AttachedModifierCloserCandidate((left, modifiers, right)) => {
+            push_segment(out, left, options);
+            out.extend(modifiers);
+            if let Some(right) = right {
+                push_segment(out, right, options);
+            }
+        }
+        ParagraphSegment::AttachedModifierCloser(c) => out.push(*c),
+        ParagraphSegment::AttachedModifierCandidate {
+            modifier_type,
+            content,
+            closer,
+        } => {
+            out.push(*modifier_type);
+            push_segments(out, content, options);
+            if let Some(closer) = closer {
+                push_segment(out, closer, options);
+            }
+        }
+    }
+}
+
+fn push_segments(out: &mut String, segments: &[ParagraphSegment], options: &PlainTextOptions) {
+    segments
+        .iter()
+        .for_each(|segment| push_segment(out, segment, options));
+}
+
+/// Renders inline content, e.g. a paragraph or a heading title, as plain text.
+pub fn inline_to_plain_text(segments: &[ParagraphSegment], options: &PlainTextOptions) -> String {
+    let mut out = String::new();
+    push_segments(&mut out, segments, options);
+    out
+}
+
+fn push_line(lines: &mut Vec<String>, line: String) {
+    let line = line.trim();
+    if !line.is_empty() {
+        lines.push(line.to_string());
+    }
+}
+
+fn push_node(lines: &mut Vec<String>, node: &NorgAST, options: &PlainTextOptions) {
+    match node {
+        NorgAST::Paragraph(segments) => push_line(lines, inline_to_plain_text(segments, options)),
+        NorgAST::Heading { title, content, .. }
+        | NorgAST::RangeableDetachedModifier { title, content, .. } => {
+            push_line(lines, inline_to_plain_text(title, options));
+            push_nodes(lines, content, options);
+        }
+        NorgAST::NestableDetachedModifier { text, content, .. } => {
+            push_node(lines, text, options);
+            push_nodes(lines, content, options);
+        }
+        NorgAST::CarryoverTag {
+            name, next_object, ..
+        } => {
+            if options.include_comments || name.as_slice() != ["comment"] {
+                push_node(lines, next_object, options);
+            }
+        }
+        NorgAST::VerbatimRangedTag { name, content, .. } => {
+            if options.include_verbatim && (options.include_comments || name != &["comment"]) {
+                lines.extend(content.lines().map(str::to_string));
+            }
+        }
+        NorgAST::RangedTag { name, content, .. } => {
+            if options.include_comments || name != &["comment"] {
+                push_nodes(lines, content, options);
+            }
+        }
+        NorgAST::InfirmTag { .. } | NorgAST::DelimitingModifier(_) => {}
+    }
+}
+
+fn push_nodes(lines: &mut Vec<String>, nodes: &[NorgAST], options: &PlainTextOptions) {
+    nodes
+        .iter()
+        .for_each(|node| push_node(lines, node, options));
+}
+
+/// Renders a whole document as plain text. Every paragraph, title and list item ends up on
+/// its own line, verbatim content keeps its original lines.
+pub fn to_plain_text(ast: &[NorgAST], options: &PlainTextOptions) -> String {
+    let mut lines = vec![];
+    push_nodes(&mut lines, ast, options);
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        parse_tree,
+        plain_text::{to_plain_text, LinkText, PlainTextOptions},
+    };
+
+    fn render(input: &str, options: &PlainTextOptions) -> String {
+        to_plain_text(&parse_tree(&(input.to_string() + "\n")).unwrap(), options)
+    }
+
+    #[test]
+    fn inline_content() {
+        let options = PlainTextOptions::default();
+
+        assert_eq!(
+            render("this *is* /some/ `inline *verbatim*` text", &options),
+            "this is some inline *verbatim* text"
+        );
+        assert_eq!(render("escaped \\*stars\\*", &options), "escaped *stars*");
+        assert_eq!(
+            render("with a %hidden% comment", &options),
+            "with a  comment"
+        );
+        assert_eq!(
+            render("an <inline target> and [anchor][described]", &options),
+            "an inline target and described"
+        );
+    }
+
+    #[test]
+    fn links() {
+        let input = "{https://github.com/nvim-neorg/neorg}[Neorg], {* Heading} and {:file:}";
+
+        assert_eq!(
+            render(input, &PlainTextOptions::default()),
+            "Neorg, Heading and file"
+        );
+        assert_eq!(
+            render(
+                input,
+                &PlainTextOptions {
+                    links: LinkText::Target,
+                    ..Default::default()
+                }
+            ),
+            "https://github.com/nvim-neorg/neorg, Heading and file"
+        );
+        assert_eq!(
+            render(
+                input,
+                &PlainTextOptions {
+                    links: LinkText::Both,
+                    ..Default::default()
+                }
+            ),
+            "Neorg (https://github.com/nvim-neorg/neorg), Heading and file"
+        );
+    }
+
+    #[test]
+    fn documents() {
+        let input = "* Heading
+                       Some content.
+                       - (x) a list
+                       -- nested
+                     #comment
+                     hidden paragraph
+                     @code lua
+                     print(\"hi\")
+                     @end
+                     $ Term
+                       Definition";
+
+        assert_eq!(
+            render(input, &PlainTextOptions::default()),
+            "Heading\nSome content.\na list\nnested\nprint(\"hi\")\nTerm\nDefinition"
+        );
+        assert_eq!(
+            render(
+                input,
+                &PlainTextOptions {
+                    include_comments: true,
+                    include_verbatim: false,
+                    ..Default::default()
+                }
+            ),
+            "Heading\nSome content.\na list\nnested\nhidden paragraph\nTerm\nDefinition"
+        );
+    }
+}
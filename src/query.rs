@@ -0,0 +1,590 @@
+//! A small selector language for finding nodes in a [`NorgAST`].
+//!
+//! Selectors look a lot like CSS selectors:
+//!
+//! ```text
+//! heading[level=2] > list-item:todo(undone)
+//! tag[name="code"][param="rust"]
+//! heading[title="Work"] list-item:todo(urgent), heading[title="Work"] heading:todo(urgent)
+//! ```
+//!
+//! A selector is a list of compound selectors joined by combinators. A space matches any
+//! descendant, `>` only direct children. Several selectors may be separated by `,`, in which
+//! case a node matches if any of them match.
+//!
+//! A compound selector starts with an optional node type, followed by any number of attribute
+//! filters and pseudo classes.
+//!
+//! Node types: `*`, `paragraph`, `heading`, `list-item` (ordered and unordered),
+//! `unordered-item`, `ordered-item`, `quote`, `definition`, `footnote`, `table`, `tag` (any tag),
+//! `ranged-tag`, `verbatim-tag`, `infirm-tag`, `carryover-tag` and `delimiter`.
+//!
+//! Attributes: `level`, `name` (the tag name with its parts joined by `.`), `param` (matches if
+//! any parameter matches) and `title`. Use `[attr=value]` for equality and `[attr*=value]` to
+//! match a substring. Values may be quoted.
+//!
+//! Pseudo classes: `:todo` (any todo status), `:todo(status)` with one of `undone`, `done`,
+//! `needs-clarification`, `paused`, `urgent`, `recurring`, `pending` or `canceled`, and
+//! `:priority(value)`.
+//!
+//! The children of a node are the `content` of headings, nestable and rangeable detached
+//! modifiers and ranged tags, and the `next_object` of a carryover tag.
+
+use chumsky::prelude::*;
+use itertools::Itertools;
+
+use crate::{
+    error::NorgParseError,
+    plain_text::{inline_to_plain_text, PlainTextOptions},
+    DetachedModifierExtension, NestableDetachedModifier, NorgAST, RangeableDetachedModifier,
+    TodoStatus,
+};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum NodeType {
+    Any,
+    Paragraph,
+    Heading,
+    ListItem,
+    UnorderedItem,
+    OrderedItem,
+    Quote,
+    Definition,
+    Footnote,
+    Table,
+    Tag,
+    RangedTag,
+    VerbatimTag,
+    InfirmTag,
+    CarryoverTag,
+    Delimiter,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum AttributeOperator {
+    Equals,
+    Contains,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Attribute {
+    name: String,
+    operator: AttributeOperator,
+    value: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Pseudo {
+    /// `None` matches any todo status.
+    Todo(Option<String>),
+    Priority(String),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Compound {
+    node_type: NodeType,
+    attributes: Vec<Attribute>,
+    pseudos: Vec<Pseudo>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Combinator {
+    Descendant,
+    Child,
+}
+
+/// A single match of a [`Selector`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct QueryMatch<'a> {
+    /// Indices leading from the root list of nodes to the matched node, see the module
+    /// documentation for what counts as a child.
+    pub path: Vec<usize>,
+    pub node: &'a NorgAST,
+}
+
+/// A parsed selector, see the [module documentation](self) for the syntax.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Selector {
+    /// Alternatives separated by `,`. Each alternative is a list of compound selectors
+    /// together with the combinator that links them to the previous one.
+    alternatives: Vec<Vec<(Combinator, Compound)>>,
+}
+
+fn selector_parser() -> impl Parser<char, Selector, Error = Simple<char>> {
+    let ws = filter(|c: &char| c.is_whitespace()).repeated();
+
+    let word = filter(|c: &char| c.is_alphanumeric() || *c == '-' || *c == '_' || *c == '.')
+        .repeated()
+        .at_least(1)
+        .collect::<String>();
+
+    let quoted = just('"')
+        .ignore_then(filter(|c: &char| *c != '"').repeated())
+        .then_ignore(just('"'))
+        .collect::<String>();
+
+    let value = quoted.or(filter(|c: &char| *c != ']' && *c != ')')
+        .repeated()
+        .at_least(1)
+        .collect::<String>()
+        .map(|value| value.trim().to_string()));
+
+    let node_type = just('*').to(NodeType::Any).or(word.try_map(|name, span| {
+        Ok(match name.as_str() {
+            "paragraph" => NodeType::Paragraph,
+            "heading" => NodeType::Heading,
+            "list-item" => NodeType::ListItem,
+            "unordered-item" => NodeType::UnorderedItem,
+            "ordered-item" => NodeType::OrderedItem,
+            "quote" => NodeType::Quote,
+            "definition" => NodeType::Definition,
+            "footnote" => NodeType::Footnote,
+            "table" => NodeType::Table,
+            "tag" => NodeType::Tag,
+            "ranged-tag" => NodeType::RangedTag,
+            "verbatim-tag" => NodeType::VerbatimTag,
+            "infirm-tag" => NodeType::InfirmTag,
+            "carryover-tag" => NodeType::CarryoverTag,
+            "delimiter" => NodeType::Delimiter,
+            _ => return Err(Simple::custom(span, format!("unknown node type `{name}`"))),
+        })
+    }));
+
+    let attribute = word
+        .then(
+            just("*=")
+                .to(AttributeOperator::Contains)
+                .or(just("=").to(AttributeOperator::Equals)),
+        )
+        .then(value)
+        .delimited_by(just('['), just(']'))
+        .map(|((name, operator), value)| Attribute {
+            name,
+            operator,
+            value,
+        });
+
+    let pseudo = just(':')
+        .ignore_then(word)
+        .then(value.delimited_by(just('('), just(')')).or_not())
+        .try_map(|(name, argument), span| match (name.as_str(), argument) {
+            ("todo", argument) => Ok(Pseudo::Todo(argument)),
+            ("priority", Some(argument)) => Ok(Pseudo::Priority(argument)),
+            (name, _) => Err(Simple::custom(
+                span,
+                format!("unknown or incomplete pseudo class `:{name}`"),
+            )),
+        });
+
+    enum Filter {
+        Attribute(Attribute),
+        Pseudo(Pseudo),
+    }
+
+    let filters = attribute
+        .map(Filter::Attribute)
+        .or(pseudo.map(Filter::Pseudo))
+        .repeated();
+
+    let compound = node_type
+        .then(filters.clone())
+        .or(filters.at_least(1).map(|filters| (NodeType::Any, filters)))
+        .map(|(node_type, filters)| {
+            let (attributes, pseudos) = filters.into_iter().partition_map(|filter| match filter {
+                Filter::Attribute(attribute) => itertools::Either::Left(attribute),
+                Filter::Pseudo(pseudo) => itertools::Either::Right(pseudo),
+            });
+
+            Compound {
+                node_type,
+                attributes,
+                pseudos,
+            }
+        });
+
+    let combinator = ws
+        .ignore_then(just('>'))
+        .then_ignore(ws)
+        .to(Combinator::Child)
+        .or(filter(|c: &char| c.is_whitespace())
+            .repeated()
+            .at_least(1)
+            .to(Combinator::Descendant));
+
+    let complex = compound
+        .clone()
+        .then(combinator.then(compound).repeated())
+        .map(|(first, rest)| {
+            std::iter::once((Combinator::Descendant, first))
+                .chain(rest)
+                .collect::<Vec<_>>()
+        });
+
+    complex
+        .separated_by(just(',').padded())
+        .at_least(1)
+        .padded()
+        .then_ignore(end())
+        .map(|alternatives| Selector { alternatives })
+}
+
+fn matches_value(operator: &AttributeOperator, actual: &str, expected: &str) -> bool {
+    match operator {
+        AttributeOperator::Equals => actual == expected,
+        AttributeOperator::Contains => actual.contains(expected),
+    }
+}
+
+pub(crate) fn todo_status_name(status: &TodoStatus) -> &'static str {
+    match status {
+        TodoStatus::Undone => "undone",
+        TodoStatus::Done => "done",
+        TodoStatus::NeedsClarification => "needs-clarification",
+        TodoStatus::Paused => "paused",
+        TodoStatus::Urgent => "urgent",
+        TodoStatus::Recurring(_) => "recurring",
+        TodoStatus::Pending => "pending",
+        TodoStatus::Canceled => "canceled",
+    }
+}
+
+impl Compound {
+    fn matches(&self, node: &NorgAST) -> bool {
+        let type_matches = matches!(
+            (&self.node_type, node),
+            (NodeType::Any, _)
+                | (NodeType::Paragraph, NorgAST::Paragraph(_))
+                | (NodeType::Heading, NorgAST::Heading { .. })
+                | (
+                    NodeType::ListItem,
+                    NorgAST::NestableDetachedModifier {
+                        modifier_type: NestableDetachedModifier::UnorderedList
+                            | NestableDetachedModifier::OrderedList,
+                        ..
+                    },
+                )
+                | (
+                    NodeType::UnorderedItem,
+                    NorgAST::NestableDetachedModifier {
+                        modifier_type: NestableDetachedModifier::UnorderedList,
+                        ..
+                    },
+                )
+                | (
+                    NodeType::OrderedItem,
+                    NorgAST::NestableDetachedModifier {
+                        modifier_type: NestableDetachedModifier::OrderedList,
+                        ..
+                    },
+                )
+                | (
+                    NodeType::Quote,
+                    NorgAST::NestableDetachedModifier {
+                        modifier_type: NestableDetachedModifier::Quote,
+                        ..
+                    },
+                )
+                | (
+                    NodeType::Definition,
+                    NorgAST::RangeableDetachedModifier {
+                        modifier_type: RangeableDetachedModifier::Definition,
+                        ..
+                    },
+                )
+                | (
+                    NodeType::Footnote,
+                    NorgAST::RangeableDetachedModifier {
+                        modifier_type: RangeableDetachedModifier::Footnote,
+                        ..
+                    },
+                )
+                | (
+                    NodeType::Table,
+                    NorgAST::RangeableDetachedModifier {
+                        modifier_type: RangeableDetachedModifier::Table,
+                        ..
+                    },
+                )
+                | (
+                    NodeType::Tag,
+                    NorgAST::RangedTag { .. }
+                        | NorgAST::VerbatimRangedTag { .. }
+                        | NorgAST::InfirmTag { .. }
+                        | NorgAST::CarryoverTag { .. },
+                )
+                | (NodeType::RangedTag, NorgAST::RangedTag { .. })
+                | (NodeType::VerbatimTag, NorgAST::VerbatimRangedTag { .. })
+                | (NodeType::InfirmTag, NorgAST::InfirmTag { .. })
+                | (NodeType::CarryoverTag, NorgAST::CarryoverTag { .. })
+                | (NodeType::Delimiter, NorgAST::DelimitingModifier(_))
+        );
+
+        type_matches
+            && self
+                .attributes
+                .iter()
+                .all(|attribute| attribute.matches(node))
+            && self.pseudos.iter().all(|pseudo| pseudo.matches(node))
+    }
+}
+
+impl Attribute {
+    fn matches(&self, node: &NorgAST) -> bool {
+        match self.name.as_str() {
+            "level" => match node {
+                NorgAST::Heading { level, .. }
+                | NorgAST::NestableDetachedModifier { level, .. } => {
+                    matches_value(&self.operator, &level.to_string(), &self.value)
+                }
+                _ => false,
+            },
+            "name" => match node {
+                NorgAST::RangedTag { name, .. }
+                | NorgAST::VerbatimRangedTag { name, .. }
+                | NorgAST::InfirmTag { name, .. }
+                | NorgAST::CarryoverTag { name, .. } => {
+                    matches_value(&self.operator, &name.join("."), &self.value)
+                }
+                _ => false,
+            },
+            "param" => match node {
+                NorgAST::RangedTag { parameters, .. }
+                | NorgAST::VerbatimRangedTag { parameters, .. }
+                | NorgAST::InfirmTag { parameters, .. }
+                | NorgAST::CarryoverTag { parameters, .. } => parameters
+                    .iter()
+                    .any(|parameter| matches_value(&self.operator, parameter, &self.value)),
+                _ => false,
+            },
+            "title" => match node {
+                NorgAST::Heading { title, .. }
+                | NorgAST::RangeableDetachedModifier { title, .. } => matches_value(
+                    &self.operator,
+                    &inline_to_plain_text(title, &PlainTextOptions::default()),
+                    &self.value,
+                ),
+                NorgAST::NestableDetachedModifier { text, .. } => match text.as_ref() {
+                    NorgAST::Paragraph(segments) => matches_value(
+                        &self.operator,
+                        &inline_to_plain_text(segments, &PlainTextOptions::default()),
+                        &self.value,
+                    ),
+                    _ => false,
+                },
+                _ => false,
+            },
+            _ => false,
+        }
+    }
+}
+
+impl Pseudo {
+    fn matches(&self, node: &NorgAST) -> bool {
+        let extensions = match node {
+            NorgAST::Heading { extensions, .. }
+            | NorgAST::NestableDetachedModifier { extensions, .. }
+            | NorgAST::RangeableDetachedModifier { extensions, .. } => extensions,
+            _ => return false,
+        };
+
+        extensions.iter().any(|extension| match (self, extension) {
+            (Pseudo::Todo(None), DetachedModifierExtension::Todo(_)) => true,
+            (Pseudo::Todo(Some(expected)), DetachedModifierExtension::Todo(status)) => {
+                todo_status_name(status) == expected
+            }
+            (Pseudo::Priority(expected), DetachedModifierExtension::Priority(priority)) => {
+                priority == expected
+            }
+            _ => false,
+        })
+    }
+}
+
+/// Returns the children of the given node.
+pub(crate) fn children(node: &NorgAST) -> &[NorgAST] {
+    match node {
+        NorgAST::Heading { content, .. }
+        | NorgAST::NestableDetachedModifier { content, .. }
+        | NorgAST::RangeableDetachedModifier { content, .. }
+        | NorgAST::RangedTag { content, .. } => content,
+        NorgAST::CarryoverTag { next_object, .. } => std::slice::from_ref(next_object.as_ref()),
+        _ => &[],
+    }
+}
+
+impl Selector {
+    /// Parses a selector.
+    pub fn parse(input: &str) -> Result<Self, NorgParseError> {
+        selector_parser()
+            .parse(input)
+            .map_err(NorgParseError::Query)
+    }
+
+    /// Returns `true` if the node, reached through the given ancestors (outermost first),
+    /// matches the selector.
+    pub fn matches(&self, node: &NorgAST, ancestors: &[&NorgAST]) -> bool {
+        self.alternatives
+            .iter()
+            .any(|parts| Self::matches_parts(parts, node, ancestors))
+    }
+
+    fn matches_parts(
+        parts: &[(Combinator, Compound)],
+        node: &NorgAST,
+        ancestors: &[&NorgAST],
+    ) -> bool {
+        let Some(((combinator, compound), rest)) = parts.split_last() else {
+            return true;
+        };
+
+        if !compound.matches(node) {
+            return false;
+        }
+
+        if rest.is_empty() {
+            return true;
+        }
+
+        match combinator {
+            Combinator::Child => ancestors
+                .split_last()
+                .is_some_and(|(parent, ancestors)| Self::matches_parts(rest, parent, ancestors)),
+            Combinator::Descendant => (0..ancestors.len())
+                .rev()
+                .any(|i| Self::matches_parts(rest, ancestors[i], &ancestors[..i])),
+        }
+    }
+
+    /// Finds all nodes matching this selector, in document order.
+    pub fn query<'a>(&self, ast: &'a [NorgAST]) -> Vec<QueryMatch<'a>> {
+        fn walk<'a>(
+            selector: &Selector,
+            nodes: &'a [NorgAST],
+            path: &mut Vec<usize>,
+            ancestors: &mut Vec<&'a NorgAST>,
+            matches: &mut Vec<QueryMatch<'a>>,
+        ) {
+            for (i, node) in nodes.iter().enumerate() {
+                path.push(i);
+
+                if selector.matches(node, ancestors) {
+                    matches.push(QueryMatch {
+                        path: path.clone(),
+                        node,
+                    });
+                }
+
+                ancestors.push(node);
+                walk(selector, children(node), path, ancestors, matches);
+                ancestors.pop();
+
+                path.pop();
+            }
+        }
+
+        let mut matches = vec![];
+        walk(self, ast, &mut vec![], &mut vec![], &mut matches);
+        matches
+    }
+}
+
+/// Parses the given selector and returns every node in `ast` that matches it.
+///
+/// # Arguments
+///
+/// * `ast` - The tree to search, as returned by [`crate::parse_tree`].
+/// * `selector` - A selector, see the [module documentation](self) for the syntax.
+///
+/// # Returns
+///
+/// * `Ok(Vec<QueryMatch>)` with all matches in document order.
+/// * `Err(NorgParseError)` if the selector is invalid.
+pub fn query<'a>(
+    ast: &'a [NorgAST],
+    selector: &str,
+) -> Result<Vec<QueryMatch<'a>>, NorgParseError> {
+    Ok(Selector::parse(selector)?.query(ast))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{parse_tree, query::query};
+
+    const INPUT: &str = "* Work
+  - ( ) write report
+  - (!) fix the build
+  -- (!) nested urgent
+** Meetings
+   - (x) standup
+   - (!|# A) prepare slides
+* Home
+  - (!) water the plants
+  @code rust
+  fn main() {}
+  @end
+  @code lua
+  print()
+  @end
+#id 123
+** (!) Tagged heading
+";
+
+    fn paths(selector: &str) -> Vec<Vec<usize>> {
+        let ast = parse_tree(INPUT).unwrap();
+        query(&ast, selector)
+            .unwrap()
+            .into_iter()
+            .map(|found| found.path)
+            .collect()
+    }
+
+    #[test]
+    fn query_node_types() {
+        assert_eq!(
+            paths("heading"),
+            [vec![0], vec![0, 2], vec![1], vec![1, 3, 0]]
+        );
+        assert_eq!(paths("heading[level=2]").len(), 2);
+        assert_eq!(paths("list-item").len(), 6);
+        assert_eq!(paths("tag"), [vec![1, 1], vec![1, 2], vec![1, 3]]);
+    }
+
+    #[test]
+    fn query_combinators() {
+        assert_eq!(
+            paths("heading[title=\"Work\"] list-item:todo(urgent)"),
+            [vec![0, 1], vec![0, 1, 0], vec![0, 2, 1]]
+        );
+        assert_eq!(
+            paths("heading[title=Work] > list-item:todo(urgent)"),
+            [vec![0, 1]]
+        );
+        assert_eq!(paths("list-item > list-item"), [vec![0, 1, 0]]);
+        assert_eq!(
+            paths("heading[title=Home] :todo(urgent)"),
+            [vec![1, 0], vec![1, 3, 0]]
+        );
+    }
+
+    #[test]
+    fn query_attributes_and_pseudos() {
+        assert_eq!(paths("tag[name=\"code\"][param=\"rust\"]"), [vec![1, 1]]);
+        assert_eq!(paths("verbatim-tag[param*=u]"), [vec![1, 1], vec![1, 2]]);
+        assert_eq!(paths(":priority(A)"), [vec![0, 2, 1]]);
+        assert_eq!(paths(":todo").len(), 7);
+        assert_eq!(paths("heading[title*=Meet], list-item:todo(done)").len(), 2);
+        assert_eq!(
+            paths("carryover-tag[name=id] > heading:todo(urgent)"),
+            [vec![1, 3, 0]]
+        );
+    }
+
+    #[test]
+    fn query_errors() {
+        let ast = parse_tree(INPUT).unwrap();
+
+        query(&ast, "not-a-node").unwrap_err();
+        query(&ast, "heading:unknown").unwrap_err();
+        query(&ast, "heading[level=1").unwrap_err();
+    }
+}
@@ -0,0 +1,535 @@
+//! A full-text search index over parsed documents.
+//!
+//! Every paragraph, heading title, list item, quote and definition/footnote/table title is
+//! indexed as its own block. Text is taken from [`inline_to_plain_text`], lowercased and split
+//! into words. Queries are a list of words and `"quoted phrases"`, all of which have to occur
+//! in a block for it to match. Results are ranked with BM25.
+//!
+//! Hits point at the block through the same node path that [`crate::query`] uses. Blocks of
+//! documents added with [`SearchIndex::add_source`] also know the range of bytes they were
+//! parsed from, which is found through the [`crate::cst`] like [`crate::json`] does.
+
+use std::{collections::HashMap, ops::Range};
+
+use crate::{
+    cst::{BlockSpans, SyntaxKind},
+    error::NorgParseError,
+    parse_tree,
+    plain_text::{inline_to_plain_text, PlainTextOptions},
+    query::{children, Selector},
+    NorgAST,
+};
+
+const K1: f64 = 1.2;
+const B: f64 = 0.75;
+
+/// A single indexed block.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Block {
+    /// The index of the document the block belongs to, see [`SearchIndex::documents`].
+    document: usize,
+    /// Titles of the headings the block is nested in, outermost first.
+    pub heading_path: Vec<String>,
+    /// Indices leading from the root of the document to the block.
+    pub path: Vec<usize>,
+    /// The range of bytes in the source the block was parsed from: the title line of headings,
+    /// definitions, footnotes and tables, or the whole paragraph or list item. `None` for
+    /// documents added through [`SearchIndex::add_document`], as there is no source.
+    pub span: Option<Range<usize>>,
+    /// The plain text of the block.
+    pub text: String,
+    /// The number of words in the block.
+    len: usize,
+}
+
+/// All positions at which a word occurs in a single block.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Posting {
+    block: usize,
+    positions: Vec<usize>,
+}
+
+/// A single match of a search, as returned by [`SearchIndex::search`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct SearchHit<'a> {
+    /// The name of the file the block was found in.
+    pub file: &'a str,
+    pub block: &'a Block,
+    pub score: f64,
+}
+
+/// An inverted index over any number of documents.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SearchIndex {
+    documents: Vec<(String, Vec<NorgAST>)>,
+    blocks: Vec<Block>,
+    postings: HashMap<String, Vec<Posting>>,
+    total_len: usize,
+}
+
+/// Splits text into lowercase words.
+fn tokenize(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+}
+
+/// Parses a query into a list of phrases, each of which is a list of words. Unquoted words
+/// become phrases of their own.
+fn parse_query(query: &str) -> Vec<Vec<String>> {
+    query
+        .split('"')
+        .enumerate()
+        .flat_map(|(i, part)| {
+            if i % 2 == 1 {
+                vec![tokenize(part).collect()]
+            } else {
+                tokenize(part).map(|word| vec![word]).collect()
+            }
+        })
+        .filter(|phrase: &Vec<String>| !phrase.is_empty())
+        .collect()
+}
+
+/// Returns the text to index for a node, if the node is a block.
+fn block_text(node: &NorgAST) -> Option<String> {
+    let options = PlainTextOptions::default();
+    match node {
+        NorgAST::Paragraph(segments)
+        | NorgAST::Heading {
+            title: segments, ..
+        }
+        | NorgAST::RangeableDetachedModifier {
+            title: segments, ..
+        } => Some(inline_to_plain_text(segments, &options)),
+        NorgAST::NestableDetachedModifier { text, .. } => match text.as_ref() {
+            NorgAST::Paragraph(segments) => Some(inline_to_plain_text(segments, &options)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Returns the range of bytes a node was parsed from, see [`Block::span`]. Takes the node, and
+/// the text of list items, from the spans.
+fn block_span(node: &NorgAST, spans: &mut BlockSpans) -> Range<usize> {
+    let kind = match node {
+        NorgAST::Paragraph(_) => SyntaxKind::Paragraph,
+        NorgAST::Heading { .. } => SyntaxKind::Heading,
+        NorgAST::NestableDetachedModifier { .. } => SyntaxKind::NestableDetachedModifier,
+        NorgAST::RangeableDetachedModifier { .. } => SyntaxKind::RangeableDetachedModifier,
+        NorgAST::CarryoverTag { .. } => SyntaxKind::CarryoverTag,
+        NorgAST::VerbatimRangedTag { .. } => SyntaxKind::VerbatimRangedTag,
+        NorgAST::RangedTag { .. } => SyntaxKind::RangedTag,
+        NorgAST::InfirmTag { .. } => SyntaxKind::InfirmTag,
+        NorgAST::DelimitingModifier(_) => SyntaxKind::DelimitingModifier,
+    };
+
+    let span = spans.take(kind);
+    match node {
+        NorgAST::NestableDetachedModifier { text, .. } => {
+            if let NorgAST::Paragraph(_) = text.as_ref() {
+                spans.take(SyntaxKind::Paragraph);
+            }
+            span
+        }
+        // Only the title is indexed, not the content below it.
+        NorgAST::RangeableDetachedModifier { .. } => {
+            let title = spans.source[span.clone()]
+                .lines()
+                .next()
+                .unwrap_or_default();
+            span.start..span.start + title.trim_end().len()
+        }
+        _ => span,
+    }
+}
+
+impl SearchIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The documents in the index, as pairs of file name and tree.
+    pub fn documents(&self) -> &[(String, Vec<NorgAST>)] {
+        &self.documents
+    }
+
+    /// Adds an already parsed document to the index.
+    pub fn add_document(&mut self, file: impl Into<String>, ast: Vec<NorgAST>) {
+        self.add(file.into(), ast, None);
+    }
+
+    fn add(&mut self, file: String, ast: Vec<NorgAST>, mut spans: Option<BlockSpans>) {
+        let document = self.documents.len();
+        self.walk(document, &ast, &mut vec![], &mut vec![], &mut spans);
+        self.documents.push((file, ast));
+    }
+
+    /// Parses the given source and adds it to the index.
+    ///
+    /// # Arguments
+    ///
+    /// * `file` - The name to report for hits in this document.
+    /// * `source` - The Norg source of the document.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` if the document was parsed and indexed.
+    /// * `Err(NorgParseError)` if parsing fails.
+    pub fn add_source(
+        &mut self,
+        file: impl Into<String>,
+        source: &str,
+    ) -> Result<(), NorgParseError> {
+        // The parser refuses empty input, which is common enough in a workspace.
+        let ast = if source.trim().is_empty() {
+            vec![]
+        } else {
+            parse_tree(source)?
+        };
+
+        self.add(file.into(), ast, Some(BlockSpans::new(source)?));
+        Ok(())
+    }
+
+    fn walk(
+        &mut self,
+        document: usize,
+        nodes: &[NorgAST],
+        path: &mut Vec<usize>,
+        heading_path: &mut Vec<String>,
+        spans: &mut Option<BlockSpans>,
+    ) {
+        for (i, node) in nodes.iter().enumerate() {
+            path.push(i);
+
+            let span = spans.as_mut().map(|spans| block_span(node, spans));
+            let text = block_text(node);
+            if let Some(text) = &text {
+                self.add_block(document, path, heading_path, span, text);
+            }
+
+            let is_heading = matches!(node, NorgAST::Heading { .. });
+            if is_heading {
+                heading_path.push(text.unwrap_or_default().trim().to_string());
+            }
+            self.walk(document, children(node), path, heading_path, spans);
+            if is_heading {
+                heading_path.pop();
+            }
+
+            path.pop();
+        }
+    }
+
+    fn add_block(
+        &mut self,
+        document: usize,
+        path: &[usize],
+        heading_path: &[String],
+        span: Option<Range<usize>>,
+        text: &str,
+    ) {
+        let block = self.blocks.len();
+        let mut len = 0;
+
+        for (position, word) in tokenize(text).enumerate() {
+            let postings = self.postings.entry(word).or_default();
+            match postings.last_mut() {
+                Some(posting) if posting.block == block => posting.positions.push(position),
+                _ => postings.push(Posting {
+                    block,
+                    positions: vec![position],
+                }),
+            }
+            len += 1;
+        }
+
+        self.total_len += len;
+        self.blocks.push(Block {
+            document,
+            heading_path: heading_path.to_vec(),
+            path: path.to_vec(),
+            span,
+            text: text.trim().to_string(),
+            len,
+        });
+    }
+
+    fn positions(&self, word: &str, block: usize) -> &[usize] {
+        self.postings
+            .get(word)
+            .and_then(|postings| {
+                postings
+                    .binary_search_by_key(&block, |posting| posting.block)
+                    .ok()
+                    .map(|i| postings[i].positions.as_slice())
+            })
+            .unwrap_or_default()
+    }
+
+    /// Returns how often the phrase occurs in each block it occurs in.
+    fn phrase_frequencies(&self, phrase: &[String]) -> HashMap<usize, usize> {
+        let Some((first, rest)) = phrase.split_first() else {
+            return HashMap::new();
+        };
+
+        self.postings
+            .get(first)
+            .into_iter()
+            .flatten()
+            .filter_map(|posting| {
+                let count = posting
+                    .positions
+                    .iter()
+                    .filter(|&&start| {
+                        rest.iter().enumerate().all(|(offset, word)| {
+                            self.positions(word, posting.block)
+                                .binary_search(&(start + offset + 1))
+                                .is_ok()
+                        })
+                    })
+                    .count();
+                (count > 0).then_some((posting.block, count))
+            })
+            .collect()
+    }
+
+    /// Returns `true` if the block, or any node it is nested in, matches the selector.
+    fn is_within(&self, block: &Block, selector: &Selector) -> bool {
+        let mut nodes = self.documents[block.document].1.as_slice();
+        let mut ancestors = vec![];
+
+        for &i in &block.path {
+            let node = &nodes[i];
+            if selector.matches(node, &ancestors) {
+                return true;
+            }
+            ancestors.push(node);
+            nodes = children(node);
+        }
+
+        false
+    }
+
+    /// Searches the index, see the [module documentation](self) for the query syntax.
+    ///
+    /// Hits are sorted by descending score, ties are kept in the order the blocks were indexed.
+    pub fn search(&self, query: &str) -> Vec<SearchHit<'_>> {
+        self.search_blocks(query, |_| true)
+    }
+
+    /// Like [`SearchIndex::search`], but only returns blocks that match the selector or are
+    /// nested in a node that does, e.g. `list-item:todo` to only search in todo items.
+    pub fn search_within(&self, query: &str, selector: &Selector) -> Vec<SearchHit<'_>> {
+        self.search_blocks(query, |block| self.is_within(block, selector))
+    }
+
+    fn search_blocks(&self, query: &str, filter: impl Fn(&Block) -> bool) -> Vec<SearchHit<'_>> {
+        let phrases = parse_query(query);
+        if phrases.is_empty() || self.blocks.is_empty() {
+            return vec![];
+        }
+
+        let count = self.blocks.len() as f64;
+        let average_len = (self.total_len as f64 / count).max(1.0);
+        let mut scores: Option<HashMap<usize, f64>> = None;
+
+        for phrase in phrases {
+            let frequencies = self.phrase_frequencies(&phrase);
+            let frequency = frequencies.len() as f64;
+            let idf = (1.0 + (count - frequency + 0.5) / (frequency + 0.5)).ln();
+
+            let score = |block: usize, tf: usize| {
+                let tf = tf as f64;
+                let len = self.blocks[block].len as f64;
+                idf * tf * (K1 + 1.0) / (tf + K1 * (1.0 - B + B * len / average_len))
+            };
+
+            scores = Some(match scores {
+                None => frequencies
+                    .into_iter()
+                    .map(|(block, tf)| (block, score(block, tf)))
+                    .collect(),
+                Some(scores) => scores
+                    .into_iter()
+                    .filter_map(|(block, total)| {
+                        frequencies
+                            .get(&block)
+                            .map(|&tf| (block, total + score(block, tf)))
+                    })
+                    .collect(),
+            });
+        }
+
+        let mut hits: Vec<_> = scores
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|&(block, _)| filter(&self.blocks[block]))
+            .collect();
+        hits.sort_by(|(a, a_score), (b, b_score)| b_score.total_cmp(a_score).then(a.cmp(b)));
+
+        hits.into_iter()
+            .map(|(block, score)| {
+                let block = &self.blocks[block];
+                SearchHit {
+                    file: &self.documents[block.document].0,
+                    block,
+                    score,
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{parse_tree, query::Selector, search::SearchIndex};
+
+    fn index() -> SearchIndex {
+        let mut index = SearchIndex::new();
+        index
+            .add_source(
+                "work.norg",
+                "* Projects
+  The parser rewrite is going well.
+** Parser
+   - ( ) benchmark the parser
+   - (x) rewrite the lexer
+   -- ( ) document the lexer rewrite
+* Notes
+  A lexer is not a parser.
+",
+            )
+            .unwrap();
+        index
+            .add_source(
+                "home.norg",
+                "* Garden
+  - (!) water the plants
+  > The parser of plants
+",
+            )
+            .unwrap();
+        index.add_source("empty.norg", "\n").unwrap();
+        index
+    }
+
+    fn hits(index: &SearchIndex, query: &str) -> Vec<(String, String, Vec<usize>)> {
+        index
+            .search(query)
+            .into_iter()
+            .map(|hit| {
+                (
+                    hit.file.to_string(),
+                    hit.block.text.clone(),
+                    hit.block.path.clone(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn words_and_phrases() {
+        let index = index();
+
+        assert_eq!(
+            hits(&index, "lexer rewrite"),
+            [
+                (
+                    "work.norg".to_string(),
+                    "rewrite the lexer".to_string(),
+                    vec![0, 1, 1]
+                ),
+                (
+                    "work.norg".to_string(),
+                    "document the lexer rewrite".to_string(),
+                    vec![0, 1, 1, 0]
+                ),
+            ]
+        );
+        assert_eq!(
+            hits(&index, "\"lexer rewrite\"")
+                .into_iter()
+                .map(|(_, text, _)| text)
+                .collect::<Vec<_>>(),
+            ["document the lexer rewrite"]
+        );
+        assert_eq!(hits(&index, "PARSER").len(), 5);
+        assert!(hits(&index, "\"parser lexer\"").is_empty());
+        assert!(hits(&index, "").is_empty());
+    }
+
+    #[test]
+    fn ranking_and_heading_paths() {
+        let index = index();
+        let results = index.search("parser");
+
+        // Short blocks that are all about the word rank first.
+        assert_eq!(results[0].block.text, "Parser");
+        assert_eq!(results[0].block.heading_path, ["Projects"]);
+        assert!(results
+            .windows(2)
+            .all(|pair| pair[0].score >= pair[1].score));
+
+        let plants = index.search("\"parser of plants\"");
+        assert_eq!(plants[0].file, "home.norg");
+        assert_eq!(plants[0].block.heading_path, ["Garden"]);
+    }
+
+    #[test]
+    fn spans() {
+        let source = "* Projects
+  The parser rewrite
+  is going well.
+** Parser
+   - ( ) benchmark the parser
+   #id 1
+   $$ Parser
+   Turns tokens into a tree.
+   $$
+   |example
+   > The parser of ünïcode
+   |end
+";
+        let mut index = SearchIndex::new();
+        index.add_source("work.norg", source).unwrap();
+
+        let spans: Vec<_> = index
+            .search("parser")
+            .into_iter()
+            .map(|hit| &source[hit.block.span.clone().unwrap()])
+            .collect();
+        assert_eq!(
+            spans,
+            [
+                "** Parser",
+                "$$ Parser",
+                "- ( ) benchmark the parser",
+                "> The parser of ünïcode",
+                "The parser rewrite\n  is going well.",
+            ]
+        );
+
+        let mut index = SearchIndex::new();
+        index.add_document("work.norg", parse_tree(source).unwrap());
+        assert_eq!(index.search("parser")[0].block.span, None);
+    }
+
+    #[test]
+    fn within_selector() {
+        let index = index();
+        let texts = |selector: &str| {
+            index
+                .search_within("parser", &Selector::parse(selector).unwrap())
+                .into_iter()
+                .map(|hit| hit.block.text.clone())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(texts("list-item:todo"), ["benchmark the parser"]);
+        assert_eq!(texts("quote"), ["The parser of plants"]);
+        assert_eq!(texts("heading[title=Notes]"), ["A lexer is not a parser."]);
+    }
+}
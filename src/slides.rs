@@ -0,0 +1,287 @@
+//! Renders documents as HTML slide decks, like the presenter mode of Neorg.
+//!
+//! Every heading up to a configurable level starts a new slide, deeper headings are part of the
+//! slide they are in. The content of `|notes` tags is taken out of the slides and becomes their
+//! speaker notes. The rest of a slide is rendered like [`crate::html`] renders it.
+//!
+//! The markup is the one reveal.js expects: a `<section>` per slide inside of
+//! `<div class="reveal"><div class="slides">`, with speaker notes in an
+//! `<aside class="notes">`. By default, a small stylesheet and script are embedded so that the
+//! deck works on its own, without a network connection.
+
+use crate::{
+    html::{header, page, Renderer},
+    NorgAST, ParagraphSegment,
+};
+
+/// Options for the slide renderer.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SlidesOptions {
+    /// Headings up to this level start a new slide. Deeper headings are part of a slide.
+    pub heading_level: u16,
+    /// Extra markup for the end of `<head>`, e.g. to include reveal.js and its theme.
+    pub head: String,
+    /// Embed a small stylesheet and script which show one slide at a time. The arrow keys, space
+    /// and page up or down move between slides, `s` shows the speaker notes. Turn this off when
+    /// including reveal.js through [`SlidesOptions::head`].
+    pub runtime: bool,
+}
+
+impl Default for SlidesOptions {
+    fn default() -> Self {
+        Self {
+            heading_level: 1,
+            head: String::new(),
+            runtime: true,
+        }
+    }
+}
+
+const STYLE: &str = r#"<style>
+html, body { margin: 0; height: 100%; }
+body { font-family: system-ui, sans-serif; font-size: 28px; line-height: 1.4; color: #222; background: #fff; }
+.reveal .slides > section { display: none; box-sizing: border-box; min-height: 100vh; padding: 5vh 8vw; }
+.reveal .slides > section.present { display: block; }
+.reveal .slides > section.title { text-align: center; padding-top: 30vh; }
+.reveal aside.notes { display: none; margin-top: 2em; padding: 0.5em 1em; border-left: 4px solid #aaa; color: #555; font-size: 0.8em; }
+body.notes .reveal aside.notes { display: block; }
+pre { overflow: auto; padding: 0.5em; background: #f4f4f4; }
+img { max-width: 100%; }
+</style>"#;
+
+const SCRIPT: &str = r##"<script>
+(() => {
+  const slides = [...document.querySelectorAll(".reveal .slides > section")];
+  let current = 0;
+  const show = (i) => {
+    current = Math.max(0, Math.min(i, slides.length - 1));
+    slides.forEach((slide, j) => slide.classList.toggle("present", j === current));
+  };
+  const go = (i) => {
+    show(i);
+    history.replaceState(null, "", "#/" + current);
+  };
+  const read = () => {
+    const hash = decodeURIComponent(location.hash.slice(1));
+    const target = /^\/\d+$/.test(hash) ? null : document.getElementById(hash);
+    const slide = target && target.closest(".reveal .slides > section");
+    show(slide ? slides.indexOf(slide) : parseInt(hash.slice(1), 10) || 0);
+  };
+  document.addEventListener("keydown", (event) => {
+    if (event.altKey || event.ctrlKey || event.metaKey) return;
+    switch (event.key) {
+      case "ArrowRight": case "ArrowDown": case "PageDown": case " ": case "n": go(current + 1); break;
+      case "ArrowLeft": case "ArrowUp": case "PageUp": case "p": go(current - 1); break;
+      case "Home": go(0); break;
+      case "End": go(slides.length - 1); break;
+      case "s": document.body.classList.toggle("notes"); break;
+      default: return;
+    }
+    event.preventDefault();
+  });
+  addEventListener("hashchange", read);
+  read();
+})();
+</script>"##;
+
+/// A slide, with the heading it starts with and the nodes which make up its content.
+#[derive(Default)]
+struct Slide<'a> {
+    heading: Option<(u16, &'a [ParagraphSegment])>,
+    nodes: Vec<&'a NorgAST>,
+}
+
+/// The level, title and content of a heading, which may have a carryover tag.
+fn heading(node: &NorgAST) -> Option<(u16, &[ParagraphSegment], &[NorgAST])> {
+    match node {
+        NorgAST::Heading {
+            level,
+            title,
+            content,
+            ..
+        } => Some((*level, title, content)),
+        NorgAST::CarryoverTag { next_object, .. } => heading(next_object),
+        _ => None,
+    }
+}
+
+/// Splits nodes into slides. Nodes which aren't part of a slide yet, e.g. the ones after a
+/// heading has been closed, go into `current` or a new slide without a heading.
+fn split<'a>(
+    nodes: &'a [NorgAST],
+    level: u16,
+    slides: &mut Vec<Slide<'a>>,
+    mut current: Option<usize>,
+) {
+    for node in nodes {
+        if matches!(node, NorgAST::CarryoverTag { name, .. } if name == &["comment"]) {
+            continue;
+        }
+
+        match heading(node) {
+            Some((heading_level, title, content)) if heading_level <= level => {
+                slides.push(Slide {
+                    heading: Some((heading_level, title)),
+                    nodes: Vec::new(),
+                });
+                split(content, level, slides, Some(slides.len() - 1));
+                current = None;
+            }
+            _ => {
+                let i = *current.get_or_insert_with(|| {
+                    slides.push(Slide::default());
+                    slides.len() - 1
+                });
+                slides[i].nodes.push(node);
+            }
+        }
+    }
+}
+
+/// Renders a document as an HTML slide deck.
+///
+/// If a top level `@document.meta` tag has a title, the deck starts with a title slide which
+/// also shows the authors and creation date. Content before the first heading goes onto that
+/// slide, or onto a slide of its own.
+///
+/// # Arguments
+///
+/// * `ast` - The document, as returned by [`crate::parse_tree`].
+/// * `options` - Which headings start a slide, and how the deck is shown.
+///
+/// # Returns
+///
+/// * `String` containing a complete HTML page.
+pub fn to_slides(ast: &[NorgAST], options: &SlidesOptions) -> String {
+    let header = header(ast);
+
+    let mut slides = Vec::new();
+    if header.is_some() {
+        slides.push(Slide::default());
+    }
+    split(
+        ast,
+        options.heading_level,
+        &mut slides,
+        header.is_some().then_some(0),
+    );
+
+    let mut renderer = Renderer::new(ast);
+    let mut sections = Vec::new();
+    for (i, slide) in slides.into_iter().enumerate() {
+        renderer.speaker_notes = Some(Vec::new());
+
+        let mut blocks = Vec::new();
+        let mut class = "";
+        if let Some((level, title)) = slide.heading {
+            blocks.push(renderer.heading(level, title));
+        } else if let Some(header) = header.as_ref().filter(|_| i == 0) {
+            blocks.push(header.clone());
+            class = " class=\"title\"";
+        }
+        blocks.extend(renderer.blocks(slide.nodes));
+        blocks.extend(renderer.footnotes());
+
+        let notes = renderer.speaker_notes.take().unwrap_or_default();
+        if !notes.is_empty() {
+            blocks.push(format!(
+                "<aside class=\"notes\">\n{}\n</aside>",
+                notes.join("\n")
+            ));
+        }
+
+        if !blocks.is_empty() {
+            sections.push(format!(
+                "<section{class}>\n{}\n</section>",
+                blocks.join("\n")
+            ));
+        }
+    }
+
+    let mut head = String::new();
+    if options.runtime {
+        head.push_str(STYLE);
+        head.push('\n');
+    }
+    head.push_str(&options.head);
+
+    let mut body = format!(
+        "<div class=\"reveal\">\n<div class=\"slides\">\n{}\n</div>\n</div>",
+        sections.join("\n")
+    );
+    if options.runtime {
+        body.push('\n');
+        body.push_str(SCRIPT);
+    }
+    page(ast, &head, &body)
+}
+
+#[cfg(test)]
+mod tests {
+    use insta::assert_snapshot;
+
+    use crate::{
+        parse_tree,
+        slides::{to_slides, SlidesOptions},
+    };
+
+    #[test]
+    fn deck() {
+        let source = "@document.meta
+title: Weekly demo
+authors: Ada
+created: 2025-03-01
+@end
+
+* Progress
+  ** Parser
+     - *faster* stage 3{^ 1}
+     - fewer allocations
+     |notes
+     Mention the benchmark.
+     |end
+  ** Renderers
+     @code rust
+     to_slides(&ast, &options)
+     @end
+     *** Details
+         Part of the same slide.
+   ---
+  Back on the renderers slide.
+===
+Between the sections.
+#comment
+* Hidden
+  This is dropped.
+* Questions?
+^ 1
+Twice as fast.
+";
+        let options = SlidesOptions {
+            heading_level: 2,
+            runtime: false,
+            ..Default::default()
+        };
+        assert_snapshot!(to_slides(&parse_tree(source).unwrap(), &options));
+    }
+
+    #[test]
+    fn options() {
+        let ast = parse_tree("Intro\n* One\n** Two\n|notes\nSay hi & wave\n|end\n").unwrap();
+
+        let deck = to_slides(&ast, &SlidesOptions::default());
+        assert!(deck.contains("<style>") && deck.contains("<script>"));
+        assert!(deck.contains(
+            "<section>\n<p>Intro</p>\n</section>\n<section>\n<h1 id=\"one\">One</h1>\n<h2 id=\"two\">Two</h2>\n<aside class=\"notes\">\n<p>Say hi &amp; wave</p>\n</aside>\n</section>"
+        ));
+
+        let options = SlidesOptions {
+            head: String::from("<script src=\"reveal.js\"></script>\n"),
+            runtime: false,
+            ..Default::default()
+        };
+        let deck = to_slides(&ast, &options);
+        assert!(!deck.contains("<style>"));
+        assert!(deck.contains("<script src=\"reveal.js\"></script>\n</head>"));
+    }
+}
@@ -7,11 +7,11 @@ use chumsky::{
     text::{keyword, Character},
     Parser,
 };
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use unicode_categories::UnicodeCategories;
 
 /// Describes an individual part of the document.
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum NorgToken {
     Whitespace(u16),
     SingleNewline,
@@ -44,10 +44,10 @@ impl From<NorgToken> for String {
 }
 
 /// A list of characters which are considered "special", i.e. for parsing of attached modifiers.
-const SPECIAL_CHARS: &str = "*-~/_!%^,\"'`$:@|=.#+<>()[]{}\\";
+pub(crate) const SPECIAL_CHARS: &str = "*-~/_!%^,\"'`$:@|=.#+<>()[]{}\\";
 
-/// Parses a `.norg` document and breaks it up into tokens.
-pub fn stage_1() -> impl Parser<char, Vec<NorgToken>, Error = chumsky::error::Simple<char>> {
+/// Parses a single token.
+fn token() -> impl Parser<char, NorgToken, Error = chumsky::error::Simple<char>> {
     let ws = filter(|c: &char| c.is_inline_whitespace() || c.is_separator_space())
         .repeated()
         .at_least(1)
@@ -60,8 +60,7 @@ pub fn stage_1() -> impl Parser<char, Vec<NorgToken>, Error = chumsky::error::Si
         *c == '\n' || *c == '\r' || c.is_separator_line() || c.is_separator_paragraph()
     });
 
-    let newline = parse_newline
-        .to(NorgToken::SingleNewline);
+    let newline = parse_newline.to(NorgToken::SingleNewline);
 
     let newlines = parse_newline
         .repeated()
@@ -78,6 +77,20 @@ pub fn stage_1() -> impl Parser<char, Vec<NorgToken>, Error = chumsky::error::Si
         .map(NorgToken::End);
 
     choice((tag_end, escape, special, newlines, newline, ws, character))
+}
+
+/// Parses a `.norg` document and breaks it up into tokens.
+pub fn stage_1() -> impl Parser<char, Vec<NorgToken>, Error = chumsky::error::Simple<char>> {
+    token().repeated().chain(end().to(NorgToken::Eof))
+}
+
+/// Like [`stage_1`], but also returns the range of characters (not bytes) each token was
+/// created from.
+pub(crate) fn stage_1_spanned(
+) -> impl Parser<char, Vec<(NorgToken, std::ops::Range<usize>)>, Error = chumsky::error::Simple<char>>
+{
+    token()
+        .map_with_span(|token, span| (token, span))
         .repeated()
-        .chain(end().to(NorgToken::Eof))
+        .chain(end().map_with_span(|_, span| (NorgToken::Eof, span)))
 }
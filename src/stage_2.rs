@@ -1,15 +1,15 @@
 //! Converts a set of Norg tokens into a set of blocks.
 
-use std::fmt::Write as _;
+use std::{fmt::Write as _, ops::Range};
 
 use chumsky::Parser;
 use itertools::Itertools;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 use crate::stage_1::NorgToken;
 use chumsky::prelude::*;
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum ParagraphSegmentToken {
     Text(String),
     Whitespace,
@@ -36,7 +36,21 @@ impl From<ParagraphSegmentToken> for String {
 
 pub type ParagraphTokenList = Vec<ParagraphSegmentToken>;
 
-fn tokens_to_paragraph_segment(tokens: Vec<NorgToken>) -> ParagraphTokenList {
+/// Writes the textual representation of every token into a single string.
+///
+/// Prefer this over converting each token into a `String` first, as that allocates once per
+/// token.
+pub(crate) fn stringify_tokens<T: std::fmt::Display>(
+    tokens: impl IntoIterator<Item = T>,
+) -> String {
+    tokens.into_iter().fold(String::new(), |mut acc, token| {
+        // Writing into a `String` never fails.
+        let _ = write!(acc, "{}", token);
+        acc
+    })
+}
+
+pub(crate) fn tokens_to_paragraph_segment(tokens: Vec<NorgToken>) -> ParagraphTokenList {
     tokens
         .into_iter()
         .peekable()
@@ -47,12 +61,12 @@ fn tokens_to_paragraph_segment(tokens: Vec<NorgToken>) -> ParagraphTokenList {
             Some(NorgToken::Special(c)) => Some(ParagraphSegmentToken::Special(c)),
             Some(NorgToken::Escape(c)) => Some(ParagraphSegmentToken::Escape(c)),
             Some(NorgToken::Regular(c)) => {
-                let mut result: String = it
-                    .peeking_take_while(|token| matches!(token, NorgToken::Regular(_)))
-                    .map_into::<String>()
-                    .collect();
+                let mut result = String::from(c);
 
-                result.insert(0, c);
+                while let Some(NorgToken::Regular(c)) = it.peek() {
+                    result.push(*c);
+                    it.next();
+                }
 
                 Some(ParagraphSegmentToken::Text(result))
             }
@@ -65,7 +79,7 @@ fn tokens_to_paragraph_segment(tokens: Vec<NorgToken>) -> ParagraphTokenList {
 }
 
 /// Represents various Norg blocks parsed from tokens.
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum NorgBlock {
     /// A segment of a paragraph consisting of Norg tokens.
     ParagraphSegment(ParagraphTokenList),
@@ -76,12 +90,17 @@ pub enum NorgBlock {
         level: u16,
         title: ParagraphTokenList,
         extension_section: ParagraphTokenList,
+        /// The tokens the extension section, including its parentheses, was parsed from. Empty
+        /// and placed right before the title if there is no extension section.
+        extension_span: Range<usize>,
     },
     /// A nestable detached modifier with a type, level, and optional extension section.
     NestableDetachedModifier {
         modifier_type: char,
         level: u16,
         extension_section: ParagraphTokenList,
+        /// The tokens the extension section was parsed from, see [`NorgBlock::Heading`].
+        extension_span: Range<usize>,
     },
     /// A rangeable detached modifier with an indication if it is ranged, type, title, and optional extension section.
     RangeableDetachedModifier {
@@ -121,14 +140,8 @@ pub enum NorgBlock {
     DelimitingModifier(char),
 }
 
-/// Defines the parser for stage 2 of the Norg parsing process, which converts tokens into blocks.
-///
-/// # Returns
-///
-/// * A parser that processes `NorgToken`s into a vector of `NorgBlock`s, which properly define
-///   paragraph boundaries.
-pub fn stage_2() -> impl Parser<NorgToken, Vec<NorgBlock>, Error = chumsky::error::Simple<NorgToken>>
-{
+/// Parses a single block.
+fn block() -> impl Parser<NorgToken, NorgBlock, Error = chumsky::error::Simple<NorgToken>> {
     use NorgToken::*;
 
     let whitespace = select! { Whitespace(_) => () };
@@ -139,12 +152,6 @@ pub fn stage_2() -> impl Parser<NorgToken, Vec<NorgBlock>, Error = chumsky::erro
         e @ Eof => e,
     };
 
-    let newlines_whitespace = select! {
-        Newlines(_) => (),
-        SingleNewline => (),
-        Whitespace(_) => (),
-    };
-
     let newlines_whitespace_or_eof = select! {
         Newlines(_) => (),
         SingleNewline => (),
@@ -165,6 +172,11 @@ pub fn stage_2() -> impl Parser<NorgToken, Vec<NorgBlock>, Error = chumsky::erro
     .at_least(1)
     .delimited_by(just(Special('(')), just(Special(')')));
 
+    let spanned_extension_section = extension_section
+        .clone()
+        .map_with_span(|tokens, span| (tokens, span))
+        .or(empty().map_with_span(|_, span: Range<usize>| (vec![], span.start..span.start)));
+
     let parameters = newlines_whitespace_or_eof
         .not()
         .repeated()
@@ -179,16 +191,17 @@ pub fn stage_2() -> impl Parser<NorgToken, Vec<NorgBlock>, Error = chumsky::erro
     .at_least(1)
     .map(|chars| chars.len() as u16)
     .then_ignore(whitespace.repeated().at_least(1))
-    .then(extension_section.clone().or_not())
+    .then(spanned_extension_section.clone())
     .then(paragraph_segment)
     .then_ignore(newlines_or_eof)
-    .map(|((level, extension_section), title)| NorgBlock::Heading {
-        level,
-        title: tokens_to_paragraph_segment(title),
-        extension_section: extension_section
-            .map(tokens_to_paragraph_segment)
-            .unwrap_or_default(),
-    })
+    .map(
+        |((level, (extension_section, extension_span)), title)| NorgBlock::Heading {
+            level,
+            title: tokens_to_paragraph_segment(title),
+            extension_section: tokens_to_paragraph_segment(extension_section),
+            extension_span,
+        },
+    )
     .labelled("heading");
 
     let nestable_detached_modifier = select! {
@@ -217,12 +230,13 @@ pub fn stage_2() -> impl Parser<NorgToken, Vec<NorgBlock>, Error = chumsky::erro
         }
     })
     .then_ignore(whitespace.repeated().at_least(1))
-    .then(extension_section.clone().or_not())
+    .then(spanned_extension_section)
     .map(
-        |((modifier_type, level), extension_section)| NorgBlock::NestableDetachedModifier {
+        |((modifier_type, level), (extension_section, extension_span))| NorgBlock::NestableDetachedModifier {
             modifier_type,
             level,
-            extension_section: extension_section.map(tokens_to_paragraph_segment).unwrap_or_default(),
+            extension_section: tokens_to_paragraph_segment(extension_section),
+            extension_span,
         },
     )
     .labelled("nestabled_detached_modifier");
@@ -431,7 +445,41 @@ pub fn stage_2() -> impl Parser<NorgToken, Vec<NorgBlock>, Error = chumsky::erro
             })
             .labelled("paragraph_segment"),
     ))
-    .padded_by(newlines_whitespace.repeated())
+}
+
+/// Parses the whitespace and newlines between blocks.
+fn block_padding() -> impl Parser<NorgToken, (), Error = chumsky::error::Simple<NorgToken>> + Clone
+{
+    select! {
+        NorgToken::Newlines(_) => (),
+        NorgToken::SingleNewline => (),
+        NorgToken::Whitespace(_) => (),
+    }
     .repeated()
-    .then_ignore(just(Eof))
+    .ignored()
+}
+
+/// Defines the parser for stage 2 of the Norg parsing process, which converts tokens into blocks.
+///
+/// # Returns
+///
+/// * A parser that processes `NorgToken`s into a vector of `NorgBlock`s, which properly define
+///   paragraph boundaries.
+pub fn stage_2() -> impl Parser<NorgToken, Vec<NorgBlock>, Error = chumsky::error::Simple<NorgToken>>
+{
+    block()
+        .padded_by(block_padding())
+        .repeated()
+        .then_ignore(just(NorgToken::Eof))
+}
+
+/// Like [`stage_2`], but also returns the range of tokens each block was parsed from.
+pub(crate) fn stage_2_spanned(
+) -> impl Parser<NorgToken, Vec<(NorgBlock, Range<usize>)>, Error = chumsky::error::Simple<NorgToken>>
+{
+    block()
+        .map_with_span(|block, span| (block, span))
+        .padded_by(block_padding())
+        .repeated()
+        .then_ignore(just(NorgToken::Eof))
 }
@@ -1,13 +1,22 @@
+// `select!` produces closures returning `Result<_, Simple<NorgBlock>>`, which we can't box.
+#![allow(clippy::result_large_err)]
+
 use std::fmt::Write;
 
 use chumsky::prelude::*;
 use itertools::Itertools;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use textwrap::dedent;
 
-use crate::stage_2::{NorgBlock, ParagraphSegmentToken, ParagraphTokenList};
+use crate::{
+    stage_1::{stage_1, NorgToken},
+    stage_2::{
+        stringify_tokens, tokens_to_paragraph_segment, NorgBlock, ParagraphSegmentToken,
+        ParagraphTokenList,
+    },
+};
 
-#[derive(Clone, Hash, Debug, PartialEq, Eq, Serialize)]
+#[derive(Clone, Hash, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum NestableDetachedModifier {
     Quote,
     UnorderedList,
@@ -24,7 +33,7 @@ impl std::fmt::Display for NestableDetachedModifier {
     }
 }
 
-#[derive(Clone, Hash, Debug, PartialEq, Eq, Serialize)]
+#[derive(Clone, Hash, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum RangeableDetachedModifier {
     Definition,
     Footnote,
@@ -41,7 +50,7 @@ impl std::fmt::Display for RangeableDetachedModifier {
     }
 }
 
-#[derive(Clone, Hash, Debug, PartialEq, Eq, Serialize)]
+#[derive(Clone, Hash, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum TodoStatus {
     /// ` `
     Undone,
@@ -61,7 +70,23 @@ pub enum TodoStatus {
     Canceled,
 }
 
-#[derive(Clone, Hash, Debug, PartialEq, Eq, Serialize)]
+impl std::fmt::Display for TodoStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Undone => f.write_char(' '),
+            Self::Done => f.write_char('x'),
+            Self::NeedsClarification => f.write_char('?'),
+            Self::Paused => f.write_char('='),
+            Self::Urgent => f.write_char('!'),
+            Self::Recurring(None) => f.write_char('+'),
+            Self::Recurring(Some(timestamp)) => write!(f, "+ {}", timestamp),
+            Self::Pending => f.write_char('-'),
+            Self::Canceled => f.write_char('_'),
+        }
+    }
+}
+
+#[derive(Clone, Hash, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum DetachedModifierExtension {
     /// todo item status:
     /// `- ( ) undone`
@@ -92,13 +117,13 @@ pub enum DetachedModifierExtension {
     StartDate(String),
 }
 
-#[derive(Clone, Hash, Debug, PartialEq, Eq, Serialize)]
+#[derive(Clone, Hash, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum CarryoverTag {
     Attribute, // `+`
     Macro,     // `#`
 }
 
-#[derive(PartialEq, Serialize)]
+#[derive(PartialEq, Serialize, Deserialize)]
 pub enum RangedTag {
     Macro,
     Standard,
@@ -111,7 +136,7 @@ fn paragraph_parser_opener_candidates_and_links() -> impl Parser<
 > {
     let token = any().map(ParagraphSegment::Token);
     let modifier = select! {
-        ParagraphSegmentToken::Special(c @ ('*' | '/' | '_' | '-')) => c,
+        ParagraphSegmentToken::Special(c @ ('*' | '/' | '_' | '-' | '%')) => c,
     };
 
     let whitespace_or_special = select! {
@@ -144,6 +169,32 @@ fn paragraph_parser_opener_candidates_and_links() -> impl Parser<
         .then_ignore(just(ParagraphSegmentToken::Special('`')))
         .map(ParagraphSegment::InlineVerbatim);
 
+    // Like inline verbatim, but `$` is also common in prose, so it follows the attached modifier
+    // rules: the content may neither start nor end with whitespace, e.g. `$5 and $10` stays text,
+    // and the closing `$` must be followed by whitespace, punctuation or the end of the paragraph.
+    let inline_math = just(ParagraphSegmentToken::Special('$'))
+        .ignore_then(
+            just(ParagraphSegmentToken::Special('$'))
+                .not()
+                .repeated()
+                .at_least(1),
+        )
+        .then_ignore(just(ParagraphSegmentToken::Special('$')))
+        .then_ignore(whitespace_or_special.ignored().or(end()).rewind())
+        .try_map(|content: Vec<ParagraphSegmentToken>, span| {
+            let padded = |token: Option<&ParagraphSegmentToken>| {
+                token == Some(&ParagraphSegmentToken::Whitespace)
+            };
+            if padded(content.first()) || padded(content.last()) {
+                Err(Simple::custom(
+                    span,
+                    "Inline math may not be padded with whitespace.",
+                ))
+            } else {
+                Ok(ParagraphSegment::InlineMath(content))
+            }
+        });
+
     let anchor = just(ParagraphSegmentToken::Special('['))
         .ignore_then(
             just(ParagraphSegmentToken::Special(']'))
@@ -196,8 +247,7 @@ fn paragraph_parser_opener_candidates_and_links() -> impl Parser<
         .then(anchor.clone().or_not())
         .map(
             |(((filepath, modifiers), content), description)| ParagraphSegment::Link {
-                filepath: filepath
-                    .map(|content| content.into_iter().map_into::<String>().collect()),
+                filepath: filepath.map(stringify_tokens),
                 description: description.map(|content| parse_paragraph(content).unwrap()),
                 targets: if let Some(content) = content {
                     vec![if let Some(modifiers) = modifiers {
@@ -206,12 +256,8 @@ fn paragraph_parser_opener_candidates_and_links() -> impl Parser<
                             "^" => LinkTarget::Footnote(parse_paragraph(content).unwrap()),
                             "?" => LinkTarget::Wiki(parse_paragraph(content).unwrap()),
                             "=" => LinkTarget::Extendable(parse_paragraph(content).unwrap()),
-                            "/" => {
-                                LinkTarget::Path(content.into_iter().map_into::<String>().collect())
-                            }
-                            "@" => LinkTarget::Timestamp(
-                                content.into_iter().map_into::<String>().collect(),
-                            ),
+                            "/" => LinkTarget::Path(stringify_tokens(content)),
+                            "@" => LinkTarget::Timestamp(stringify_tokens(content)),
 
                             // Only other possibility is a heading.
                             str => LinkTarget::Heading {
@@ -220,7 +266,7 @@ fn paragraph_parser_opener_candidates_and_links() -> impl Parser<
                             },
                         }
                     } else {
-                        LinkTarget::Url(content.into_iter().map_into::<String>().collect())
+                        LinkTarget::Url(stringify_tokens(content))
                     }]
                 } else {
                     vec![]
@@ -238,31 +284,49 @@ fn paragraph_parser_opener_candidates_and_links() -> impl Parser<
         .then_ignore(just(ParagraphSegmentToken::Special('>')))
         .map(|content| ParagraphSegment::InlineLinkTarget(parse_paragraph(content).unwrap()));
 
-    left_empty_opening_modifier.or_not().chain(
-        choice((
-            link.clone(),
-            anchor
-                .clone()
-                .then(link)
-                .map(|(content, link)| ParagraphSegment::AnchorDefinition {
-                    content: parse_paragraph(content).unwrap(),
-                    target: Box::new(link),
-                }),
-            inline_verbatim,
-            anchor
-                .clone()
-                .then(anchor.clone().or_not())
-                .map(|(content, description)| ParagraphSegment::Anchor {
-                    content: parse_paragraph(content).unwrap(),
-                    description: description.map(|content| parse_paragraph(content).unwrap()),
-                }),
-            inline_linkable,
-            opening_modifier_candidate,
-            token,
-        ))
-        .repeated()
-        .at_least(1),
-    )
+    // A `$` right after a word can't open inline math, e.g. `US$5`, so it's taken as text along
+    // with the word.
+    let attached_dollar = select! {
+        t @ ParagraphSegmentToken::Text(_) => t,
+        e @ ParagraphSegmentToken::Escape(_) => e,
+    }
+    .then(just(ParagraphSegmentToken::Special('$')))
+    .map(|(left, dollar)| {
+        vec![
+            ParagraphSegment::Token(left),
+            ParagraphSegment::Token(dollar),
+        ]
+    });
+
+    let segment = choice((
+        link.clone(),
+        anchor
+            .clone()
+            .then(link)
+            .map(|(content, link)| ParagraphSegment::AnchorDefinition {
+                content: parse_paragraph(content).unwrap(),
+                target: Box::new(link),
+            }),
+        inline_verbatim,
+        inline_math,
+        anchor
+            .clone()
+            .then(anchor.clone().or_not())
+            .map(|(content, description)| ParagraphSegment::Anchor {
+                content: parse_paragraph(content).unwrap(),
+                description: description.map(|content| parse_paragraph(content).unwrap()),
+            }),
+        inline_linkable,
+        opening_modifier_candidate,
+        token,
+    ))
+    .map(|segment| vec![segment]);
+    let segment = attached_dollar.or(segment);
+
+    // An opener at the very start may take up the whole paragraph, e.g. an unclosed `*bold`.
+    left_empty_opening_modifier
+        .chain(segment.clone().repeated().flatten())
+        .or(segment.repeated().at_least(1).flatten())
 }
 
 fn dedup_opener_candidates(input: Vec<ParagraphSegment>) -> Vec<ParagraphSegment> {
@@ -286,7 +350,7 @@ fn paragraph_parser_closer_candidates(
 
     let token = any();
     let modifier = select! {
-        Token(ParagraphSegmentToken::Special(c @ ('*' | '/' | '_' | '-'))) => c,
+        Token(ParagraphSegmentToken::Special(c @ ('*' | '/' | '_' | '-' | '%'))) => c,
     };
 
     let whitespace_or_special = select! {
@@ -427,7 +491,7 @@ fn eliminate_invalid_candidates(input: Vec<ParagraphSegment>) -> Vec<ParagraphSe
         })
 }
 
-#[derive(Clone, Hash, Debug, PartialEq, Eq, Serialize)]
+#[derive(Clone, Hash, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum LinkTarget {
     Heading {
         level: u16,
@@ -443,7 +507,7 @@ pub enum LinkTarget {
     Timestamp(String),
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize, Hash, Eq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Hash, Eq)]
 pub enum ParagraphSegment {
     Token(ParagraphSegmentToken),
     AttachedModifierOpener(
@@ -492,9 +556,10 @@ pub enum ParagraphSegment {
     },
     InlineLinkTarget(Vec<ParagraphSegment>),
     InlineVerbatim(Vec<ParagraphSegmentToken>),
+    InlineMath(Vec<ParagraphSegmentToken>),
 }
 
-fn parse_paragraph(
+pub(crate) fn parse_paragraph(
     input: Vec<ParagraphSegmentToken>,
 ) -> Result<Vec<ParagraphSegment>, Vec<chumsky::error::Simple<ParagraphSegmentToken>>> {
     Ok(eliminate_invalid_candidates(unravel_candidates(
@@ -510,7 +575,7 @@ fn parse_paragraph(
     )))
 }
 
-#[derive(Clone, Debug, PartialEq, Hash, Eq, Serialize)]
+#[derive(Clone, Debug, PartialEq, Hash, Eq, Serialize, Deserialize)]
 pub enum NorgASTFlat {
     Paragraph(Vec<ParagraphSegment>),
     NestableDetachedModifier {
@@ -553,13 +618,24 @@ pub enum NorgASTFlat {
     DelimitingModifier(DelimitingModifier),
 }
 
-#[derive(Clone, Hash, Debug, PartialEq, Eq, Serialize)]
+#[derive(Clone, Hash, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum DelimitingModifier {
     Weak,
     Strong,
     HorizontalRule,
 }
 
+/// Converts the (optional) parameters of a tag into a list of strings.
+fn stringify_parameters(parameters: Option<Vec<ParagraphTokenList>>) -> Vec<String> {
+    parameters
+        .unwrap_or_default()
+        .into_iter()
+        .map(stringify_tokens)
+        .collect()
+}
+
+/// Parses the contents of a detached modifier extension section, e.g. the `x|# A` in
+/// `- (x|# A) task`. The whole section has to be made up of valid extensions.
 fn detached_modifier_extensions() -> impl Parser<
     ParagraphSegmentToken,
     Vec<DetachedModifierExtension>,
@@ -567,55 +643,119 @@ fn detached_modifier_extensions() -> impl Parser<
 > {
     use ParagraphSegmentToken::*;
 
-    let detached_modifier_extension_tokens = select! {
-        c @ Special('@' | '#' | '<' | '>' | '+' | '=' | '_' | '-' | '!') => c,
-        Whitespace => Whitespace,
-        Text(c) if c == "x" || c == "?" => Text(c),
-    };
+    let separator = just(Special('|'));
+
+    // Everything up to the next `|`, after a single space.
+    let metadata = just(Whitespace)
+        .ignore_then(separator.clone().not().repeated().at_least(1))
+        .map(|tokens| stringify_tokens(tokens).trim_end().to_string());
+
+    let status = select! {
+        Whitespace => TodoStatus::Undone,
+        Text(c) if c == "x" => TodoStatus::Done,
+        Text(c) if c == "?" => TodoStatus::NeedsClarification,
+        Special('=') => TodoStatus::Paused,
+        Special('!') => TodoStatus::Urgent,
+        Special('-') => TodoStatus::Pending,
+        Special('_') => TodoStatus::Canceled,
+    }
+    .then_ignore(just(Whitespace).or_not())
+    .map(DetachedModifierExtension::Todo);
 
-    let detached_modifier_extension = detached_modifier_extension_tokens
-        .then(
-            just(Whitespace)
-                .ignore_then(select!(Special('|') => Special('|')).not().repeated())
-                .or_not()
-                .map(|tokens| {
-                    if let Some(tokens) = tokens {
-                        tokens
-                            .into_iter()
-                            .map_into::<String>()
-                            .collect()
-                    } else {
-                        String::from("")
-                    }
-                }),
-        )
-        .map(|(spec, metadata)| match spec {
-            Special('@') => DetachedModifierExtension::Timestamp(metadata),
-            Special('#') => DetachedModifierExtension::Priority(metadata),
-            Special('<') => DetachedModifierExtension::DueDate(metadata),
-            Special('>') => DetachedModifierExtension::StartDate(metadata),
-            Special('+') => {
-                DetachedModifierExtension::Todo(TodoStatus::Recurring(if metadata.is_empty() {
-                    None
-                } else {
-                    Some(metadata)
-                }))
-            }
-            Special('=') => DetachedModifierExtension::Todo(TodoStatus::Paused),
-            Special('_') => DetachedModifierExtension::Todo(TodoStatus::Canceled),
-            Special('-') => DetachedModifierExtension::Todo(TodoStatus::Pending),
-            Special('!') => DetachedModifierExtension::Todo(TodoStatus::Urgent),
-            Whitespace => DetachedModifierExtension::Todo(TodoStatus::Undone),
-            Text(str) if str == "x" => DetachedModifierExtension::Todo(TodoStatus::Done),
-            Text(str) if str == "?" => {
-                DetachedModifierExtension::Todo(TodoStatus::NeedsClarification)
-            }
-            _ => unreachable!(),
-        });
+    let recurring = just(Special('+'))
+        .ignore_then(metadata.clone().or_not())
+        .then_ignore(just(Whitespace).or_not())
+        .map(|timestamp| DetachedModifierExtension::Todo(TodoStatus::Recurring(timestamp)));
+
+    let with_metadata = select! {
+        Special(c @ ('#' | '@' | '<' | '>')) => c,
+    }
+    .then(metadata.or_not())
+    .try_map(|(c, metadata), span| {
+        let (name, extension): (_, fn(String) -> DetachedModifierExtension) = match c {
+            '#' => ("priority", DetachedModifierExtension::Priority),
+            '@' => ("timestamp", DetachedModifierExtension::Timestamp),
+            '<' => ("due date", DetachedModifierExtension::DueDate),
+            _ => ("start date", DetachedModifierExtension::StartDate),
+        };
+
+        metadata.map(extension).ok_or_else(|| {
+            Simple::custom(
+                span,
+                format!("Expected a space followed by a {} after '{}'.", name, c),
+            )
+        })
+    });
 
-    detached_modifier_extension
-        .separated_by(just(Special('|')))
+    choice((recurring, with_metadata, status))
+        .separated_by(separator)
         .at_least(1)
+        .then_ignore(end())
+}
+
+/// Describes what is wrong with an extension section, e.g. `Unexpected 'y'.` for `(y)`.
+fn extension_error(extension_section: ParagraphTokenList) -> Option<String> {
+    let errors = detached_modifier_extensions()
+        .parse(extension_section)
+        .err()?;
+
+    Some(
+        match errors.first().map(|error| (error.reason(), error.found())) {
+            Some((chumsky::error::SimpleReason::Custom(message), _)) => message.clone(),
+            Some((_, Some(found))) => format!("Unexpected '{}'.", found),
+            _ => String::from("Unexpected end of extension."),
+        },
+    )
+}
+
+/// Checks the extension section of a detached modifier, given as source text without its
+/// parentheses.
+///
+/// The parser doesn't reject invalid extension sections, it reads them as text instead, so that
+/// e.g. `- (see below) item` is a list item. This is how the linter finds them anyway.
+pub(crate) fn check_extensions(extension_section: &str) -> Result<(), String> {
+    let tokens = stage_1()
+        .parse(extension_section)
+        .map_err(|_| String::from("Unexpected end of extension."))?
+        .into_iter()
+        .filter(|token| *token != NorgToken::Eof)
+        .collect();
+
+    match extension_error(tokens_to_paragraph_segment(tokens)) {
+        Some(reason) => Err(format!(
+            "Invalid detached modifier extension '({})': {}",
+            extension_section, reason
+        )),
+        None => Ok(()),
+    }
+}
+
+/// Parses the extension section of a detached modifier. If it isn't a valid extension section,
+/// it is put back in front of `text` with its parentheses, as `- (see below) item` is just a
+/// list item which starts with parentheses.
+fn parse_extensions(
+    extension_section: ParagraphTokenList,
+    text: &mut ParagraphTokenList,
+) -> Vec<DetachedModifierExtension> {
+    if extension_section.is_empty() {
+        return vec![];
+    }
+
+    match detached_modifier_extensions().parse(extension_section.clone()) {
+        Ok(extensions) => extensions,
+        Err(_) => {
+            let separator = match text.first() {
+                Some(ParagraphSegmentToken::Whitespace) | None => None,
+                Some(_) => Some(ParagraphSegmentToken::Whitespace),
+            };
+            let section = std::iter::once(ParagraphSegmentToken::Special('('))
+                .chain(extension_section)
+                .chain(std::iter::once(ParagraphSegmentToken::Special(')')))
+                .chain(separator);
+            text.splice(0..0, section);
+            vec![]
+        }
+    }
 }
 
 pub fn stage_3(
@@ -629,7 +769,7 @@ pub fn stage_3(
             NorgBlock::ParagraphSegmentEnd(content) => content,
         };
 
-        let paragraph = choice((
+        let paragraph_tokens = choice((
             paragraph_segment
                 .repeated()
                 .at_least(1)
@@ -644,28 +784,30 @@ pub fn stage_3(
                     tokens.pop();
                 }
 
-                NorgASTFlat::Paragraph(parse_paragraph(tokens).unwrap())
+                tokens
             });
 
+        let paragraph = paragraph_tokens.map(|tokens| NorgASTFlat::Paragraph(parse_paragraph(tokens).unwrap()));
+
         let nestable_detached_modifier = select! {
-            NorgBlock::NestableDetachedModifier { modifier_type: '-', level, extension_section } => (NestableDetachedModifier::UnorderedList, level, extension_section),
-            NorgBlock::NestableDetachedModifier { modifier_type: '~', level, extension_section } => (NestableDetachedModifier::OrderedList, level, extension_section),
-            NorgBlock::NestableDetachedModifier { modifier_type: '>', level, extension_section } => (NestableDetachedModifier::Quote, level, extension_section),
-        }.then(paragraph).map(|((modifier_type, level, extension_section), paragraph)| NorgASTFlat::NestableDetachedModifier {
+            NorgBlock::NestableDetachedModifier { modifier_type: '-', level, extension_section, .. } => (NestableDetachedModifier::UnorderedList, level, extension_section),
+            NorgBlock::NestableDetachedModifier { modifier_type: '~', level, extension_section, .. } => (NestableDetachedModifier::OrderedList, level, extension_section),
+            NorgBlock::NestableDetachedModifier { modifier_type: '>', level, extension_section, .. } => (NestableDetachedModifier::Quote, level, extension_section),
+        }.then(paragraph_tokens).map(|((modifier_type, level, extension_section), mut text)| NorgASTFlat::NestableDetachedModifier {
                 modifier_type,
                 level,
-                extensions: detached_modifier_extensions().parse(extension_section).unwrap_or_default(),
-                content: Box::new(paragraph),
+                extensions: parse_extensions(extension_section, &mut text),
+                content: Box::new(NorgASTFlat::Paragraph(parse_paragraph(text).unwrap())),
             });
 
         let nonranged_detached_modifier = select! {
             NorgBlock::RangeableDetachedModifier { modifier_type: '$', ranged: false, title, extension_section } => (RangeableDetachedModifier::Definition, title, extension_section),
             NorgBlock::RangeableDetachedModifier { modifier_type: '^', ranged: false, title, extension_section} => (RangeableDetachedModifier::Footnote, title, extension_section),
             NorgBlock::RangeableDetachedModifier { modifier_type: ':', ranged: false, title, extension_section } => (RangeableDetachedModifier::Table, title, extension_section),
-        }.then(paragraph).map(|((modifier_type, title, extension_section), paragraph)| NorgASTFlat::RangeableDetachedModifier {
+        }.then(paragraph).map(|((modifier_type, mut title, extension_section), paragraph)| NorgASTFlat::RangeableDetachedModifier {
                 modifier_type,
+                extensions: parse_extensions(extension_section, &mut title),
                 title: parse_paragraph(title).unwrap(),
-                extensions: detached_modifier_extensions().parse(extension_section).unwrap_or_default(),
                 content: vec![paragraph],
             });
 
@@ -676,12 +818,12 @@ pub fn stage_3(
         }
             .then(stage_3.clone().repeated())
             .then(select! { NorgBlock::RangeableDetachedModifierClose(c) => c })
-            .try_map(|(((opening_ch, modifier_type, title, extension_section), content), closing_ch), span|
+            .try_map(|(((opening_ch, modifier_type, mut title, extension_section), content), closing_ch), span|
                 if opening_ch == closing_ch {
                     Ok(NorgASTFlat::RangeableDetachedModifier {
                         modifier_type,
+                        extensions: parse_extensions(extension_section, &mut title),
                         title: parse_paragraph(title).unwrap(),
-                        extensions: detached_modifier_extensions().parse(extension_section).unwrap_or_default(),
                         content,
                     })
                 } else {
@@ -689,16 +831,16 @@ pub fn stage_3(
                 });
 
         let heading = select! {
-            NorgBlock::Heading { level, title, extension_section } => (level, title, extension_section),
+            NorgBlock::Heading { level, title, extension_section, .. } => (level, title, extension_section),
         }
-        .try_map(move |(level, title, extension_section), _span| Ok(NorgASTFlat::Heading {
+        .map(move |(level, mut title, extension_section)| NorgASTFlat::Heading {
             level,
+            extensions: parse_extensions(extension_section, &mut title),
             title: parse_paragraph(title).unwrap(),
-            extensions: detached_modifier_extensions().parse(extension_section).unwrap_or_default(),
-        }));
+        });
 
         let stringify_tokens_and_split = move |tokens: ParagraphTokenList| -> Vec<String> {
-            tokens.into_iter().map_into::<String>().collect::<String>().split('.').map_into().collect()
+            stringify_tokens(tokens).split('.').map_into().collect()
         };
 
         let carryover_tag = select! {
@@ -708,7 +850,7 @@ pub fn stage_3(
                 NorgASTFlat::CarryoverTag {
                     tag_type,
                     name: stringify_tokens_and_split(name),
-                    parameters: parameters.unwrap_or_default().into_iter().map(|parameter| parameter.into_iter().map_into::<String>().collect()).collect(),
+                    parameters: stringify_parameters(parameters),
                     next_object: Box::new(next_object),
                 }
             });
@@ -717,15 +859,15 @@ pub fn stage_3(
             NorgBlock::VerbatimRangedTag { name, parameters, content } => {
                 NorgASTFlat::VerbatimRangedTag {
                     name: stringify_tokens_and_split(name),
-                    parameters: parameters.unwrap_or_default().into_iter().map(|parameter| parameter.into_iter().map_into::<String>().collect()).collect(),
-                    content: dedent(content.into_iter().map_into::<String>().collect::<String>().as_str()),
+                    parameters: stringify_parameters(parameters),
+                    content: dedent(stringify_tokens(content).as_str()),
                 }
             },
         };
 
         let ranged_tag = select! {
-            NorgBlock::RangedTag { tag_type: '=', name, parameters } => (RangedTag::Macro, stringify_tokens_and_split(name), parameters.unwrap_or_default().into_iter().map(|parameter| parameter.into_iter().map_into::<String>().collect()).collect()),
-            NorgBlock::RangedTag { tag_type: '|', name, parameters } => (RangedTag::Standard, stringify_tokens_and_split(name), parameters.unwrap_or_default().into_iter().map(|parameter| parameter.into_iter().map_into::<String>().collect()).collect())
+            NorgBlock::RangedTag { tag_type: '=', name, parameters } => (RangedTag::Macro, stringify_tokens_and_split(name), stringify_parameters(parameters)),
+            NorgBlock::RangedTag { tag_type: '|', name, parameters } => (RangedTag::Standard, stringify_tokens_and_split(name), stringify_parameters(parameters))
         }.then(stage_3.repeated()).then(select! {
             NorgBlock::RangedTagEnd('=') => RangedTag::Macro,
             NorgBlock::RangedTagEnd('|') => RangedTag::Standard,
@@ -736,7 +878,7 @@ pub fn stage_3(
         });
 
         let infirm_tag = select! {
-            NorgBlock::InfirmTag { name, parameters, } => NorgASTFlat::InfirmTag { name: stringify_tokens_and_split(name), parameters: parameters.unwrap_or_default().into_iter().map(|parameter| parameter.into_iter().map_into::<String>().collect()).collect() },
+            NorgBlock::InfirmTag { name, parameters, } => NorgASTFlat::InfirmTag { name: stringify_tokens_and_split(name), parameters: stringify_parameters(parameters) },
         };
 
         let delimiting_mod = select! {
@@ -1,25 +1,25 @@
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 use crate::{
     stage_3::{DelimitingModifier, NorgASTFlat, ParagraphSegment},
     CarryoverTag, DetachedModifierExtension, NestableDetachedModifier, RangeableDetachedModifier,
 };
 
-#[derive(Debug, PartialEq, Eq, Clone, Hash, Serialize)]
+#[derive(Debug, PartialEq, Eq, Clone, Hash, Serialize, Deserialize)]
 pub enum NorgAST {
     Paragraph(Vec<ParagraphSegment>),
     NestableDetachedModifier {
         modifier_type: NestableDetachedModifier,
         level: u16,
         extensions: Vec<DetachedModifierExtension>,
-        text: Box<NorgASTFlat>,
+        text: Box<Self>,
         content: Vec<Self>,
     },
     RangeableDetachedModifier {
         modifier_type: RangeableDetachedModifier,
         title: Vec<ParagraphSegment>,
         extensions: Vec<DetachedModifierExtension>,
-        content: Vec<NorgASTFlat>,
+        content: Vec<Self>,
     },
     Heading {
         level: u16,
@@ -41,7 +41,7 @@ pub enum NorgAST {
     RangedTag {
         name: Vec<String>,
         parameters: Vec<String>,
-        content: Vec<NorgASTFlat>,
+        content: Vec<Self>,
     },
     InfirmTag {
         name: Vec<String>,
@@ -62,7 +62,7 @@ fn convert(flat: NorgASTFlat) -> NorgAST {
             modifier_type,
             title,
             extensions,
-            content,
+            content: stage_4(content),
         },
         NorgASTFlat::VerbatimRangedTag {
             name,
@@ -80,7 +80,7 @@ fn convert(flat: NorgASTFlat) -> NorgAST {
         } => NorgAST::RangedTag {
             name,
             parameters,
-            content,
+            content: stage_4(content),
         },
         NorgASTFlat::InfirmTag { name, parameters } => NorgAST::InfirmTag { name, parameters },
         NorgASTFlat::DelimitingModifier(t) => NorgAST::DelimitingModifier(t),
@@ -93,7 +93,7 @@ fn convert(flat: NorgASTFlat) -> NorgAST {
             modifier_type,
             level,
             extensions,
-            text: content,
+            text: Box::new(convert(*content)),
             content: vec![],
         },
         NorgASTFlat::Heading {
@@ -120,6 +120,55 @@ fn convert(flat: NorgASTFlat) -> NorgAST {
     }
 }
 
+/// Returns the object a (possibly nested) carryover tag applies to.
+fn carried_object(object: &NorgASTFlat) -> &NorgASTFlat {
+    match object {
+        NorgASTFlat::CarryoverTag { next_object, .. } => carried_object(next_object),
+        _ => object,
+    }
+}
+
+/// Converts a heading or nestable detached modifier, wrapped in any number of carryover tags,
+/// and gives it the content that was consumed for it.
+fn with_content(object: NorgASTFlat, content: Vec<NorgAST>) -> NorgAST {
+    match object {
+        NorgASTFlat::CarryoverTag {
+            tag_type,
+            name,
+            parameters,
+            next_object,
+        } => NorgAST::CarryoverTag {
+            tag_type,
+            name,
+            parameters,
+            next_object: Box::new(with_content(*next_object, content)),
+        },
+        NorgASTFlat::Heading {
+            level,
+            title,
+            extensions,
+        } => NorgAST::Heading {
+            level,
+            title,
+            extensions,
+            content,
+        },
+        NorgASTFlat::NestableDetachedModifier {
+            modifier_type,
+            level,
+            extensions,
+            content: text,
+        } => NorgAST::NestableDetachedModifier {
+            modifier_type,
+            level,
+            extensions,
+            text: Box::new(convert(*text)),
+            content,
+        },
+        other => convert(other),
+    }
+}
+
 fn consume_heading_content(start_level: &u16, flat: &[NorgASTFlat], i: &mut usize) -> Vec<NorgAST> {
     let mut heading_level = *start_level as i16;
     let mut content = vec![];
@@ -153,9 +202,9 @@ fn consume_heading_content(start_level: &u16, flat: &[NorgASTFlat], i: &mut usiz
                 break;
             }
             NorgASTFlat::CarryoverTag { next_object, .. }
-                if matches!(**next_object, NorgASTFlat::Heading { .. }) =>
+                if matches!(carried_object(next_object), NorgASTFlat::Heading { .. }) =>
             {
-                if let NorgASTFlat::Heading { level, .. } = **next_object {
+                if let NorgASTFlat::Heading { level, .. } = *carried_object(next_object) {
                     if level <= *start_level {
                         // stop.
                         content = stage_4(flat[(*i + 1)..j].to_vec());
@@ -206,9 +255,14 @@ fn consume_nestable_detached_mod_content(
                 }
             }
             NorgASTFlat::CarryoverTag { next_object, .. }
-                if matches!(**next_object, NorgASTFlat::NestableDetachedModifier { .. }) =>
+                if matches!(
+                    carried_object(next_object),
+                    NorgASTFlat::NestableDetachedModifier { .. }
+                ) =>
             {
-                if let NorgASTFlat::NestableDetachedModifier { level, .. } = **next_object {
+                if let NorgASTFlat::NestableDetachedModifier { level, .. } =
+                    *carried_object(next_object)
+                {
                     if level <= *start_level {
                         content = stage_4(flat[(*i + 1)..j].to_vec());
                         *i = j - 1;
@@ -255,57 +309,17 @@ pub fn stage_4(flat: Vec<NorgASTFlat>) -> Vec<NorgAST> {
                     content,
                 })
             }
-            NorgASTFlat::CarryoverTag {
-                tag_type,
-                name,
-                parameters,
-                next_object,
-            } => {
-                match *next_object.clone() {
-                    NorgASTFlat::Heading {
-                        level,
-                        title,
-                        extensions,
-                    } => {
-                        let content = consume_heading_content(&level, &flat, &mut i);
-                        ast.push(NorgAST::CarryoverTag {
-                            tag_type: tag_type.clone(),
-                            name: name.to_vec(),
-                            parameters: parameters.to_vec(),
-                            next_object: Box::new(NorgAST::Heading {
-                                level,
-                                title,
-                                extensions,
-                                content,
-                            }),
-                        })
-                    }
-                    NorgASTFlat::NestableDetachedModifier {
-                        modifier_type,
-                        level,
-                        extensions,
-                        content,
-                    } => {
-                        let new_content =
-                            consume_nestable_detached_mod_content(&level, &flat, &mut i);
-                        ast.push(NorgAST::CarryoverTag {
-                            tag_type: tag_type.clone(),
-                            name: name.to_vec(),
-                            parameters: parameters.to_vec(),
-                            next_object: Box::new(NorgAST::NestableDetachedModifier {
-                                modifier_type,
-                                level,
-                                extensions,
-                                text: content,
-                                content: new_content,
-                            }),
-                        })
-                    }
-                    _ => {
-                        ast.push(convert(item.clone()))
-                    }
+            NorgASTFlat::CarryoverTag { .. } => match *carried_object(item) {
+                NorgASTFlat::Heading { level, .. } => {
+                    let content = consume_heading_content(&level, &flat, &mut i);
+                    ast.push(with_content(item.clone(), content))
                 }
-            }
+                NorgASTFlat::NestableDetachedModifier { level, .. } => {
+                    let content = consume_nestable_detached_mod_content(&level, &flat, &mut i);
+                    ast.push(with_content(item.clone(), content))
+                }
+                _ => ast.push(convert(item.clone())),
+            },
             NorgASTFlat::NestableDetachedModifier {
                 level: start_level,
                 modifier_type,
@@ -318,7 +332,7 @@ pub fn stage_4(flat: Vec<NorgASTFlat>) -> Vec<NorgAST> {
                     modifier_type: modifier_type.clone(),
                     level: *start_level,
                     extensions: extensions.to_vec(),
-                    text: text.clone(),
+                    text: Box::new(convert(*text.clone())),
                     content,
                 });
             }
@@ -0,0 +1,312 @@
+//! Streaming parser which yields top-level [`NorgAST`] nodes while the input is still being
+//! read.
+//!
+//! The input is consumed line by line and split into chunks at boundaries which can never be
+//! part of a larger top-level node: before a level one heading (together with any carryover
+//! tags attached to it) and after a strong delimiting modifier. Each chunk is then run through
+//! the regular parser, so only a single chunk is ever held in memory at a time.
+
+use std::collections::VecDeque;
+use std::io::BufRead;
+
+use chumsky::Parser as _;
+
+use crate::{error::NorgParseError, parse_tree, stage_1, stage_2, stage_2::NorgBlock, NorgAST};
+
+/// Iterator over the lines of a [`BufRead`], keeping the original line terminators intact.
+pub struct RawLines<R> {
+    reader: R,
+}
+
+impl<R: BufRead> Iterator for RawLines<R> {
+    type Item = std::io::Result<String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut line = String::new();
+        match self.reader.read_line(&mut line) {
+            Ok(0) => None,
+            Ok(_) => Some(Ok(line)),
+            Err(err) => Some(Err(err)),
+        }
+    }
+}
+
+/// Keeps track of the ranged constructs that are currently open, as it is never safe to split
+/// the document while inside one of them.
+#[derive(Default)]
+struct ScanState {
+    /// Whether we're inside of an `@tag` verbatim block.
+    verbatim: bool,
+    /// Number of currently open `|tag` and `=tag` blocks. The parser reads any such line as a
+    /// tag, so one that is never closed keeps the rest of the document in a single chunk.
+    ranged_tags: usize,
+    /// Currently open ranged detached modifiers (`$$`, `^^` and `::`).
+    ranged_modifiers: Vec<char>,
+}
+
+impl ScanState {
+    fn at_top_level(&self) -> bool {
+        !self.verbatim && self.ranged_tags == 0 && self.ranged_modifiers.is_empty()
+    }
+
+    /// Updates the state with the given line, which has already been stripped of leading
+    /// whitespace.
+    fn update(&mut self, line: &str) {
+        if matches!(line.chars().next(), Some('|' | '=')) {
+            match ranged_tag(line) {
+                Some(true) if !self.verbatim => self.ranged_tags += 1,
+                Some(false) if !self.verbatim => {
+                    self.ranged_tags = self.ranged_tags.saturating_sub(1);
+                }
+                _ => {}
+            }
+            return;
+        }
+
+        let line = line.trim_end();
+
+        if self.verbatim {
+            if line == "@end" {
+                self.verbatim = false;
+            }
+            return;
+        }
+
+        let mut chars = line.chars();
+        match (chars.next(), chars.next()) {
+            (Some('@'), Some(c)) if !c.is_whitespace() && line != "@end" => self.verbatim = true,
+            (Some(c @ ('$' | '^' | ':')), Some(next)) if next == c => {
+                if line.chars().count() == 2 {
+                    if self.ranged_modifiers.last() == Some(&c) {
+                        self.ranged_modifiers.pop();
+                    }
+                } else if line.chars().nth(2).is_some_and(char::is_whitespace) {
+                    self.ranged_modifiers.push(c);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Returns `Some(true)` if the line opens a `|tag` or `=tag` and `Some(false)` if it closes one,
+/// as decided by the block parser, so that e.g. a strong delimiting modifier isn't counted.
+fn ranged_tag(line: &str) -> Option<bool> {
+    let tokens = stage_1().parse(line).ok()?;
+    match stage_2().parse(tokens).ok()?.first()? {
+        NorgBlock::RangedTag { .. } => Some(true),
+        NorgBlock::RangedTagEnd('|' | '=') => Some(false),
+        _ => None,
+    }
+}
+
+/// Returns `true` if the line opens a level one heading.
+fn is_top_level_heading(line: &str) -> bool {
+    let mut chars = line.chars();
+    chars.next() == Some('*') && chars.next().is_some_and(char::is_whitespace)
+}
+
+/// Returns `true` if the line is a strong delimiting modifier (`===`).
+fn is_strong_delimiter(line: &str) -> bool {
+    let line = line.trim_end();
+    line.len() >= 2 && line.chars().all(|c| c == '=')
+}
+
+/// Returns `true` if the line is a carryover tag (`#tag` or `+tag`).
+fn is_carryover_tag(line: &str) -> bool {
+    let mut chars = line.chars();
+    matches!(chars.next(), Some('#' | '+')) && chars.next().is_some_and(|c| !c.is_whitespace())
+}
+
+/// A streaming parser over lines of Norg source, produced by [`parse_tree_stream`] or
+/// [`NorgStream::from_lines`].
+///
+/// Yields every top-level node as soon as the chunk containing it has been read in full. A
+/// parse error only affects the chunk it occurs in, parsing resumes with the next chunk.
+pub struct NorgStream<I> {
+    lines: I,
+    state: ScanState,
+    /// The chunk currently being collected.
+    chunk: String,
+    /// Carryover tags which will be attached to whatever comes next.
+    pending: String,
+    ready: VecDeque<NorgAST>,
+    done: bool,
+}
+
+impl<I> NorgStream<I>
+where
+    I: Iterator<Item = std::io::Result<String>>,
+{
+    /// Creates a new stream from an iterator over lines. Lines may or may not contain their
+    /// trailing newline.
+    pub fn from_lines(lines: I) -> Self {
+        Self {
+            lines,
+            state: ScanState::default(),
+            chunk: String::new(),
+            pending: String::new(),
+            ready: VecDeque::new(),
+            done: false,
+        }
+    }
+
+    /// Feeds a single line into the current chunk. Returns a finished chunk if this line
+    /// started a new one.
+    fn feed(&mut self, line: &str) -> Option<String> {
+        let trimmed = line.trim_start();
+        let mut finished = None;
+
+        if self.state.at_top_level() {
+            if is_carryover_tag(trimmed) {
+                self.pending.push_str(line);
+                return None;
+            }
+
+            if is_top_level_heading(trimmed) {
+                finished = Some(std::mem::take(&mut self.chunk));
+            }
+        }
+
+        self.chunk.push_str(&std::mem::take(&mut self.pending));
+        self.chunk.push_str(line);
+
+        let top_level = self.state.at_top_level();
+        self.state.update(trimmed);
+
+        if top_level && is_strong_delimiter(trimmed) {
+            return Some(std::mem::take(&mut self.chunk));
+        }
+
+        finished
+    }
+
+    /// Parses a finished chunk and queues up the resulting nodes.
+    fn parse_chunk(&mut self, chunk: String) -> Result<(), NorgParseError> {
+        if chunk.trim().is_empty() {
+            return Ok(());
+        }
+
+        self.ready.extend(parse_tree(&chunk)?);
+        Ok(())
+    }
+}
+
+impl<I> Iterator for NorgStream<I>
+where
+    I: Iterator<Item = std::io::Result<String>>,
+{
+    type Item = Result<NorgAST, NorgParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(node) = self.ready.pop_front() {
+                return Some(Ok(node));
+            }
+
+            if self.done {
+                return None;
+            }
+
+            let chunk = match self.lines.next() {
+                Some(Ok(mut line)) => {
+                    if !line.ends_with('\n') {
+                        line.push('\n');
+                    }
+
+                    match self.feed(&line) {
+                        Some(chunk) => chunk,
+                        None => continue,
+                    }
+                }
+                Some(Err(err)) => {
+                    self.done = true;
+                    return Some(Err(err.into()));
+                }
+                None => {
+                    self.done = true;
+                    self.chunk.push_str(&std::mem::take(&mut self.pending));
+                    std::mem::take(&mut self.chunk)
+                }
+            };
+
+            if let Err(err) = self.parse_chunk(chunk) {
+                return Some(Err(err));
+            }
+        }
+    }
+}
+
+/// Parses Norg from the given reader, yielding top-level nodes as soon as they are complete.
+///
+/// # Arguments
+///
+/// * `reader` - Any buffered reader, e.g. a `BufReader<File>`.
+///
+/// # Returns
+///
+/// * An iterator over `Result<NorgAST, NorgParseError>`. Reading errors are reported once, after
+///   which the iterator ends.
+pub fn parse_tree_stream<R: BufRead>(reader: R) -> NorgStream<RawLines<R>> {
+    NorgStream::from_lines(RawLines { reader })
+}
+
+#[cfg(test)]
+mod tests {
+    use itertools::Itertools;
+
+    use crate::{
+        parse_tree,
+        stream::{parse_tree_stream, ScanState},
+    };
+
+    #[test]
+    fn stream_matches_parse_tree() {
+        [
+            "* One\n  content\n* Two\n** Nested\n   text\n",
+            "#id 123\n* tree\n** nested\n#id there\n* another\n",
+            "* One\n  ===\nafter\n* Two\n",
+            "- list\n-- nested\n* Heading\n- list again\n",
+            "|example\n* Hello world!\n|end\n* After\n",
+            "==macro\n* inside\n=end\n* outside\n",
+            "|=x\n* inside\n|end\n* outside\n",
+            "| spaced\n* A\n===\n* B\n",
+            "@code\n* not a heading\n@end\n* heading\n",
+            "$$ Term\n* inside\n$$\n* outside\n",
+            "paragraph\nspanning lines\n\n* Heading\nparagraph\n",
+            "* (x) done\n* ( ) undone\n",
+        ]
+        .into_iter()
+        .for_each(|input| {
+            let streamed: Vec<_> = parse_tree_stream(input.as_bytes()).try_collect().unwrap();
+            assert_eq!(streamed, parse_tree(input).unwrap(), "input: {input:?}");
+        });
+    }
+
+    #[test]
+    fn ranged_tags() {
+        let mut state = ScanState::default();
+        for line in ["| not a tag\n", "===\n", "=\n", "|\n"] {
+            state.update(line);
+            assert!(state.at_top_level(), "line: {line:?}");
+        }
+
+        state.update("==macro\n");
+        state.update("@code\n");
+        state.update("|end\n");
+        state.update("@end\n");
+        assert_eq!(state.ranged_tags, 1);
+        state.update("=end\n");
+        assert!(state.at_top_level());
+    }
+
+    #[test]
+    fn stream_recovers_after_error() {
+        let input = "- - invalid\n* Two\n";
+        let results: Vec<_> = parse_tree_stream(input.as_bytes()).collect();
+
+        assert_eq!(results.len(), 2);
+        assert!(results[0].is_err());
+        assert!(results[1].is_ok());
+    }
+}
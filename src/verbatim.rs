@@ -0,0 +1,172 @@
+//! Byte-exact access to the content of verbatim ranged tags.
+//!
+//! The AST only has the content of a verbatim ranged tag after it was run through the lexer
+//! (which turns every line ending into `\n` and tabs into spaces) and dedented. That's fine for
+//! rendering, but code that gets tangled into files has to stay exactly as it was written.
+
+use std::ops::Range;
+
+use crate::{
+    cst::{parse_cst, SyntaxElement, SyntaxKind, SyntaxNode},
+    error::NorgParseError,
+};
+
+/// A verbatim ranged tag like `@code`, with its content exactly as it appears in the source.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VerbatimBlock<'a> {
+    /// The name of the tag, split at `.`.
+    pub name: Vec<String>,
+    pub parameters: Vec<String>,
+    /// The source of the lines between the tag and its `@end`, including their line endings.
+    pub raw: &'a str,
+    /// The range of bytes `raw` was taken from.
+    pub range: Range<usize>,
+    /// The column of the `@` that opens the tag, in characters.
+    pub indentation: usize,
+}
+
+impl VerbatimBlock<'_> {
+    /// Returns the content with the indentation of the tag removed from every line.
+    ///
+    /// As per the spec, indentation is relative to the column of the `@`: up to
+    /// [`indentation`](Self::indentation) whitespace characters are removed from the start of
+    /// each line, and anything beyond that is kept. Tabs count as a single character. Line
+    /// endings are left exactly as they are.
+    pub fn content(&self) -> String {
+        split_lines(self.raw)
+            .map(|line| {
+                let indentation: usize = line
+                    .chars()
+                    .take(self.indentation)
+                    .take_while(|c| is_indentation(*c))
+                    .map(char::len_utf8)
+                    .sum();
+                &line[indentation..]
+            })
+            .collect()
+    }
+}
+
+fn is_line_break(c: char) -> bool {
+    matches!(c, '\n' | '\r' | '\u{2028}' | '\u{2029}')
+}
+
+fn is_indentation(c: char) -> bool {
+    c.is_whitespace() && !is_line_break(c)
+}
+
+/// Splits text into lines, keeping the line endings (`\r\n` counts as one).
+fn split_lines(text: &str) -> impl Iterator<Item = &str> {
+    let mut rest = text;
+    std::iter::from_fn(move || {
+        if rest.is_empty() {
+            return None;
+        }
+
+        let end = match rest.char_indices().find(|(_, c)| is_line_break(*c)) {
+            Some((i, '\r')) if rest[i + 1..].starts_with('\n') => i + 2,
+            Some((i, c)) => i + c.len_utf8(),
+            None => rest.len(),
+        };
+        let (line, tail) = rest.split_at(end);
+        rest = tail;
+        Some(line)
+    })
+}
+
+/// Reads the name and parameters from the line of the tag, e.g. `@code lua`.
+fn header(node: &SyntaxNode) -> (Vec<String>, Vec<String>) {
+    let mut words = vec![String::new()];
+    for token in node
+        .children_with_tokens()
+        .skip(1)
+        .map_while(SyntaxElement::into_token)
+        .take_while(|token| token.kind() != SyntaxKind::Newline)
+    {
+        match token.kind() {
+            SyntaxKind::Whitespace => words.push(String::new()),
+            _ => words.last_mut().unwrap().push_str(token.text()),
+        }
+    }
+
+    let mut words = words.into_iter().filter(|word| !word.is_empty());
+    let name = words
+        .next()
+        .map(|name| name.split('.').map(String::from).collect())
+        .unwrap_or_default();
+
+    (name, words.collect())
+}
+
+/// Finds all verbatim ranged tags in a document, in document order.
+///
+/// # Arguments
+///
+/// * `source` - The source of the document.
+///
+/// # Returns
+///
+/// * `Ok(Vec<VerbatimBlock>)` with the tags, borrowing their content from `source`.
+/// * `Err(NorgParseError)` if the document can't be parsed.
+pub fn verbatim_blocks(source: &str) -> Result<Vec<VerbatimBlock<'_>>, NorgParseError> {
+    let cst = parse_cst(source)?;
+
+    Ok(cst
+        .descendants()
+        .filter(|node| node.kind() == SyntaxKind::VerbatimRangedTag)
+        .filter_map(|node| {
+            let content = node
+                .children()
+                .find(|child| child.kind() == SyntaxKind::VerbatimContent)?;
+            let range = content.text_range();
+            let start = node.text_range().start;
+            let line_start = source[..start]
+                .rfind(is_line_break)
+                .map_or(0, |i| i + source[i..].chars().next().unwrap().len_utf8());
+            let (name, parameters) = header(&node);
+
+            Some(VerbatimBlock {
+                name,
+                parameters,
+                raw: &source[range.clone()],
+                range,
+                indentation: source[line_start..start].chars().count(),
+            })
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::verbatim::verbatim_blocks;
+
+    #[test]
+    fn byte_exact() {
+        let source = "* Build\n  @code make\n  all:\n  \tcc -o main main.c\n\n  @end\n";
+        let blocks = verbatim_blocks(source).unwrap();
+
+        assert_eq!(blocks.len(), 1);
+        assert_eq!(blocks[0].name, vec!["code"]);
+        assert_eq!(blocks[0].parameters, vec!["make"]);
+        assert_eq!(blocks[0].raw, "  all:\n  \tcc -o main main.c\n\n");
+        assert_eq!(&source[blocks[0].range.clone()], blocks[0].raw);
+        assert_eq!(blocks[0].indentation, 2);
+        assert_eq!(blocks[0].content(), "all:\n\tcc -o main main.c\n\n");
+    }
+
+    #[test]
+    fn relative_to_tag_column() {
+        let source = "@code.some-text.here python\\ 3 second\r\n    def f():\r\n\treturn 1\r\n@end\r\n\n    @diff\n   - a\n    + b\n      c\n    @end";
+        let blocks = verbatim_blocks(source).unwrap();
+
+        assert_eq!(blocks[0].name, vec!["code", "some-text", "here"]);
+        assert_eq!(blocks[0].parameters, vec!["python\\ 3", "second"]);
+        assert_eq!(blocks[0].indentation, 0);
+        // Nothing is removed relative to column 0, unlike the dedented AST content.
+        assert_eq!(blocks[0].content(), "    def f():\r\n\treturn 1\r\n");
+
+        assert_eq!(blocks[1].indentation, 4);
+        // Lines with less indentation than the tag lose what they have.
+        assert_eq!(blocks[1].content(), "- a\n+ b\n  c\n");
+    }
+}
@@ -0,0 +1,772 @@
+//! Traversal of the syntax tree.
+//!
+//! [`Visitor`] and [`VisitorMut`] walk every node, inline segment, link target and extension in
+//! a [`NorgAST`] (or [`NorgASTFlat`]). Every method has a default implementation which simply
+//! keeps on walking, so implementors only override the methods they care about. When
+//! overriding a method, call the matching `walk_*` function to continue into the children.
+//!
+//! [`Fold`] consumes the tree instead and rebuilds it bottom up, which makes it the right tool
+//! for transformations that replace or remove nodes.
+
+use crate::{
+    DetachedModifierExtension, LinkTarget, NorgAST, NorgASTFlat, ParagraphSegment,
+    ParagraphSegmentToken,
+};
+
+/// Immutable traversal over a syntax tree.
+pub trait Visitor {
+    fn visit_node(&mut self, node: &NorgAST) {
+        walk_node(self, node)
+    }
+
+    fn visit_flat_node(&mut self, node: &NorgASTFlat) {
+        walk_flat_node(self, node)
+    }
+
+    /// Called for every list of inline segments: paragraphs, titles, link descriptions and
+    /// the content of attached modifiers.
+    fn visit_segments(&mut self, segments: &[ParagraphSegment]) {
+        walk_segments(self, segments)
+    }
+
+    fn visit_segment(&mut self, segment: &ParagraphSegment) {
+        walk_segment(self, segment)
+    }
+
+    fn visit_link_target(&mut self, target: &LinkTarget) {
+        walk_link_target(self, target)
+    }
+
+    fn visit_extension(&mut self, _extension: &DetachedModifierExtension) {}
+
+    fn visit_token(&mut self, _token: &ParagraphSegmentToken) {}
+}
+
+pub fn walk_node<V: Visitor + ?Sized>(visitor: &mut V, node: &NorgAST) {
+    match node {
+        NorgAST::Paragraph(segments) => visitor.visit_segments(segments),
+        NorgAST::NestableDetachedModifier {
+            extensions,
+            text,
+            content,
+            ..
+        } => {
+            extensions
+                .iter()
+                .for_each(|ext| visitor.visit_extension(ext));
+            visitor.visit_node(text);
+            content.iter().for_each(|node| visitor.visit_node(node));
+        }
+        NorgAST::RangeableDetachedModifier {
+            title,
+            extensions,
+            content,
+            ..
+        } => {
+            visitor.visit_segments(title);
+            extensions
+                .iter()
+                .for_each(|ext| visitor.visit_extension(ext));
+            content.iter().for_each(|node| visitor.visit_node(node));
+        }
+        NorgAST::Heading {
+            title,
+            extensions,
+            content,
+            ..
+        } => {
+            visitor.visit_segments(title);
+            extensions
+                .iter()
+                .for_each(|ext| visitor.visit_extension(ext));
+            content.iter().for_each(|node| visitor.visit_node(node));
+        }
+        NorgAST::CarryoverTag { next_object, .. } => visitor.visit_node(next_object),
+        NorgAST::RangedTag { content, .. } => {
+            content.iter().for_each(|node| visitor.visit_node(node))
+        }
+        NorgAST::VerbatimRangedTag { .. }
+        | NorgAST::InfirmTag { .. }
+        | NorgAST::DelimitingModifier(_) => {}
+    }
+}
+
+pub fn walk_flat_node<V: Visitor + ?Sized>(visitor: &mut V, node: &NorgASTFlat) {
+    match node {
+        NorgASTFlat::Paragraph(segments) => visitor.visit_segments(segments),
+        NorgASTFlat::NestableDetachedModifier {
+            extensions,
+            content,
+            ..
+        } => {
+            extensions
+                .iter()
+                .for_each(|ext| visitor.visit_extension(ext));
+            visitor.visit_flat_node(content);
+        }
+        NorgASTFlat::RangeableDetachedModifier {
+            title,
+            extensions,
+            content,
+            ..
+        } => {
+            visitor.visit_segments(title);
+            extensions
+                .iter()
+                .for_each(|ext| visitor.visit_extension(ext));
+            content
+                .iter()
+                .for_each(|node| visitor.visit_flat_node(node));
+        }
+        NorgASTFlat::Heading {
+            title, extensions, ..
+        } => {
+            visitor.visit_segments(title);
+            extensions
+                .iter()
+                .for_each(|ext| visitor.visit_extension(ext));
+        }
+        NorgASTFlat::CarryoverTag { next_object, .. } => visitor.visit_flat_node(next_object),
+        NorgASTFlat::RangedTag { content, .. } => content
+            .iter()
+            .for_each(|node| visitor.visit_flat_node(node)),
+        NorgASTFlat::VerbatimRangedTag { .. }
+        | NorgASTFlat::InfirmTag { .. }
+        | NorgASTFlat::DelimitingModifier(_) => {}
+    }
+}
+
+pub fn walk_segments<V: Visitor + ?Sized>(visitor: &mut V, segments: &[ParagraphSegment]) {
+    segments
+        .iter()
+        .for_each(|segment| visitor.visit_segment(segment));
+}
+
+pub fn walk_segment<V: Visitor + ?Sized>(visitor: &mut V, segment: &ParagraphSegment) {
+    match segment {
+        ParagraphSegment::Token(token) => visitor.visit_token(token),
+        ParagraphSegment::AttachedModifierOpener((left, _, right))
+        | ParagraphSegment::AttachedModifierOpenerFail((left, _, right)) => {
+            if let Some(left) = left {
+                visitor.visit_token(left);
+            }
+            visitor.visit_token(right);
+        }
+        ParagraphSegment::AttachedModifierCloserCandidate((left, _, right)) => {
+            visitor.visit_segment(left);
+            if let Some(right) = right {
+                visitor.visit_segment(right);
+            }
+        }
+        ParagraphSegment::AttachedModifierCloser(_) => {}
+        ParagraphSegment::AttachedModifierCandidate {
+            content, closer, ..
+        } => {
+            visitor.visit_segments(content);
+            if let Some(closer) = closer {
+                visitor.visit_segment(closer);
+            }
+        }
+        ParagraphSegment::AttachedModifier { content, .. } => visitor.visit_segments(content),
+        ParagraphSegment::Link {
+            targets,
+            description,
+            ..
+        } => {
+            targets
+                .iter()
+                .for_each(|target| visitor.visit_link_target(target));
+            if let Some(description) = description {
+                visitor.visit_segments(description);
+            }
+        }
+        ParagraphSegment::AnchorDefinition { content, target } => {
+            visitor.visit_segments(content);
+            visitor.visit_segment(target);
+        }
+        ParagraphSegment::Anchor {
+            content,
+            description,
+        } => {
+            visitor.visit_segments(content);
+            if let Some(description) = description {
+                visitor.visit_segments(description);
+            }
+        }
+        ParagraphSegment::InlineLinkTarget(content) => visitor.visit_segments(content),
+        ParagraphSegment::InlineVerbatim(tokens) | ParagraphSegment::InlineMath(tokens) => {
+            tokens.iter().for_each(|token| visitor.visit_token(token))
+        }
+    }
+}
+
+pub fn walk_link_target<V: Visitor + ?Sized>(visitor: &mut V, target: &LinkTarget) {
+    match target {
+        LinkTarget::Heading { title: content, .. }
+        | LinkTarget::Footnote(content)
+        | LinkTarget::Definition(content)
+        | LinkTarget::Generic(content)
+        | LinkTarget::Wiki(content)
+        | LinkTarget::Extendable(content) => visitor.visit_segments(content),
+        LinkTarget::Path(_) | LinkTarget::Url(_) | LinkTarget::Timestamp(_) => {}
+    }
+}
+
+/// Mutable traversal over a syntax tree, for in-place edits that keep the shape of the tree.
+pub trait VisitorMut {
+    fn visit_node_mut(&mut self, node: &mut NorgAST) {
+        walk_node_mut(self, node)
+    }
+
+    fn visit_flat_node_mut(&mut self, node: &mut NorgASTFlat) {
+        walk_flat_node_mut(self, node)
+    }
+
+    /// Called for every list of inline segments: paragraphs, titles, link descriptions and
+    /// the content of attached modifiers.
+    fn visit_segments_mut(&mut self, segments: &mut Vec<ParagraphSegment>) {
+        walk_segments_mut(self, segments)
+    }
+
+    fn visit_segment_mut(&mut self, segment: &mut ParagraphSegment) {
+        walk_segment_mut(self, segment)
+    }
+
+    fn visit_link_target_mut(&mut self, target: &mut LinkTarget) {
+        walk_link_target_mut(self, target)
+    }
+
+    fn visit_extension_mut(&mut self, _extension: &mut DetachedModifierExtension) {}
+
+    fn visit_token_mut(&mut self, _token: &mut ParagraphSegmentToken) {}
+}
+
+pub fn walk_node_mut<V: VisitorMut + ?Sized>(visitor: &mut V, node: &mut NorgAST) {
+    match node {
+        NorgAST::Paragraph(segments) => visitor.visit_segments_mut(segments),
+        NorgAST::NestableDetachedModifier {
+            extensions,
+            text,
+            content,
+            ..
+        } => {
+            extensions
+                .iter_mut()
+                .for_each(|ext| visitor.visit_extension_mut(ext));
+            visitor.visit_node_mut(text);
+            content
+                .iter_mut()
+                .for_each(|node| visitor.visit_node_mut(node));
+        }
+        NorgAST::RangeableDetachedModifier {
+            title,
+            extensions,
+            content,
+            ..
+        } => {
+            visitor.visit_segments_mut(title);
+            extensions
+                .iter_mut()
+                .for_each(|ext| visitor.visit_extension_mut(ext));
+            content
+                .iter_mut()
+                .for_each(|node| visitor.visit_node_mut(node));
+        }
+        NorgAST::Heading {
+            title,
+            extensions,
+            content,
+            ..
+        } => {
+            visitor.visit_segments_mut(title);
+            extensions
+                .iter_mut()
+                .for_each(|ext| visitor.visit_extension_mut(ext));
+            content
+                .iter_mut()
+                .for_each(|node| visitor.visit_node_mut(node));
+        }
+        NorgAST::CarryoverTag { next_object, .. } => visitor.visit_node_mut(next_object),
+        NorgAST::RangedTag { content, .. } => content
+            .iter_mut()
+            .for_each(|node| visitor.visit_node_mut(node)),
+        NorgAST::VerbatimRangedTag { .. }
+        | NorgAST::InfirmTag { .. }
+        | NorgAST::DelimitingModifier(_) => {}
+    }
+}
+
+pub fn walk_flat_node_mut<V: VisitorMut + ?Sized>(visitor: &mut V, node: &mut NorgASTFlat) {
+    match node {
+        NorgASTFlat::Paragraph(segments) => visitor.visit_segments_mut(segments),
+        NorgASTFlat::NestableDetachedModifier {
+            extensions,
+            content,
+            ..
+        } => {
+            extensions
+                .iter_mut()
+                .for_each(|ext| visitor.visit_extension_mut(ext));
+            visitor.visit_flat_node_mut(content);
+        }
+        NorgASTFlat::RangeableDetachedModifier {
+            title,
+            extensions,
+            content,
+            ..
+        } => {
+            visitor.visit_segments_mut(title);
+            extensions
+                .iter_mut()
+                .for_each(|ext| visitor.visit_extension_mut(ext));
+            content
+                .iter_mut()
+                .for_each(|node| visitor.visit_flat_node_mut(node));
+        }
+        NorgASTFlat::Heading {
+            title, extensions, ..
+        } => {
+            visitor.visit_segments_mut(title);
+            extensions
+                .iter_mut()
+                .for_each(|ext| visitor.visit_extension_mut(ext));
+        }
+        NorgASTFlat::CarryoverTag { next_object, .. } => visitor.visit_flat_node_mut(next_object),
+        NorgASTFlat::RangedTag { content, .. } => content
+            .iter_mut()
+            .for_each(|node| visitor.visit_flat_node_mut(node)),
+        NorgASTFlat::VerbatimRangedTag { .. }
+        | NorgASTFlat::InfirmTag { .. }
+        | NorgASTFlat::DelimitingModifier(_) => {}
+    }
+}
+
+pub fn walk_segments_mut<V: VisitorMut + ?Sized>(
+    visitor: &mut V,
+    segments: &mut [ParagraphSegment],
+) {
+    segments
+        .iter_mut()
+        .for_each(|segment| visitor.visit_segment_mut(segment));
+}
+
+pub fn walk_segment_mut<V: VisitorMut + ?Sized>(visitor: &mut V, segment: &mut ParagraphSegment) {
+    match segment {
+        ParagraphSegment::Token(token) => visitor.visit_token_mut(token),
+        ParagraphSegment::AttachedModifierOpener((left, _, right))
+        | ParagraphSegment::AttachedModifierOpenerFail((left, _, right)) => {
+            if let Some(left) = left {
+                visitor.visit_token_mut(left);
+            }
+            visitor.visit_token_mut(right);
+        }
+        ParagraphSegment::AttachedModifierCloserCandidate((left, _, right)) => {
+            visitor.visit_segment_mut(left);
+            if let Some(right) = right {
+                visitor.visit_segment_mut(right);
+            }
+        }
+        ParagraphSegment::AttachedModifierCloser(_) => {}
+        ParagraphSegment::AttachedModifierCandidate {
+            content, closer, ..
+        } => {
+            visitor.visit_segments_mut(content);
+            if let Some(closer) = closer {
+                visitor.visit_segment_mut(closer);
+            }
+        }
+        ParagraphSegment::AttachedModifier { content, .. } => visitor.visit_segments_mut(content),
+        ParagraphSegment::Link {
+            targets,
+            description,
+            ..
+        } => {
+            targets
+                .iter_mut()
+                .for_each(|target| visitor.visit_link_target_mut(target));
+            if let Some(description) = description {
+                visitor.visit_segments_mut(description);
+            }
+        }
+        ParagraphSegment::AnchorDefinition { content, target } => {
+            visitor.visit_segments_mut(content);
+            visitor.visit_segment_mut(target);
+        }
+        ParagraphSegment::Anchor {
+            content,
+            description,
+        } => {
+            visitor.visit_segments_mut(content);
+            if let Some(description) = description {
+                visitor.visit_segments_mut(description);
+            }
+        }
+        ParagraphSegment::InlineLinkTarget(content) => visitor.visit_segments_mut(content),
+        ParagraphSegment::InlineVerbatim(tokens) | ParagraphSegment::InlineMath(tokens) => tokens
+            .iter_mut()
+            .for_each(|token| visitor.visit_token_mut(token)),
+    }
+}
+
+pub fn walk_link_target_mut<V: VisitorMut + ?Sized>(visitor: &mut V, target: &mut LinkTarget) {
+    match target {
+        LinkTarget::Heading { title: content, .. }
+        | LinkTarget::Footnote(content)
+        | LinkTarget::Definition(content)
+        | LinkTarget::Generic(content)
+        | LinkTarget::Wiki(content)
+        | LinkTarget::Extendable(content) => visitor.visit_segments_mut(content),
+        LinkTarget::Path(_) | LinkTarget::Url(_) | LinkTarget::Timestamp(_) => {}
+    }
+}
+
+/// Consuming transformation of a syntax tree.
+///
+/// Every method takes ownership of a part of the tree and returns its replacement. Lists of
+/// nodes and segments go through [`Fold::fold_nodes`] and [`Fold::fold_segments`], which may
+/// also add or remove entries.
+pub trait Fold {
+    fn fold_nodes(&mut self, nodes: Vec<NorgAST>) -> Vec<NorgAST> {
+        nodes.into_iter().map(|node| self.fold_node(node)).collect()
+    }
+
+    fn fold_node(&mut self, node: NorgAST) -> NorgAST {
+        fold_node(self, node)
+    }
+
+    fn fold_flat_nodes(&mut self, nodes: Vec<NorgASTFlat>) -> Vec<NorgASTFlat> {
+        nodes
+            .into_iter()
+            .map(|node| self.fold_flat_node(node))
+            .collect()
+    }
+
+    fn fold_flat_node(&mut self, node: NorgASTFlat) -> NorgASTFlat {
+        fold_flat_node(self, node)
+    }
+
+    fn fold_segments(&mut self, segments: Vec<ParagraphSegment>) -> Vec<ParagraphSegment> {
+        segments
+            .into_iter()
+            .map(|segment| self.fold_segment(segment))
+            .collect()
+    }
+
+    fn fold_segment(&mut self, segment: ParagraphSegment) -> ParagraphSegment {
+        fold_segment(self, segment)
+    }
+
+    fn fold_link_target(&mut self, target: LinkTarget) -> LinkTarget {
+        fold_link_target(self, target)
+    }
+
+    fn fold_extensions(
+        &mut self,
+        extensions: Vec<DetachedModifierExtension>,
+    ) -> Vec<DetachedModifierExtension> {
+        extensions
+    }
+}
+
+pub fn fold_node<F: Fold + ?Sized>(folder: &mut F, node: NorgAST) -> NorgAST {
+    match node {
+        NorgAST::Paragraph(segments) => NorgAST::Paragraph(folder.fold_segments(segments)),
+        NorgAST::NestableDetachedModifier {
+            modifier_type,
+            level,
+            extensions,
+            text,
+            content,
+        } => NorgAST::NestableDetachedModifier {
+            modifier_type,
+            level,
+            extensions: folder.fold_extensions(extensions),
+            text: Box::new(folder.fold_node(*text)),
+            content: folder.fold_nodes(content),
+        },
+        NorgAST::RangeableDetachedModifier {
+            modifier_type,
+            title,
+            extensions,
+            content,
+        } => NorgAST::RangeableDetachedModifier {
+            modifier_type,
+            title: folder.fold_segments(title),
+            extensions: folder.fold_extensions(extensions),
+            content: folder.fold_nodes(content),
+        },
+        NorgAST::Heading {
+            level,
+            title,
+            extensions,
+            content,
+        } => NorgAST::Heading {
+            level,
+            title: folder.fold_segments(title),
+            extensions: folder.fold_extensions(extensions),
+            content: folder.fold_nodes(content),
+        },
+        NorgAST::CarryoverTag {
+            tag_type,
+            name,
+            parameters,
+            next_object,
+        } => NorgAST::CarryoverTag {
+            tag_type,
+            name,
+            parameters,
+            next_object: Box::new(folder.fold_node(*next_object)),
+        },
+        NorgAST::RangedTag {
+            name,
+            parameters,
+            content,
+        } => NorgAST::RangedTag {
+            name,
+            parameters,
+            content: folder.fold_nodes(content),
+        },
+        node @ (NorgAST::VerbatimRangedTag { .. }
+        | NorgAST::InfirmTag { .. }
+        | NorgAST::DelimitingModifier(_)) => node,
+    }
+}
+
+pub fn fold_flat_node<F: Fold + ?Sized>(folder: &mut F, node: NorgASTFlat) -> NorgASTFlat {
+    match node {
+        NorgASTFlat::Paragraph(segments) => NorgASTFlat::Paragraph(folder.fold_segments(segments)),
+        NorgASTFlat::NestableDetachedModifier {
+            modifier_type,
+            level,
+            extensions,
+            content,
+        } => NorgASTFlat::NestableDetachedModifier {
+            modifier_type,
+            level,
+            extensions: folder.fold_extensions(extensions),
+            content: Box::new(folder.fold_flat_node(*content)),
+        },
+        NorgASTFlat::RangeableDetachedModifier {
+            modifier_type,
+            title,
+            extensions,
+            content,
+        } => NorgASTFlat::RangeableDetachedModifier {
+            modifier_type,
+            title: folder.fold_segments(title),
+            extensions: folder.fold_extensions(extensions),
+            content: folder.fold_flat_nodes(content),
+        },
+        NorgASTFlat::Heading {
+            level,
+            title,
+            extensions,
+        } => NorgASTFlat::Heading {
+            level,
+            title: folder.fold_segments(title),
+            extensions: folder.fold_extensions(extensions),
+        },
+        NorgASTFlat::CarryoverTag {
+            tag_type,
+            name,
+            parameters,
+            next_object,
+        } => NorgASTFlat::CarryoverTag {
+            tag_type,
+            name,
+            parameters,
+            next_object: Box::new(folder.fold_flat_node(*next_object)),
+        },
+        NorgASTFlat::RangedTag {
+            name,
+            parameters,
+            content,
+        } => NorgASTFlat::RangedTag {
+            name,
+            parameters,
+            content: folder.fold_flat_nodes(content),
+        },
+        node @ (NorgASTFlat::VerbatimRangedTag { .. }
+        | NorgASTFlat::InfirmTag { .. }
+        | NorgASTFlat::DelimitingModifier(_)) => node,
+    }
+}
+
+pub fn fold_segment<F: Fold + ?Sized>(
+    folder: &mut F,
+    segment: ParagraphSegment,
+) -> ParagraphSegment {
+    match segment {
+        ParagraphSegment::AttachedModifierCandidate {
+            modifier_type,
+            content,
+            closer,
+        } => ParagraphSegment::AttachedModifierCandidate {
+            modifier_type,
+            content: folder.fold_segments(content),
+            closer: closer.map(|closer| Box::new(folder.fold_segment(*closer))),
+        },
+        ParagraphSegment::AttachedModifierCloserCandidate((left, modifiers, right)) => {
+            ParagraphSegment::AttachedModifierCloserCandidate((
+                Box::new(folder.fold_segment(*left)),
+                modifiers,
+                right.map(|right| Box::new(folder.fold_segment(*right))),
+            ))
+        }
+        ParagraphSegment::AttachedModifier {
+            modifier_type,
+            content,
+        } => ParagraphSegment::AttachedModifier {
+            modifier_type,
+            content: folder.fold_segments(content),
+        },
+        ParagraphSegment::Link {
+            filepath,
+            targets,
+            description,
+        } => ParagraphSegment::Link {
+            filepath,
+            targets: targets
+                .into_iter()
+                .map(|target| folder.fold_link_target(target))
+                .collect(),
+            description: description.map(|description| folder.fold_segments(description)),
+        },
+        ParagraphSegment::AnchorDefinition { content, target } => {
+            ParagraphSegment::AnchorDefinition {
+                content: folder.fold_segments(content),
+                target: Box::new(folder.fold_segment(*target)),
+            }
+        }
+        ParagraphSegment::Anchor {
+            content,
+            description,
+        } => ParagraphSegment::Anchor {
+            content: folder.fold_segments(content),
+            description: description.map(|description| folder.fold_segments(description)),
+        },
+        ParagraphSegment::InlineLinkTarget(content) => {
+            ParagraphSegment::InlineLinkTarget(folder.fold_segments(content))
+        }
+        segment @ (ParagraphSegment::Token(_)
+        | ParagraphSegment::AttachedModifierOpener(_)
+        | ParagraphSegment::AttachedModifierOpenerFail(_)
+        | ParagraphSegment::AttachedModifierCloser(_)
+        | ParagraphSegment::InlineVerbatim(_)
+        | ParagraphSegment::InlineMath(_)) => segment,
+    }
+}
+
+pub fn fold_link_target<F: Fold + ?Sized>(folder: &mut F, target: LinkTarget) -> LinkTarget {
+    match target {
+        LinkTarget::Heading { level, title } => LinkTarget::Heading {
+            level,
+            title: folder.fold_segments(title),
+        },
+        LinkTarget::Footnote(content) => LinkTarget::Footnote(folder.fold_segments(content)),
+        LinkTarget::Definition(content) => LinkTarget::Definition(folder.fold_segments(content)),
+        LinkTarget::Generic(content) => LinkTarget::Generic(folder.fold_segments(content)),
+        LinkTarget::Wiki(content) => LinkTarget::Wiki(folder.fold_segments(content)),
+        LinkTarget::Extendable(content) => LinkTarget::Extendable(folder.fold_segments(content)),
+        target @ (LinkTarget::Path(_) | LinkTarget::Url(_) | LinkTarget::Timestamp(_)) => target,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        parse_tree,
+        visit::{Fold, Visitor, VisitorMut},
+        DelimitingModifier, LinkTarget, NorgAST, ParagraphSegmentToken,
+    };
+
+    const INPUT: &str = "* Heading with *bold* text
+  A paragraph with a {https://example.com}[link].
+  - list item
+  -- nested *item*
+  |example
+  ranged tag content
+  |end
+  ---
+===
+after
+";
+
+    #[test]
+    fn visitor_counts_words() {
+        #[derive(Default)]
+        struct WordCount {
+            words: usize,
+            urls: Vec<String>,
+        }
+
+        impl Visitor for WordCount {
+            fn visit_token(&mut self, token: &ParagraphSegmentToken) {
+                if let ParagraphSegmentToken::Text(_) = token {
+                    self.words += 1;
+                }
+            }
+
+            fn visit_link_target(&mut self, target: &LinkTarget) {
+                if let LinkTarget::Url(url) = target {
+                    self.urls.push(url.clone());
+                }
+            }
+        }
+
+        let mut counter = WordCount::default();
+        parse_tree(INPUT)
+            .unwrap()
+            .iter()
+            .for_each(|node| counter.visit_node(node));
+
+        assert_eq!(counter.words, 17);
+        assert_eq!(counter.urls, ["https://example.com"]);
+    }
+
+    #[test]
+    fn visitor_mut_edits_in_place() {
+        struct Uppercase;
+
+        impl VisitorMut for Uppercase {
+            fn visit_token_mut(&mut self, token: &mut ParagraphSegmentToken) {
+                if let ParagraphSegmentToken::Text(text) = token {
+                    *text = text.to_uppercase();
+                }
+            }
+        }
+
+        let mut ast = parse_tree("* heading\n  content\n").unwrap();
+        ast.iter_mut()
+            .for_each(|node| Uppercase.visit_node_mut(node));
+
+        assert_eq!(ast, parse_tree("* HEADING\n  CONTENT\n").unwrap());
+    }
+
+    #[test]
+    fn fold_removes_nodes() {
+        struct StripDelimiters;
+
+        impl Fold for StripDelimiters {
+            fn fold_nodes(&mut self, nodes: Vec<NorgAST>) -> Vec<NorgAST> {
+                nodes
+                    .into_iter()
+                    .filter(|node| {
+                        !matches!(
+                            node,
+                            NorgAST::DelimitingModifier(DelimitingModifier::HorizontalRule)
+                        )
+                    })
+                    .map(|node| self.fold_node(node))
+                    .collect()
+            }
+        }
+
+        let ast = parse_tree("* heading\n  one\n  ___\n  two\n").unwrap();
+
+        assert_eq!(
+            StripDelimiters.fold_nodes(ast),
+            parse_tree("* heading\n  one\n\n  two\n").unwrap()
+        );
+    }
+}